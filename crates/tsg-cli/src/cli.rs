@@ -1,26 +1,47 @@
+mod analyze;
+mod bed;
+mod convert;
 mod dot;
+mod events;
 mod fa;
+mod gfa;
+mod gff;
 mod gtf;
+mod index;
 mod json;
 mod merge;
+mod parse;
 mod path;
+mod plot;
 mod query;
+mod sites;
 mod split;
 mod vcf;
 
+pub use analyze::*;
+pub use bed::*;
+pub use convert::*;
 pub use dot::*;
+pub use events::*;
 pub use fa::*;
+pub use gfa::*;
+pub use gff::*;
 pub use gtf::*;
+pub use index::*;
 pub use json::*;
 pub use merge::*;
+pub use parse::*;
 pub use path::*;
+pub use plot::*;
 pub use query::*;
+pub use sites::*;
 pub use split::*;
 pub use vcf::*;
 
 use clap::Subcommand;
 use clap::ValueHint;
 use std::path::PathBuf;
+use tsg::graph::OrderingPolicy;
 
 /// Command line interface for the TSG tool
 #[derive(Subcommand)]
@@ -30,6 +51,10 @@ pub enum Commands {
         /// Input TSG file path
         #[arg(required = true, value_hint = ValueHint::FilePath)]
         input: PathBuf,
+
+        /// Skip malformed records and report them instead of stopping at the first one
+        #[arg(long)]
+        lenient: bool,
     },
 
     /// Convert a TSG file to FASTA format
@@ -41,6 +66,10 @@ pub enum Commands {
         /// Output file path for the FASTA
         #[arg(short, long, value_hint = ValueHint::FilePath)]
         output: Option<PathBuf>,
+
+        /// Order of graphs, nodes, edges and paths in the output
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted)]
+        ordering: OrderingPolicy,
     },
 
     /// Convert a TSG file to GTF format
@@ -52,15 +81,111 @@ pub enum Commands {
         /// Output file path for the GTF
         #[arg(short, long, value_hint = ValueHint::FilePath)]
         output: Option<PathBuf>,
+
+        /// Order of graphs, nodes, edges and paths in the output
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted)]
+        ordering: OrderingPolicy,
+    },
+
+    /// Convert a TSG file to GFF3 format with gene, mRNA and exon features
+    Gff3 {
+        /// Input TSG file path
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+
+        /// Output file path for the GFF3
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+
+        /// Order of graphs, nodes, edges and paths in the output
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted)]
+        ordering: OrderingPolicy,
+    },
+
+    /// Build a reference TSG file from a GTF or GFF3 annotation, one graph per gene
+    FromGtf {
+        /// Input GTF or GFF3 file path
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+
+        /// Output file path for the TSG, default is stdout
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+
+        /// Order of graphs, nodes, edges and paths in the output
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted)]
+        ordering: OrderingPolicy,
     },
 
-    /// Convert a TSG file to VCF format
+    /// Convert a TSG file to BED12 (paths, nodes) or BED6 (edges) format
+    Bed {
+        /// Input TSG file path
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+
+        /// Write one BED12 record per traversed path [default]
+        #[arg(long, group = "mode")]
+        paths: bool,
+
+        /// Write one BED12 record per node
+        #[arg(long, group = "mode")]
+        nodes: bool,
+
+        /// Write one BED6 record per edge, spanning its junction
+        #[arg(long, group = "mode")]
+        edges: bool,
+
+        /// Numeric attribute used as the BED score
+        #[arg(long, value_name = "ATTRIBUTE")]
+        score: Option<String>,
+
+        /// Output file path for the BED
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+
+        /// Order of graphs, nodes, edges and paths in the output
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted)]
+        ordering: OrderingPolicy,
+    },
+
+    /// Convert a TSG file to VCF format, or import structural variants from a VCF file
     Vcf {
         /// Input TSG file path
         #[arg(required = true, value_hint = ValueHint::FilePath)]
         input: PathBuf,
 
-        /// Output file path for the VCF
+        /// Merge the structural variants of this VCF file into the input and write TSG
+        #[arg(long, value_name = "VCF", value_hint = ValueHint::FilePath)]
+        import: Option<PathBuf>,
+
+        /// Graph receiving imported variants that match no node [default: the default graph]
+        #[arg(long, requires = "import")]
+        graph: Option<String>,
+
+        /// Indexed reference FASTA file providing the REF bases of the VCF
+        #[arg(short, long, conflicts_with = "import", value_hint = ValueHint::FilePath)]
+        reference: Option<PathBuf>,
+
+        /// Output file path for the VCF, or for the TSG when importing
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+
+        /// Order of graphs, nodes, edges and paths in the TSG written when importing
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted, requires = "import")]
+        ordering: OrderingPolicy,
+    },
+
+    /// Convert a TSG file to GFA 1.0 or GFA 2.0 format
+    Gfa {
+        /// Input TSG file path
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+
+        /// GFA version to write
+        #[arg(short = 'g', long, value_enum, default_value_t = GfaSpec::V2)]
+        gfa_version: GfaSpec,
+
+        /// Output file path for the GFA
         #[arg(short, long, value_hint = ValueHint::FilePath)]
         output: Option<PathBuf>,
     },
@@ -71,7 +196,83 @@ pub enum Commands {
         #[arg(required = true, value_hint = ValueHint::FilePath)]
         input: PathBuf,
 
-        /// Output DOT file path
+        /// File format of each graph
+        #[arg(short, long, value_enum, default_value_t = GraphFileFormat::Dot)]
+        format: GraphFileFormat,
+
+        /// Path IDs to highlight in the DOT output, can be separated by commas
+        #[arg(long, value_name = "PATH_ID", value_delimiter = ',')]
+        highlight: Vec<String>,
+
+        /// Output directory [default: <INPUT>_<FORMAT>]
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+
+    /// Report connectivity, cycles, bubbles and other statistics of each graph
+    Analyze {
+        /// Input TSG file path
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+
+        /// List the bubbles of each graph instead of the summary
+        #[arg(short, long)]
+        bubbles: bool,
+
+        /// Write the summaries as JSON instead of a table
+        #[arg(short, long, conflicts_with = "bubbles")]
+        json: bool,
+
+        /// Output file path [default: stdout]
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+
+    /// Decompose each graph into nested superbubble sites, written as U groups or JSON
+    Sites {
+        /// Input TSG file path
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+
+        /// Write the site trees as JSON instead of the TSG with a U group per site
+        #[arg(short, long)]
+        json: bool,
+
+        /// Output file path [default: stdout]
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+
+        /// Order of graphs, nodes, edges and paths in the output
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted)]
+        ordering: OrderingPolicy,
+    },
+
+    /// Classify alternative splicing events (SE, A5SS, A3SS, RI, MXE, AF, AL) of each graph
+    Events {
+        /// Input TSG file path
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+
+        /// Prefix of the <PREFIX>.events.tsv and <PREFIX>.ioe outputs [default: <INPUT> without extension]
+        #[arg(short, long, value_name = "PREFIX", value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+
+    /// Plot each graph as an SVG image with exons at genomic scale and path tracks
+    Plot {
+        /// Input TSG file path
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+
+        /// Image width in pixels
+        #[arg(short, long, default_value_t = 1200)]
+        width: usize,
+
+        /// Compress introns and other gaps between exons to at most this many base pairs
+        #[arg(long, value_name = "BP")]
+        max_intron: Option<usize>,
+
+        /// Output directory [default: <INPUT>_svg]
         #[arg(short, long, value_hint = ValueHint::FilePath)]
         output: Option<PathBuf>,
     },
@@ -82,13 +283,25 @@ pub enum Commands {
         #[arg(required = true, value_hint = ValueHint::FilePath)]
         input: PathBuf,
 
+        /// JSON layout: Cytoscape.js files per graph, or the complete graph in one file
+        #[arg(short, long, value_enum, default_value_t = JsonSchema::Cytoscape)]
+        schema: JsonSchema,
+
+        /// File format of each graph with the Cytoscape layout
+        #[arg(short, long, value_enum, default_value_t = GraphFileFormat::Json)]
+        format: GraphFileFormat,
+
         /// Format JSON with indentation for better readability
         #[arg(short, long, default_value = "false")]
         pretty: bool,
 
-        /// Output file path for the JSON
+        /// Output directory for Cytoscape files [default: <INPUT>_<FORMAT>], or output file for the full schema [default: <INPUT>.json]
         #[arg(short, long, value_hint = ValueHint::FilePath)]
         output: Option<PathBuf>,
+
+        /// Order of graphs, nodes and edges in per-graph files
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted)]
+        ordering: OrderingPolicy,
     },
 
     /// Find and enumerate all valid paths through the graph
@@ -104,6 +317,10 @@ pub enum Commands {
         /// Output file path for the paths, default is stdout
         #[arg(short, long, value_hint = ValueHint::FilePath)]
         output: Option<PathBuf>,
+
+        /// Order of graphs, nodes, edges and paths in the output
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted)]
+        ordering: OrderingPolicy,
     },
 
     /// Merge multiple TSG files into a single TSG file
@@ -115,6 +332,10 @@ pub enum Commands {
         /// Output file path for the merged TSG
         #[arg(short, long, value_hint = ValueHint::FilePath)]
         output: Option<PathBuf>,
+
+        /// Order of graphs, nodes, edges and paths in the output
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted)]
+        ordering: OrderingPolicy,
     },
 
     /// Split a TSG file into multiple TSG files
@@ -146,4 +367,38 @@ pub enum Commands {
         #[arg(short, long, value_hint = ValueHint::FilePath)]
         output: Option<PathBuf>,
     },
+
+    /// Build a random-access index (.tsi) of the graph sections in a TSG file
+    Index {
+        /// Input TSG file path (uncompressed or BGZF-compressed)
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+
+        /// Output index path [default: <INPUT>.tsi]
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+
+    /// Convert a graph between the text TSG, binary BTSG, GFA and JSON formats
+    Convert {
+        /// Input graph file path
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+
+        /// Input format [default: detected from the file]
+        #[arg(long, value_enum)]
+        from: Option<GraphFormat>,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        to: GraphFormat,
+
+        /// Output file path, default is stdout
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+
+        /// Order of graphs, nodes, edges and paths in the output
+        #[arg(long, value_enum, default_value_t = OrderingPolicy::Sorted)]
+        ordering: OrderingPolicy,
+    },
 }
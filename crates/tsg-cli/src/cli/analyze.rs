@@ -0,0 +1,94 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tracing::info;
+use tsg::graph::GraphAnalysis;
+use tsg::io::GraphSectionReader;
+
+/// Columns of the summary table written by `tsg-cli analyze`
+const SUMMARY_HEADER: &str = "graph\tnodes\tedges\tpaths\tchains\treads\tcomponents\tconnected\tcyclic\tsources\tsinks\tbubbles\tsv_types";
+
+/// Analyze the structure of every graph of a TSG file
+///
+/// Writes a tab-separated table with one summary row per graph, or with `bubbles`, one
+/// row per bubble with its source, sink and the nodes inside it. With `json`, the
+/// summaries are written as a JSON array instead.
+pub fn analyze<P: AsRef<Path>>(
+    input: P,
+    bubbles: bool,
+    json: bool,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    info!("Analyzing TSG file: {}", input.as_ref().display());
+    let reader = GraphSectionReader::from_file(input.as_ref())?;
+    let mut writer = match output {
+        Some(path) => tsg::io::create_writer(path)?,
+        None => tsg::io::CompressedWriter::stdout(),
+    };
+
+    let mut summaries = Vec::new();
+    if bubbles {
+        writeln!(writer, "graph\tsource\tsink\tnodes")?;
+    } else if !json {
+        writeln!(writer, "{}", SUMMARY_HEADER)?;
+    }
+    for graph in reader {
+        let graph = graph?;
+        if bubbles {
+            for bubble in graph.detect_bubbles() {
+                let ids: Vec<String> = bubble
+                    .iter()
+                    .map(|&idx| graph.node_by_idx(idx).unwrap().id.to_string())
+                    .collect();
+                writeln!(
+                    writer,
+                    "{}\t{}\t{}\t{}",
+                    graph.id,
+                    ids[0],
+                    ids[ids.len() - 1],
+                    ids[1..ids.len() - 1].join(",")
+                )?;
+            }
+            continue;
+        }
+
+        let summary = graph.summarize()?;
+        if json {
+            summaries.push(summary);
+            continue;
+        }
+        let sv_types: Vec<String> = summary
+            .sv_types
+            .iter()
+            .map(|(sv_type, count)| format!("{}:{}", sv_type, count))
+            .collect();
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            summary.id,
+            summary.nodes,
+            summary.edges,
+            summary.paths,
+            summary.chains,
+            summary.reads,
+            summary.components,
+            summary.is_connected,
+            summary.is_cyclic,
+            summary.sources,
+            summary.sinks,
+            summary.bubbles,
+            if sv_types.is_empty() {
+                ".".to_string()
+            } else {
+                sv_types.join(",")
+            }
+        )?;
+    }
+    if json && !bubbles {
+        serde_json::to_writer_pretty(&mut writer, &summaries)?;
+        writeln!(writer)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
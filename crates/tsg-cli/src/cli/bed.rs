@@ -0,0 +1,33 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tracing::info;
+use tsg::graph::{OrderingPolicy, WriteOptions};
+use tsg::io::{BedMode, GraphSectionReader};
+
+/// Convert the paths, nodes or edges of a TSG file to BED
+pub fn to_bed<P: AsRef<Path>>(
+    input: P,
+    mode: BedMode,
+    score: Option<String>,
+    output: Option<PathBuf>,
+    ordering: OrderingPolicy,
+) -> Result<()> {
+    let options = WriteOptions::builder().ordering(ordering).build();
+    let reader = GraphSectionReader::from_file(input.as_ref())?;
+    let mut writer = match output {
+        Some(path) => {
+            info!("Writing to file: {:?}", path);
+            tsg::io::create_writer(path)?
+        }
+        None => {
+            info!("Writing to stdout");
+            tsg::io::CompressedWriter::stdout()
+        }
+    };
+    for graph in reader {
+        tsg::io::graph_to_bed_with_options(&graph?, &mut writer, mode, score.as_deref(), &options)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use tracing::info;
+use tsg::graph::{OrderingPolicy, TSGraph, WriteOptions};
+use tsg::io::{BTSG_EXTENSION, GfaVersion, is_btsg};
+
+/// Serialisation formats understood by `tsg-cli convert`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// Text TSG, optionally gzip, BGZF or zstd compressed
+    Tsg,
+    /// Binary TSG with zstd-compressed graph blocks
+    Btsg,
+    /// GFA 1.0 or 2.0; written as GFA 2.0
+    Gfa,
+    /// TSG JSON schema, or the Cytoscape JSON of `tsg-cli json` when reading
+    Json,
+}
+
+impl GraphFormat {
+    /// Guess the format of an existing file from its extension or leading bytes
+    pub fn detect<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(BTSG_EXTENSION))
+        {
+            return Ok(GraphFormat::Btsg);
+        }
+        // GFA and JSON files may carry a compression extension, e.g. graph.gfa.gz
+        if path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy().to_ascii_lowercase();
+            name.ends_with(".gfa") || name.contains(".gfa.")
+        }) {
+            return Ok(GraphFormat::Gfa);
+        }
+        if path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy().to_ascii_lowercase();
+            name.ends_with(".json") || name.contains(".json.")
+        }) {
+            return Ok(GraphFormat::Json);
+        }
+
+        let mut magic = Vec::with_capacity(4);
+        File::open(path)?.take(4).read_to_end(&mut magic)?;
+        Ok(if is_btsg(&magic) {
+            GraphFormat::Btsg
+        } else {
+            GraphFormat::Tsg
+        })
+    }
+}
+
+/// Convert a graph between the text TSG, binary TSG, GFA and JSON formats
+///
+/// The input format is detected from the file when `from` is not given.
+pub fn convert<P: AsRef<Path>>(
+    input: P,
+    from: Option<GraphFormat>,
+    to: GraphFormat,
+    output: Option<P>,
+    ordering: OrderingPolicy,
+) -> Result<()> {
+    let input = input.as_ref();
+    let from = match from {
+        Some(format) => format,
+        None => GraphFormat::detect(input)?,
+    };
+
+    info!("Reading {:?} file: {}", from, input.display());
+    let tsg = match from {
+        GraphFormat::Tsg => TSGraph::from_file(input)?,
+        GraphFormat::Btsg => TSGraph::from_binary(BufReader::new(File::open(input)?))?,
+        GraphFormat::Gfa => TSGraph::from_gfa_file(input)?,
+        GraphFormat::Json => TSGraph::from_json_file(input)?,
+    };
+
+    let mut writer = match output {
+        Some(path) => {
+            info!("Writing {:?} to file: {}", to, path.as_ref().display());
+            tsg::io::create_writer(path)?
+        }
+        None => {
+            info!("Writing {:?} to stdout", to);
+            tsg::io::CompressedWriter::stdout()
+        }
+    };
+
+    match to {
+        GraphFormat::Tsg => tsg.to_writer_with_options(
+            &mut writer,
+            &WriteOptions::builder().ordering(ordering).build(),
+        )?,
+        GraphFormat::Btsg => tsg.to_binary(&mut writer)?,
+        GraphFormat::Gfa => tsg::io::write_gfa(&tsg, &mut writer, GfaVersion::V2)?,
+        GraphFormat::Json => tsg::io::write_json(&tsg, &mut writer, true)?,
+    }
+    writer.finish()?;
+    Ok(())
+}
@@ -1,40 +1,54 @@
-use std::{io::Write, path::Path};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
-use tracing::info;
-use tsg::graph::TSGraph;
+use bstr::BString;
+use clap::ValueEnum;
+use tracing::{info, warn};
+use tsg::graph::{DotOptions, WriteOptions};
+use tsg::io::GraphSectionReader;
 
-/// Converts a TSG graph from a file into DOT format and writes it to an output directory.
-///
-/// # Parameters
-/// - `input`: The path to the input file containing the TSG graph.
-/// - `output`: An optional path to the output directory. If not provided, a directory
-///   named `<input_file_stem>_dot` will be created in the same location as the input file.
-///
-/// # Returns
-/// - `Result<()>`: Returns `Ok(())` if the operation succeeds, or an error if it fails.
+/// Per-graph file formats written by `tsg-cli dot` and `tsg-cli json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFileFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Cytoscape.js JSON
+    Json,
+    /// GraphML, for yEd, networkx and igraph
+    Graphml,
+    /// GEXF 1.3, for Gephi
+    Gexf,
+}
+
+impl GraphFileFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            GraphFileFormat::Dot => "dot",
+            GraphFileFormat::Json => "json",
+            GraphFileFormat::Graphml => "graphml",
+            GraphFileFormat::Gexf => "gexf",
+        }
+    }
+}
+
+/// Create the output directory of the per-graph files of a TSG file
 ///
-/// # Errors
-/// - Returns an error if the input file cannot be read or parsed.
-/// - Returns an error if the output directory cannot be created or written to.
-pub fn to_dot<P: AsRef<Path>>(input: P, output: Option<P>) -> Result<()> {
-    let tsg_graph = TSGraph::from_file(input.as_ref())?;
-
-    info!(
-        "parsing {} TSG graph from file: {:?}",
-        tsg_graph.graphs.len(),
-        input.as_ref()
-    );
+/// Without an explicit `output`, the directory is `<input_file_stem>_<extension>` next to
+/// the input file.
+pub fn graph_output_dir<P: AsRef<Path>>(
+    input: &Path,
+    extension: &str,
+    output: Option<P>,
+) -> Result<PathBuf> {
     let output_path = match output {
         Some(path) => path.as_ref().to_path_buf(),
         None => {
-            let input_path = input.as_ref().to_path_buf();
-            let parent = input_path.parent().unwrap_or(Path::new("."));
-            let stem = input_path
+            let parent = input.parent().unwrap_or(Path::new("."));
+            let stem = input
                 .file_stem()
                 .unwrap_or_else(|| std::ffi::OsStr::new("output"));
-            let dot_dir = format!("{}_dot", stem.to_string_lossy());
-            parent.join(dot_dir)
+            parent.join(format!("{}_{}", stem.to_string_lossy(), extension))
         }
     };
 
@@ -42,13 +56,104 @@ pub fn to_dot<P: AsRef<Path>>(input: P, output: Option<P>) -> Result<()> {
     if !output_path.exists() {
         std::fs::create_dir_all(&output_path)?;
     }
-    for (id, graph) in tsg_graph.graphs.iter() {
-        // create a dot file for each graph under the output directory
-        let graph_output_file = output_path.join(format!("{}.dot", id));
+    Ok(output_path)
+}
+
+/// Write each graph of a TSG file to its own file in an output directory
+///
+/// The directory defaults to `<input_file_stem>_<extension>` next to the input file, and
+/// the files are named after the graph IDs. Paths highlighted by `dot_options` are looked
+/// up in each graph, and only those missing from every graph are reported.
+pub fn write_graph_files<P: AsRef<Path>>(
+    input: P,
+    format: GraphFileFormat,
+    output: Option<P>,
+    pretty: bool,
+    options: &WriteOptions,
+    dot_options: &DotOptions,
+) -> Result<()> {
+    info!("parsing TSG graphs from file: {:?}", input.as_ref());
+    let reader = GraphSectionReader::from_file(input.as_ref())?;
+
+    let output_path = graph_output_dir(input.as_ref(), format.extension(), output)?;
+    let mut highlighted: Vec<&BString> = Vec::new();
+    for graph in reader {
+        let graph = graph?;
+        // create a file for each graph under the output directory
+        let graph_output_file = output_path.join(format!("{}.{}", graph.id, format.extension()));
         let output_file = std::fs::File::create(graph_output_file)?;
         let mut writer = std::io::BufWriter::new(output_file);
-        let dot = graph.to_dot(true, true)?;
-        writer.write_all(dot.as_bytes())?;
+        match format {
+            GraphFileFormat::Dot => {
+                let highlight_paths: Vec<BString> = dot_options
+                    .highlight_paths
+                    .iter()
+                    .filter(|id| graph.groups().contains_key(*id))
+                    .cloned()
+                    .collect();
+                highlighted.extend(
+                    dot_options
+                        .highlight_paths
+                        .iter()
+                        .filter(|id| highlight_paths.contains(id)),
+                );
+                let dot = graph.to_dot_with_options(&DotOptions {
+                    highlight_paths,
+                    ..dot_options.clone()
+                })?;
+                writer.write_all(dot.as_bytes())?;
+            }
+            GraphFileFormat::Json => {
+                let json = graph.to_json_with_options(options)?;
+                if pretty {
+                    serde_json::to_writer_pretty(&mut writer, &json)?;
+                } else {
+                    serde_json::to_writer(&mut writer, &json)?;
+                }
+            }
+            GraphFileFormat::Graphml => graph.to_graphml_with_options(&mut writer, options)?,
+            GraphFileFormat::Gexf => graph.to_gexf_with_options(&mut writer, options)?,
+        }
+        writer.flush()?;
+    }
+    for path_id in &dot_options.highlight_paths {
+        if !highlighted.contains(&path_id) {
+            warn!("path {} to highlight not found in any graph", path_id);
+        }
     }
     Ok(())
 }
+
+/// Converts a TSG graph from a file into DOT format and writes it to an output directory.
+///
+/// # Parameters
+/// - `input`: The path to the input file containing the TSG graph.
+/// - `format`: The file format, DOT unless GraphML or GEXF is requested.
+/// - `highlight`: IDs of the paths to colour in the DOT output, greying out the rest.
+/// - `output`: An optional path to the output directory. If not provided, a directory
+///   named `<input_file_stem>_dot` will be created in the same location as the input file.
+///
+/// # Returns
+/// - `Result<()>`: Returns `Ok(())` if the operation succeeds, or an error if it fails.
+///
+/// # Errors
+/// - Returns an error if the input file cannot be read or parsed.
+/// - Returns an error if the output directory cannot be created or written to.
+pub fn to_dot<P: AsRef<Path>>(
+    input: P,
+    format: GraphFileFormat,
+    highlight: Vec<String>,
+    output: Option<P>,
+) -> Result<()> {
+    let dot_options = DotOptions::builder()
+        .highlight_paths(highlight.into_iter().map(BString::from).collect())
+        .build();
+    write_graph_files(
+        input,
+        format,
+        output,
+        false,
+        &WriteOptions::default(),
+        &dot_options,
+    )
+}
@@ -0,0 +1,46 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tracing::info;
+use tsg::graph::SplicingEvent;
+use tsg::io::GraphSectionReader;
+
+/// Classify the alternative splicing events of every graph of a TSG file
+///
+/// Writes `<prefix>.events.tsv`, with the paths and read support of each event, and
+/// `<prefix>.ioe`, listing the events in the SUPPA `.ioe` layout. The prefix defaults to
+/// the input path without its extension.
+pub fn events<P: AsRef<Path>>(input: P, output_prefix: Option<PathBuf>) -> Result<()> {
+    info!(
+        "Classifying splicing events of TSG file: {}",
+        input.as_ref().display()
+    );
+    let reader = GraphSectionReader::from_file(input.as_ref())?;
+    let prefix = output_prefix.unwrap_or_else(|| input.as_ref().with_extension(""));
+    let tsv_path = PathBuf::from(format!("{}.events.tsv", prefix.display()));
+    let ioe_path = PathBuf::from(format!("{}.ioe", prefix.display()));
+    let mut tsv = tsg::io::create_writer(&tsv_path)?;
+    let mut ioe = tsg::io::create_writer(&ioe_path)?;
+
+    writeln!(tsv, "{}", SplicingEvent::TSV_HEADER)?;
+    writeln!(ioe, "{}", SplicingEvent::IOE_HEADER)?;
+    let mut count = 0;
+    for graph in reader {
+        let graph = graph?;
+        for event in graph.splicing_events()? {
+            writeln!(tsv, "{}", event.to_tsv(&graph.id))?;
+            writeln!(ioe, "{}", event.to_ioe(&graph.id))?;
+            count += 1;
+        }
+    }
+    tsv.finish()?;
+    ioe.finish()?;
+    info!(
+        "{} events written to {} and {}",
+        count,
+        tsv_path.display(),
+        ioe_path.display()
+    );
+    Ok(())
+}
@@ -1,22 +1,30 @@
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
-use std::io::Write;
 use tracing::info;
-use tsg::graph::TSGraph;
+use tsg::graph::{OrderingPolicy, WriteOptions};
+use tsg::io::GraphSectionReader;
 
-pub fn to_fa<P: AsRef<Path>>(input: P, output: Option<PathBuf>) -> Result<()> {
-    let mut tsg_graph = TSGraph::from_file(input.as_ref())?;
-    let mut writer: Box<dyn Write> = match output {
+pub fn to_fa<P: AsRef<Path>>(
+    input: P,
+    output: Option<PathBuf>,
+    ordering: OrderingPolicy,
+) -> Result<()> {
+    let options = WriteOptions::builder().ordering(ordering).build();
+    let reader = GraphSectionReader::from_file(input.as_ref())?;
+    let mut writer = match output {
         Some(path) => {
             info!("Writing to file: {:?}", path);
-            Box::new(std::io::BufWriter::new(std::fs::File::create(path)?))
+            tsg::io::create_writer(path)?
         }
         None => {
             info!("Writing to stdout");
-            Box::new(std::io::BufWriter::new(std::io::stdout().lock()))
+            tsg::io::CompressedWriter::stdout()
         }
     };
-    tsg::io::to_fa(&mut tsg_graph, &mut writer)?;
+    for graph in reader {
+        tsg::io::graph_to_fa_with_options(&graph?, &mut writer, &options)?;
+    }
+    writer.finish()?;
     Ok(())
 }
@@ -0,0 +1,48 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use tracing::info;
+use tsg::graph::TSGraph;
+use tsg::io::GfaVersion;
+
+/// GFA specification versions that can be written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum GfaSpec {
+    /// GFA 1.0 (segments, links and paths)
+    #[value(name = "1")]
+    V1,
+    /// GFA 2.0 (segments, edges and groups)
+    #[default]
+    #[value(name = "2")]
+    V2,
+}
+
+impl From<GfaSpec> for GfaVersion {
+    fn from(spec: GfaSpec) -> Self {
+        match spec {
+            GfaSpec::V1 => GfaVersion::V1,
+            GfaSpec::V2 => GfaVersion::V2,
+        }
+    }
+}
+
+/// Convert a TSG file to GFA 1.0 or GFA 2.0
+///
+/// All graphs of the input are written to one GFA file; TSG-specific fields are kept in optional tags.
+pub fn to_gfa<P: AsRef<Path>>(input: P, spec: GfaSpec, output: Option<PathBuf>) -> Result<()> {
+    let tsg = TSGraph::from_file(input.as_ref())?;
+    let mut writer = match output {
+        Some(path) => {
+            info!("Writing to file: {:?}", path);
+            tsg::io::create_writer(path)?
+        }
+        None => {
+            info!("Writing to stdout");
+            tsg::io::CompressedWriter::stdout()
+        }
+    };
+    tsg::io::write_gfa(&tsg, &mut writer, spec.into())?;
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tracing::info;
+use tsg::graph::{OrderingPolicy, WriteOptions};
+use tsg::io::{GFF3_HEADER, GraphSectionReader};
+
+/// Convert the paths of a TSG file to GFF3 with a gene/mRNA/exon hierarchy
+pub fn to_gff3<P: AsRef<Path>>(
+    input: P,
+    output: Option<PathBuf>,
+    ordering: OrderingPolicy,
+) -> Result<()> {
+    let options = WriteOptions::builder().ordering(ordering).build();
+    let reader = GraphSectionReader::from_file(input.as_ref())?;
+    let mut writer = match output {
+        Some(path) => {
+            info!("Writing to file: {:?}", path);
+            tsg::io::create_writer(path)?
+        }
+        None => {
+            info!("Writing to stdout");
+            tsg::io::CompressedWriter::stdout()
+        }
+    };
+    writeln!(writer, "{}", GFF3_HEADER)?;
+    for graph in reader {
+        tsg::io::graph_to_gff3_with_options(&graph?, &mut writer, &options)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
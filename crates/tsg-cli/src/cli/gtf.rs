@@ -1,22 +1,56 @@
-use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use tracing::info;
-use tsg::graph::TSGraph;
+use tsg::graph::{OrderingPolicy, TSGraph, WriteOptions};
+use tsg::io::GraphSectionReader;
 
-pub fn to_gtf<P: AsRef<Path>>(input: P, output: Option<PathBuf>) -> Result<()> {
-    let tsg_graph = TSGraph::from_file(input.as_ref())?;
-    let mut writer: Box<dyn Write> = match output {
+pub fn to_gtf<P: AsRef<Path>>(
+    input: P,
+    output: Option<PathBuf>,
+    ordering: OrderingPolicy,
+) -> Result<()> {
+    let options = WriteOptions::builder().ordering(ordering).build();
+    let reader = GraphSectionReader::from_file(input.as_ref())?;
+    let mut writer = match output {
         Some(path) => {
             info!("Writing to file: {:?}", path);
-            Box::new(std::io::BufWriter::new(std::fs::File::create(path)?))
+            tsg::io::create_writer(path)?
         }
         None => {
             info!("Writing to stdout");
-            Box::new(std::io::BufWriter::new(std::io::stdout().lock()))
+            tsg::io::CompressedWriter::stdout()
         }
     };
-    tsg::io::to_gtf(&tsg_graph, &mut writer)?;
+    for graph in reader {
+        tsg::io::graph_to_gtf_with_options(&graph?, &mut writer, &options)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Build a reference TSG from a GTF or GFF3 annotation
+pub fn from_gtf<P: AsRef<Path>>(
+    input: P,
+    output: Option<PathBuf>,
+    ordering: OrderingPolicy,
+) -> Result<()> {
+    let tsg = TSGraph::from_gtf_file(input.as_ref())?;
+    info!("Built {} gene graphs", tsg.graphs.len());
+    let mut writer = match output {
+        Some(path) => {
+            info!("Writing to file: {:?}", path);
+            tsg::io::create_writer(path)?
+        }
+        None => {
+            info!("Writing to stdout");
+            tsg::io::CompressedWriter::stdout()
+        }
+    };
+    tsg.to_writer_with_options(
+        &mut writer,
+        &WriteOptions::builder().ordering(ordering).build(),
+    )?;
+    writer.finish()?;
     Ok(())
 }
@@ -0,0 +1,25 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tracing::info;
+use tsg::io::{TsgIndex, index_path};
+
+/// Build a `.tsi` index of the graph sections in a TSG file
+///
+/// The index is written next to the input as `<input>.tsi` unless an output path is given.
+pub fn index<P: AsRef<Path>>(input: P, output: Option<PathBuf>) -> Result<()> {
+    let input = input.as_ref();
+    let output = output.unwrap_or_else(|| index_path(input));
+
+    info!("Indexing TSG file: {}", input.display());
+    let index = TsgIndex::from_tsg_file(input)?;
+    index.save(&output)?;
+
+    info!(
+        "Indexed {} graphs and {} links into {}",
+        index.graphs.len(),
+        index.links.len(),
+        output.display()
+    );
+    Ok(())
+}
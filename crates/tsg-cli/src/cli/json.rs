@@ -1,41 +1,107 @@
-use std::io::Write;
 use std::path::Path;
 
-use anyhow::Result;
-use tsg::graph::TSGraph;
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+use tracing::info;
+use tsg::graph::{DotOptions, OrderingPolicy, TSGraph, WriteOptions};
 
-pub fn to_json<P: AsRef<Path>>(input: P, pretty: bool, output: Option<P>) -> Result<()> {
-    let graph = TSGraph::from_file(input.as_ref())?;
+use super::{GraphFileFormat, write_graph_files};
+
+/// JSON layouts written by `tsg-cli json`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum JsonSchema {
+    /// One Cytoscape.js file per graph, with nodes, edges and styling hints
+    #[default]
+    Cytoscape,
+    /// A single file with every header, graph, group, attribute and link
+    Full,
+}
+
+/// Write each graph as Cytoscape JSON (or GraphML or GEXF), or the whole graph in the full schema
+pub fn to_json<P: AsRef<Path>>(
+    input: P,
+    schema: JsonSchema,
+    format: GraphFileFormat,
+    pretty: bool,
+    output: Option<P>,
+    ordering: OrderingPolicy,
+) -> Result<()> {
+    let options = WriteOptions::builder().ordering(ordering).build();
+    if schema == JsonSchema::Full {
+        if format != GraphFileFormat::Json {
+            bail!("The full schema is only available as JSON");
+        }
+        return to_full_json(input, pretty, output, &options);
+    }
+    write_graph_files(
+        input,
+        format,
+        output,
+        pretty,
+        &options,
+        &DotOptions::default(),
+    )
+}
+
+/// Write the whole graph in the TSG JSON schema, next to the input by default
+fn to_full_json<P: AsRef<Path>>(
+    input: P,
+    pretty: bool,
+    output: Option<P>,
+    options: &WriteOptions,
+) -> Result<()> {
+    let input_path = input.as_ref();
     let output_path = match output {
         Some(path) => path.as_ref().to_path_buf(),
-        None => {
-            let input_path = input.as_ref().to_path_buf();
-            let parent = input_path.parent().unwrap_or(Path::new("."));
-            let stem = input_path
-                .file_stem()
-                .unwrap_or_else(|| std::ffi::OsStr::new("output"));
-            let dot_dir = format!("{}_json", stem.to_string_lossy());
-            parent.join(dot_dir)
-        }
+        None => input_path.with_extension("json"),
     };
+    let tsg = TSGraph::from_file(input_path)?;
+    info!("Writing to file: {:?}", output_path);
+    let mut writer = tsg::io::create_writer(&output_path)?;
+    tsg::io::write_json_with_options(&tsg, &mut writer, pretty, options)?;
+    writer.finish()?;
+    Ok(())
+}
 
-    // create a folder for the output if it doesn't exist
-    if !output_path.exists() {
-        std::fs::create_dir_all(&output_path)?;
-    }
-    for (id, graph) in graph.graphs.iter() {
-        // create a dot file for each graph under the output directory
-        let graph_output_file = output_path.join(format!("{}.json", id));
-        let output_file = std::fs::File::create(graph_output_file)?;
-        let mut writer = std::io::BufWriter::new(output_file);
-        let json = graph.to_json()?;
-        if pretty {
-            let json = serde_json::to_string_pretty(&json)?;
-            writer.write_all(json.as_bytes())?;
-        } else {
-            let json = serde_json::to_string(&json)?;
-            writer.write_all(json.as_bytes())?;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_to_json_full_schema() -> Result<()> {
+        let input = PathBuf::from("../tsg-core/tests/data/test_mul.tsg");
+        let output = std::env::temp_dir().join("tsg_cli_full_schema.json");
+        to_json(
+            input.clone(),
+            JsonSchema::Full,
+            GraphFileFormat::Json,
+            false,
+            Some(output.clone()),
+            OrderingPolicy::Insertion,
+        )?;
+        let parsed = TSGraph::from_json_file(&output)?;
+        let tsg = TSGraph::from_file(&input)?;
+        assert_eq!(parsed.graphs.len(), tsg.graphs.len());
+        let graph_ids =
+            |tsg: &TSGraph| -> Vec<String> { tsg.graphs.keys().map(ToString::to_string).collect() };
+        assert_eq!(graph_ids(&parsed), graph_ids(&tsg));
+
+        to_full_json(&input, true, Some(&output), &WriteOptions::sorted())?;
+        let parsed = TSGraph::from_json_file(&output)?;
+        assert_eq!(parsed.links.len(), tsg.links.len());
+
+        assert!(
+            to_json(
+                input,
+                JsonSchema::Full,
+                GraphFileFormat::Graphml,
+                false,
+                Some(output),
+                OrderingPolicy::Sorted,
+            )
+            .is_err()
+        );
+        Ok(())
     }
-    Ok(())
 }
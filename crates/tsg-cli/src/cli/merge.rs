@@ -1,17 +1,20 @@
-use std::io::Write;
 use std::path::Path;
 
 use anyhow::{Result, anyhow};
 use bstr::ByteSlice;
 use tracing::info;
-use tsg::graph::TSGraph;
+use tsg::graph::{OrderingPolicy, TSGraph, WriteOptions};
 
 /// Merge multiple TSG files into a single TSG file
 ///
 /// This function takes multiple TSG files and merges them into a single TSG file.
 /// The merged TSG will contain all graphs from all input files, with unique graph IDs.
 /// If there are duplicate graph IDs, they will be renamed with a suffix.
-pub fn merge<P: AsRef<Path>>(inputs: Vec<P>, output: Option<P>) -> Result<()> {
+pub fn merge<P: AsRef<Path>>(
+    inputs: Vec<P>,
+    output: Option<P>,
+    ordering: OrderingPolicy,
+) -> Result<()> {
     if inputs.is_empty() {
         return Err(anyhow!("No input files provided"));
     }
@@ -38,6 +41,13 @@ pub fn merge<P: AsRef<Path>>(inputs: Vec<P>, output: Option<P>) -> Result<()> {
             }
         }
 
+        // Merge file-level comments (avoiding duplicates)
+        for comment in tsg.comments {
+            if !merged_tsg.comments.contains(&comment) {
+                merged_tsg.comments.push(comment);
+            }
+        }
+
         // Merge graphs (handling potential ID conflicts)
         for (graph_id, graph) in tsg.graphs {
             let mut new_id = graph_id.clone();
@@ -54,7 +64,9 @@ pub fn merge<P: AsRef<Path>>(inputs: Vec<P>, output: Option<P>) -> Result<()> {
             }
 
             // Add the graph to the merged TSG
-            merged_tsg.graphs.insert(new_id, graph);
+            let mut graph = graph;
+            graph.id = new_id;
+            merged_tsg.add_graph(graph);
         }
 
         // Merge inter-graph links
@@ -65,18 +77,22 @@ pub fn merge<P: AsRef<Path>>(inputs: Vec<P>, output: Option<P>) -> Result<()> {
 
     // Write the merged TSG to the output file
 
-    let mut writer: Box<dyn Write> = match output {
+    let mut writer = match output {
         Some(path) => {
             info!("Writing paths to file: {}", path.as_ref().display());
-            Box::new(std::io::BufWriter::new(std::fs::File::create(path)?))
+            tsg::io::create_writer(path)?
         }
         None => {
             info!("Writing paths to stdout");
-            Box::new(std::io::BufWriter::new(std::io::stdout().lock()))
+            tsg::io::CompressedWriter::stdout()
         }
     };
 
-    merged_tsg.to_writer(&mut writer)?;
+    merged_tsg.to_writer_with_options(
+        &mut writer,
+        &WriteOptions::builder().ordering(ordering).build(),
+    )?;
+    writer.finish()?;
     info!("Merge completed successfully");
     Ok(())
 }
@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use anyhow::Result;
+use colored::Colorize;
+use tracing::info;
+use tsg::graph::{ParseOptions, TSGraph, TsgError};
+
+/// Parse a TSG file and report its graphs
+///
+/// In lenient mode malformed records are skipped and a summary table
+/// of every problem found is printed after the parsed graphs.
+pub fn parse<P: AsRef<Path>>(input: P, lenient: bool) -> Result<()> {
+    let options = if lenient {
+        ParseOptions::lenient()
+    } else {
+        ParseOptions::strict()
+    };
+
+    info!("Parsing TSG file: {}", input.as_ref().display());
+    let (graph, diagnostics) = TSGraph::from_file_with_options(input, &options)?;
+
+    for (id, graph) in graph.graphs.iter() {
+        println!(
+            "Successfully parsed graph {} with {} nodes and {} edges",
+            id.to_string().green(),
+            graph.nodes().len(),
+            graph.edges().len()
+        );
+    }
+
+    if !diagnostics.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!("Skipped {} malformed record(s)", diagnostics.len()).yellow()
+        );
+        print_diagnostics(&diagnostics);
+    }
+    Ok(())
+}
+
+/// Print diagnostics as an aligned table of line, record, field and reason
+fn print_diagnostics(diagnostics: &[TsgError]) {
+    let rows: Vec<[String; 4]> = diagnostics
+        .iter()
+        .map(|err| {
+            [
+                err.line().map_or("-".to_string(), |line| line.to_string()),
+                err.record().unwrap_or("-").to_string(),
+                err.field().unwrap_or("-").to_string(),
+                err.reason(),
+            ]
+        })
+        .collect();
+
+    let header = ["line", "record", "field", "reason"];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    println!(
+        "{:>w0$}  {:<w1$}  {:<w2$}  {}",
+        header[0].bold(),
+        header[1].bold(),
+        header[2].bold(),
+        header[3].bold(),
+        w0 = widths[0],
+        w1 = widths[1],
+        w2 = widths[2],
+    );
+    for row in rows {
+        println!(
+            "{:>w0$}  {:<w1$}  {:<w2$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use tracing::{info, warn};
+use tsg::graph::PlotOptions;
+use tsg::io::GraphSectionReader;
+
+use super::graph_output_dir;
+
+/// Plot each graph of a TSG file as an SVG image in an output directory
+///
+/// # Parameters
+/// - `input`: The path to the input file containing the TSG graphs.
+/// - `options`: The image width and the optional intron compression.
+/// - `output`: An optional path to the output directory. If not provided, a directory
+///   named `<input_file_stem>_svg` will be created in the same location as the input file.
+pub fn plot<P: AsRef<Path>>(input: P, options: &PlotOptions, output: Option<P>) -> Result<()> {
+    info!("parsing TSG graphs from file: {:?}", input.as_ref());
+    let reader = GraphSectionReader::from_file(input.as_ref())?;
+    let output_path = graph_output_dir(input.as_ref(), "svg", output)?;
+
+    for graph in reader {
+        let graph = graph?;
+        if graph.nodes().iter().all(|node| node.exons.is_empty()) {
+            warn!("Skipping graph {} without exons to plot", graph.id);
+            continue;
+        }
+        let graph_output_file = output_path.join(format!("{}.svg", graph.id));
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(graph_output_file)?);
+        graph.to_svg_with_options(&mut writer, options)?;
+        writer.flush()?;
+    }
+    info!("SVG images written to {}", output_path.display());
+    Ok(())
+}
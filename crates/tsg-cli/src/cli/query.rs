@@ -1,17 +1,20 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Result, anyhow};
 use bstr::BString;
-use tracing::info;
+use tracing::{info, warn};
 
-use tsg::graph::TSGraph;
+use tsg::graph::{InterGraphLink, TSGraph};
+use tsg::io::{IndexedReader, TsgIndex, index_path};
 
 /// Query specific graphs from a TSG file
 ///
 /// This function extracts specific graphs by their IDs from a TSG file
 /// and outputs them in the specified format.
+/// When an up-to-date `.tsi` index exists next to the input, only the
+/// requested graph sections are read instead of parsing the whole file.
 pub fn query(
     input: PathBuf,
     ids_str: String,
@@ -19,7 +22,6 @@ pub fn query(
     output: Option<PathBuf>,
 ) -> Result<()> {
     info!("Querying graphs from TSG file: {}", input.display());
-    let tsg = TSGraph::from_file(&input)?;
 
     // Collect all graph IDs to query
     let mut graph_ids = ids_str
@@ -31,10 +33,9 @@ pub fn query(
     // If an IDs file is provided, read IDs from the file (one per line)
     if let Some(ids_file_path) = ids_file {
         info!("Reading graph IDs from file: {}", ids_file_path.display());
-        let file =
-            File::open(&ids_file_path).map_err(|e| anyhow!("Failed to open IDs file: {}", e))?;
+        let reader = tsg::io::open_reader(&ids_file_path)
+            .map_err(|e| anyhow!("Failed to open IDs file: {}", e))?;
 
-        let reader = BufReader::new(file);
         for line in reader.lines() {
             let id = line?;
             if !id.trim().is_empty() {
@@ -49,33 +50,14 @@ pub fn query(
 
     info!("Querying {} graphs", graph_ids.len());
 
-    // Create a new TSGraph to hold the queried graphs
-    let mut queried_tsg = TSGraph::new();
-
-    // Copy headers from the original TSG
-    queried_tsg.headers = tsg.headers.clone();
-
-    // Process each requested graph ID
-    for id in &graph_ids {
-        let bstring_id = BString::from(id.as_bytes());
-
-        // Check if the graph exists
-        if !tsg.graphs.contains_key(&bstring_id) {
-            return Err(anyhow!("Graph with ID '{}' not found", id));
-        }
-
-        // Copy the graph to the new TSG
-        if let Some(graph) = tsg.graphs.get(&bstring_id) {
-            queried_tsg.graphs.insert(bstring_id.clone(), graph.clone());
-
-            // Copy relevant links
-            for link in &tsg.links {
-                if link.source_graph == bstring_id || link.target_graph == bstring_id {
-                    queried_tsg.links.push(link.clone());
-                }
-            }
+    let index_file = index_path(&input);
+    let queried_tsg = match load_fresh_index(&input, &index_file) {
+        Some(index) => {
+            info!("Using index: {}", index_file.display());
+            query_indexed(&input, index, &graph_ids)?
         }
-    }
+        None => query_parsed(&input, &graph_ids)?,
+    };
 
     // Output the result
     if let Some(output_path) = output {
@@ -91,3 +73,104 @@ pub fn query(
     info!("Query completed successfully");
     Ok(())
 }
+
+/// Load the index of a TSG file if it exists and still describes the file
+///
+/// An index is stale when it is older than the TSG file or was built for a file of another size.
+fn load_fresh_index(input: &Path, index_file: &Path) -> Option<TsgIndex> {
+    let modified = |path: &Path| path.metadata().and_then(|meta| meta.modified()).ok();
+    let (input_modified, index_modified) = (modified(input)?, modified(index_file)?);
+    if index_modified < input_modified {
+        warn!(
+            "Index {} is older than {}, ignoring it",
+            index_file.display(),
+            input.display()
+        );
+        return None;
+    }
+
+    let index = match TsgIndex::load(index_file) {
+        Ok(index) => index,
+        Err(e) => {
+            warn!(
+                "Failed to load index {}, ignoring it: {}",
+                index_file.display(),
+                e
+            );
+            return None;
+        }
+    };
+    if !index.matches_source(input).unwrap_or(false) {
+        warn!(
+            "Index {} was built for a different version of {}, ignoring it",
+            index_file.display(),
+            input.display()
+        );
+        return None;
+    }
+    Some(index)
+}
+
+/// Whether a link touches any of the queried graphs
+fn is_queried_link(link: &InterGraphLink, graph_ids: &[BString]) -> bool {
+    graph_ids.contains(&link.source_graph) || graph_ids.contains(&link.target_graph)
+}
+
+/// Query graphs by seeking to their sections through the index
+fn query_indexed(input: &Path, index: TsgIndex, graph_ids: &[String]) -> Result<TSGraph> {
+    let mut reader = IndexedReader::new(BufReader::new(File::open(input)?), index);
+
+    // Create a new TSGraph to hold the queried graphs
+    let mut queried_tsg = TSGraph::new();
+    queried_tsg.headers = reader.read_headers()?;
+    queried_tsg.comments = reader.read_comments()?;
+
+    let bstring_ids: Vec<BString> = graph_ids.iter().map(|id| id.as_str().into()).collect();
+    for id in graph_ids {
+        if reader.index().graph(id).is_none() {
+            return Err(anyhow!("Graph with ID '{}' not found", id));
+        }
+        let graph = reader.read_graph(id)?;
+        queried_tsg.graphs.insert(graph.id.clone(), graph);
+    }
+
+    queried_tsg.links = reader
+        .read_links()?
+        .into_iter()
+        .filter(|link| is_queried_link(link, &bstring_ids))
+        .collect();
+
+    Ok(queried_tsg)
+}
+
+/// Query graphs by parsing the whole file
+fn query_parsed(input: &Path, graph_ids: &[String]) -> Result<TSGraph> {
+    let tsg = TSGraph::from_file(input)?;
+
+    // Create a new TSGraph to hold the queried graphs
+    let mut queried_tsg = TSGraph::new();
+
+    // Copy headers and comments from the original TSG
+    queried_tsg.headers = tsg.headers.clone();
+    queried_tsg.comments = tsg.comments.clone();
+
+    let bstring_ids: Vec<BString> = graph_ids.iter().map(|id| id.as_str().into()).collect();
+    for bstring_id in &bstring_ids {
+        // Copy the graph to the new TSG
+        let graph = tsg
+            .graphs
+            .get(bstring_id)
+            .ok_or_else(|| anyhow!("Graph with ID '{}' not found", bstring_id))?;
+        queried_tsg.graphs.insert(bstring_id.clone(), graph.clone());
+    }
+
+    // Copy relevant links
+    queried_tsg.links = tsg
+        .links
+        .iter()
+        .filter(|link| is_queried_link(link, &bstring_ids))
+        .cloned()
+        .collect();
+
+    Ok(queried_tsg)
+}
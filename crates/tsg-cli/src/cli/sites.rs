@@ -0,0 +1,57 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde_json::json;
+use tracing::info;
+use tsg::graph::{OrderingPolicy, TSGraph, WriteOptions};
+
+/// Decompose every graph of a TSG file into nested superbubble sites
+///
+/// By default the TSG is written back with a `U` group per site. With `json`, the site
+/// trees of the graphs are written as a JSON array instead.
+pub fn sites<P: AsRef<Path>>(
+    input: P,
+    json: bool,
+    output: Option<PathBuf>,
+    ordering: OrderingPolicy,
+) -> Result<()> {
+    info!(
+        "Decomposing TSG file into sites: {}",
+        input.as_ref().display()
+    );
+    let mut tsg = TSGraph::from_file(input.as_ref())?;
+    let mut writer = match output {
+        Some(path) => tsg::io::create_writer(path)?,
+        None => tsg::io::CompressedWriter::stdout(),
+    };
+
+    let mut graphs = Vec::new();
+    let mut count = 0;
+    for graph in tsg.graphs.values_mut() {
+        let sites = graph.decompose_sites()?;
+        count += sites.iter().map(|site| site.flatten().len()).sum::<usize>();
+        if json {
+            graphs.push(json!({
+                "graph": graph.id.to_string(),
+                "sites": sites.iter().map(|site| site.to_json()).collect::<Vec<_>>(),
+            }));
+        } else {
+            graph.add_site_groups(&sites)?;
+        }
+    }
+    info!("Found {} sites in {} graphs", count, tsg.graphs.len());
+
+    if json {
+        graphs.sort_by(|a, b| a["graph"].as_str().cmp(&b["graph"].as_str()));
+        serde_json::to_writer_pretty(&mut writer, &graphs)?;
+        writeln!(writer)?;
+    } else {
+        tsg.to_writer_with_options(
+            &mut writer,
+            &WriteOptions::builder().ordering(ordering).build(),
+        )?;
+    }
+    writer.finish()?;
+    Ok(())
+}
@@ -1,18 +1,28 @@
+use std::collections::BTreeMap;
+use std::io::BufRead;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use anyhow::{Result, anyhow};
+use bstr::BString;
 use tracing::info;
-use tsg::graph::TSGraph;
+use tsg::graph::{InterGraphLink, TSGraph};
+use tsg::io::{Compression, GraphSectionReader};
 
 /// Split a TSG file containing multiple graphs into multiple TSG files, each containing a single graph
 ///
 /// This function takes a TSG file with multiple graphs and splits it into multiple TSG files,
 /// where each output file contains a single graph from the original file.
 /// The output files will be named based on the graph IDs.
+///
+/// Graphs are streamed one at a time, so memory use does not grow with the input.
+/// Links between elements of the same graph are collected in a first pass over the `L` records
+/// and written together with their graph; links between different graphs cannot be kept in a
+/// single-graph file.
 pub fn split<P: AsRef<Path>>(input: P, output_dir: Option<PathBuf>) -> Result<()> {
-    // Load the input TSG file
     info!("Loading TSG file: {}", input.as_ref().display());
-    let tsg = TSGraph::from_file(input.as_ref())?;
+    let mut links = read_graph_links(input.as_ref())?;
+    let mut reader = GraphSectionReader::from_file(input.as_ref())?;
 
     // if output_dir is None, create a default output directory
     let output_dir = match output_dir {
@@ -34,36 +44,27 @@ pub fn split<P: AsRef<Path>>(input: P, output_dir: Option<PathBuf>) -> Result<()
         info!("Created output directory: {}", output_dir.display());
     }
 
-    // Check if there are any graphs to split
-    if tsg.graphs.is_empty() {
-        return Err(anyhow!("No graphs found in the input TSG file"));
-    }
+    let compression = Compression::from_path(input.as_ref());
+    let mut graph_count = 0;
 
-    info!("Found {} graphs to split", tsg.graphs.len());
+    // Process each graph as it is read
+    while let Some(graph) = reader.next() {
+        let graph = graph?;
+        let graph_id = graph.id.clone();
 
-    // Process each graph in the input TSG
-    for (graph_id, graph) in &tsg.graphs {
-        // Create a new TSGraph for this single graph
+        // Create a new TSGraph for this single graph with the headers and comments read so far
         let mut single_graph_tsg = TSGraph::new();
+        single_graph_tsg.headers = reader.headers().to_vec();
+        single_graph_tsg.comments = reader.comments().to_vec();
+        single_graph_tsg.graphs.insert(graph_id.clone(), graph);
+        single_graph_tsg.links = links.remove(&graph_id).unwrap_or_default();
 
-        // Copy the headers from the original TSG
-        single_graph_tsg.headers = tsg.headers.clone();
-
-        // Add the current graph to the new TSGraph
-        single_graph_tsg
-            .graphs
-            .insert(graph_id.clone(), graph.clone());
-
-        // Filter links that are relevant to this graph
-        for link in &tsg.links {
-            if link.source_graph == *graph_id || link.target_graph == *graph_id {
-                single_graph_tsg.links.push(link.clone());
-            }
-        }
-
-        // Create the output file path
+        // Create the output file path, keeping the compression of the input file
         let graph_id_str = graph_id.to_string();
-        let output_file = output_dir.join(format!("{}.tsg", graph_id_str));
+        let output_file = match compression.extension() {
+            Some(ext) => output_dir.join(format!("{}.tsg.{}", graph_id_str, ext)),
+            None => output_dir.join(format!("{}.tsg", graph_id_str)),
+        };
 
         // Write the single-graph TSG to a file
         info!(
@@ -72,8 +73,36 @@ pub fn split<P: AsRef<Path>>(input: P, output_dir: Option<PathBuf>) -> Result<()
             output_file.display()
         );
         single_graph_tsg.to_file(&output_file)?;
+        graph_count += 1;
+    }
+
+    // Check if there are any graphs to split
+    if graph_count == 0 {
+        return Err(anyhow!("No graphs found in the input TSG file"));
     }
+    info!("Split {} graphs", graph_count);
 
     info!("Split completed successfully");
     Ok(())
 }
+
+/// Collect the links whose both ends are in the same graph, keyed by that graph
+///
+/// Only `L` records are parsed, so this pass stays cheap on large inputs.
+fn read_graph_links(input: &Path) -> Result<BTreeMap<BString, Vec<InterGraphLink>>> {
+    let mut links: BTreeMap<BString, Vec<InterGraphLink>> = BTreeMap::new();
+    for (idx, line) in tsg::io::open_reader(input)?.lines().enumerate() {
+        let line = line?;
+        if line.split_whitespace().next() != Some("L") {
+            continue;
+        }
+        let link = InterGraphLink::from_str(&line).map_err(|e| e.at_line(idx + 1))?;
+        if link.source_graph == link.target_graph {
+            links
+                .entry(link.source_graph.clone())
+                .or_default()
+                .push(link);
+        }
+    }
+    Ok(links)
+}
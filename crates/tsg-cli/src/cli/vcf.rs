@@ -1,22 +1,64 @@
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
-use std::io::Write;
 use tracing::info;
-use tsg::graph::TSGraph;
+use tsg::graph::{OrderingPolicy, TSGraph, WriteOptions};
+use tsg::io::{GraphSectionReader, VcfCollector};
 
-pub fn to_vcf<P: AsRef<Path>>(input: P, output: Option<PathBuf>) -> Result<()> {
-    let tsg_graph = TSGraph::from_file(input.as_ref())?;
-    let mut writer: Box<dyn Write> = match output {
+/// Convert the edges of a TSG file to a position-sorted VCF 4.3 file
+///
+/// Graph sections are streamed and only their records are kept until the file is written.
+/// Reference bases are read from `reference` when given, which must be indexed.
+pub fn to_vcf<P: AsRef<Path>>(
+    input: P,
+    reference: Option<PathBuf>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let mut collector = VcfCollector::new();
+    for graph in GraphSectionReader::from_file(input.as_ref())? {
+        collector.add_graph(&graph?);
+    }
+    let mut writer = match output {
         Some(path) => {
             info!("Writing to file: {:?}", path);
-            Box::new(std::io::BufWriter::new(std::fs::File::create(path)?))
+            tsg::io::create_writer(path)?
         }
         None => {
             info!("Writing to stdout");
-            Box::new(std::io::BufWriter::new(std::io::stdout().lock()))
+            tsg::io::CompressedWriter::stdout()
         }
     };
-    tsg::io::to_vcf(&tsg_graph, &mut writer)?;
+    collector.write(&mut writer, reference.as_deref())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Merge the structural variants of a VCF file into a TSG file
+pub fn import_vcf<P: AsRef<Path>>(
+    input: P,
+    vcf: PathBuf,
+    graph: Option<String>,
+    output: Option<PathBuf>,
+    ordering: OrderingPolicy,
+) -> Result<()> {
+    let mut tsg = TSGraph::from_file(input.as_ref())?;
+    let count = tsg.import_vcf(tsg::io::open_reader(vcf)?, graph.as_deref())?;
+    info!("Imported {} structural variants", count);
+
+    let mut writer = match output {
+        Some(path) => {
+            info!("Writing to file: {:?}", path);
+            tsg::io::create_writer(path)?
+        }
+        None => {
+            info!("Writing to stdout");
+            tsg::io::CompressedWriter::stdout()
+        }
+    };
+    tsg.to_writer_with_options(
+        &mut writer,
+        &WriteOptions::builder().ordering(ordering).build(),
+    )?;
+    writer.finish()?;
     Ok(())
 }
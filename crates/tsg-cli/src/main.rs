@@ -5,11 +5,11 @@ use tracing::info;
 use anyhow::Result;
 use clap::{Command, CommandFactory, Parser};
 use cli::Commands;
-use tsg::graph::TSGraph;
 
 use clap_complete::aot::{Generator, Shell, generate};
-use colored::Colorize;
 use std::io::stdout;
+use tsg::graph::PlotOptions;
+use tsg::io::BedMode;
 
 #[derive(Parser)]
 #[command(author, version, about = "Transcript Segment Graph (TSG) CLI tool")]
@@ -63,23 +63,18 @@ fn run() -> Result<()> {
     tracing_subscriber::fmt().with_max_level(cli.verbose).init();
 
     match command {
-        Commands::Parse { input } => {
-            info!("Parsing TSG file: {}", input.display());
-            let graph = TSGraph::from_file(input)?;
-
-            for (id, graph) in graph.graphs.iter() {
-                println!(
-                    "Successfully parsed graph {} with {} nodes and {} edges",
-                    id.to_string().green(),
-                    graph.nodes().len(),
-                    graph.edges().len()
-                );
-            }
+        Commands::Parse { input, lenient } => {
+            cli::parse(input, lenient)?;
             Ok(())
         }
 
-        Commands::Dot { input, output } => {
-            cli::to_dot(input, output)?;
+        Commands::Dot {
+            input,
+            format,
+            highlight,
+            output,
+        } => {
+            cli::to_dot(input, format, highlight, output)?;
             Ok(())
         }
 
@@ -87,42 +82,160 @@ fn run() -> Result<()> {
             input,
             text_path,
             output,
+            ordering,
         } => {
-            cli::traverse(input, text_path, output)?;
+            cli::traverse(input, text_path, output, ordering)?;
             Ok(())
         }
 
-        Commands::Fa { input, output } => {
+        Commands::Fa {
+            input,
+            output,
+            ordering,
+        } => {
             info!("Converting TSG file to FASTA: {}", input.display());
-            cli::to_fa(input, output)?;
+            cli::to_fa(input, output, ordering)?;
             Ok(())
         }
 
-        Commands::Gtf { input, output } => {
+        Commands::Gtf {
+            input,
+            output,
+            ordering,
+        } => {
             info!("Converting TSG file to GTF: {}", input.display());
-            cli::to_gtf(input, output)?;
+            cli::to_gtf(input, output, ordering)?;
+            Ok(())
+        }
+
+        Commands::Gff3 {
+            input,
+            output,
+            ordering,
+        } => {
+            info!("Converting TSG file to GFF3: {}", input.display());
+            cli::to_gff3(input, output, ordering)?;
+            Ok(())
+        }
+
+        Commands::FromGtf {
+            input,
+            output,
+            ordering,
+        } => {
+            info!("Building TSG file from annotation: {}", input.display());
+            cli::from_gtf(input, output, ordering)?;
+            Ok(())
+        }
+
+        Commands::Bed {
+            input,
+            paths,
+            nodes,
+            edges,
+            score,
+            output,
+            ordering,
+        } => {
+            info!("Converting TSG file to BED: {}", input.display());
+            // The modes are exclusive, and paths are written when none is given
+            let mode = match (paths, nodes, edges) {
+                (_, true, _) => BedMode::Nodes,
+                (_, _, true) => BedMode::Edges,
+                (true, _, _) | (false, false, false) => BedMode::Paths,
+            };
+            cli::to_bed(input, mode, score, output, ordering)?;
+            Ok(())
+        }
+
+        Commands::Vcf {
+            input,
+            import,
+            graph,
+            reference,
+            output,
+            ordering,
+        } => {
+            if let Some(vcf) = import {
+                info!("Importing VCF file into TSG: {}", vcf.display());
+                cli::import_vcf(input, vcf, graph, output, ordering)?;
+            } else {
+                info!("Converting TSG file to VCF: {}", input.display());
+                cli::to_vcf(input, reference, output)?;
+            }
             Ok(())
         }
 
-        Commands::Vcf { input, output } => {
-            info!("Converting TSG file to VCF: {}", input.display());
-            cli::to_vcf(input, output)?;
+        Commands::Gfa {
+            input,
+            gfa_version,
+            output,
+        } => {
+            info!("Converting TSG file to GFA: {}", input.display());
+            cli::to_gfa(input, gfa_version, output)?;
+            Ok(())
+        }
+
+        Commands::Analyze {
+            input,
+            bubbles,
+            json,
+            output,
+        } => {
+            cli::analyze(input, bubbles, json, output)?;
+            Ok(())
+        }
+
+        Commands::Sites {
+            input,
+            json,
+            output,
+            ordering,
+        } => {
+            cli::sites(input, json, output, ordering)?;
+            Ok(())
+        }
+
+        Commands::Events { input, output } => {
+            cli::events(input, output)?;
+            Ok(())
+        }
+
+        Commands::Plot {
+            input,
+            width,
+            max_intron,
+            output,
+        } => {
+            info!("Plotting TSG file: {}", input.display());
+            let options = PlotOptions::builder()
+                .width(width)
+                .maybe_max_intron(max_intron)
+                .build();
+            cli::plot(input, &options, output)?;
             Ok(())
         }
 
         Commands::Json {
             input,
+            schema,
+            format,
             pretty,
             output,
+            ordering,
         } => {
             info!("Converting TSG file to JSON: {}", input.display());
-            cli::to_json(input, pretty, output)?;
+            cli::to_json(input, schema, format, pretty, output, ordering)?;
             Ok(())
         }
 
-        Commands::Merge { inputs, output } => {
+        Commands::Merge {
+            inputs,
+            output,
+            ordering,
+        } => {
             info!("Merging TSG files: {:?}", inputs);
-            cli::merge(inputs, output)?;
+            cli::merge(inputs, output, ordering)?;
             Ok(())
         }
 
@@ -142,6 +255,23 @@ fn run() -> Result<()> {
             cli::query(input, ids, ids_file, output)?;
             Ok(())
         }
+
+        Commands::Index { input, output } => {
+            cli::index(input, output)?;
+            Ok(())
+        }
+
+        Commands::Convert {
+            input,
+            from,
+            to,
+            output,
+            ordering,
+        } => {
+            info!("Converting TSG file: {}", input.display());
+            cli::convert(input, from, to, output, ordering)?;
+            Ok(())
+        }
     }
 }
 
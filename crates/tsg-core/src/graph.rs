@@ -1,51 +1,98 @@
 mod analysis;
 mod attr;
 mod edge;
+mod edit;
+mod error;
+mod event;
 mod group;
 mod header;
 mod node;
+mod options;
 mod path;
+#[cfg(feature = "serde")]
+mod serialization;
+mod site;
 mod utils;
 
+use crate::io::{create_writer, open_reader};
 use noodles::fasta;
-use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter};
+use std::fmt;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::str::FromStr;
 use tracing::debug;
 use tracing::warn;
 
-use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
-use anyhow::{Context, Result, anyhow};
+use ahash::{HashMap, HashMapExt, HashSet};
+use anyhow::{Result, anyhow};
 use bstr::{BStr, BString, ByteSlice};
 
+pub use analysis::*;
 pub use attr::*;
 pub use edge::*;
+pub use error::*;
+pub use event::*;
 pub use group::*;
 pub use header::*;
 pub use node::*;
+pub use options::*;
 pub use path::*;
+pub use site::*;
 pub use utils::*;
 
 use bon::Builder;
-use petgraph::dot::{Config, Dot};
+use indexmap::IndexMap;
 use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
 use petgraph::visit::EdgeRef;
 use rayon::prelude::*;
 use serde_json::json;
 use std::collections::VecDeque;
+use std::sync::OnceLock;
 
 pub const DEFAULT_GRAPH_ID: &str = "graph";
+
+/// Map that iterates in insertion order, which [`OrderingPolicy::Insertion`] follows
+pub type OrderedMap<K, V> = IndexMap<K, V, ahash::RandomState>;
+
 /// Represents a graph section within the TSG file
 #[derive(Debug, Clone, Default, Builder)]
 pub struct GraphSection {
     pub id: BString,
     pub attributes: HashMap<BString, Attribute>,
     _graph: DiGraph<NodeData, EdgeData>,
-    pub node_indices: HashMap<BString, NodeIndex>,
-    pub edge_indices: HashMap<BString, EdgeIndex>,
-    pub groups: HashMap<BString, Group>,
-    pub chains: HashMap<BString, Group>,
+    /// Index of each node and edge ID in the order they were added, kept in sync by the
+    /// `add_*`, `remove_*` and `rename_*` methods; read through [`GraphSection::node_indices`]
+    /// and [`GraphSection::edge_indices`]
+    node_indices: OrderedMap<BString, NodeIndex>,
+    edge_indices: OrderedMap<BString, EdgeIndex>,
+    /// Groups in the order they were added, with the chains among them also kept by ID;
+    /// changed only through [`GraphSection::add_group`] and the `remove_*` and `rename_*`
+    /// methods, read through [`GraphSection::groups`] and [`GraphSection::chains`]
+    groups: OrderedMap<BString, Group>,
+    chains: HashMap<BString, Group>,
+    /// Comment lines of the section without their leading `#`, written after the `G` record
+    #[builder(default)]
+    pub comments: Vec<BString>,
+    /// Node and edge indices in sorted order, computed on first use and reset whenever
+    /// nodes or edges change
+    #[builder(skip)]
+    sorted_indices: OnceLock<(Vec<NodeIndex>, Vec<EdgeIndex>)>,
+}
+
+/// Rename a key of an ordered map, keeping its position
+fn rename_key<V>(map: &mut OrderedMap<BString, V>, id: &BStr, new_id: BString) {
+    if let Some(position) = map.get_index_of(id)
+        && let Some((_, value)) = map.shift_remove_index(position)
+    {
+        map.shift_insert(position, new_id, value);
+    }
+}
+
+/// Sort key placing nodes by genomic position, then by ID
+fn node_position_key(node: &NodeData) -> (&BString, usize, usize, &BString) {
+    let start = node.exons.exons.first().map_or(0, |exon| exon.start);
+    let end = node.exons.exons.last().map_or(0, |exon| exon.end);
+    (&node.reference_id, start, end, &node.id)
 }
 
 impl GraphSection {
@@ -61,9 +108,30 @@ impl GraphSection {
         Self::new(DEFAULT_GRAPH_ID.into())
     }
 
+    /// Index of each node ID, in the order the nodes were added
+    pub fn node_indices(&self) -> &OrderedMap<BString, NodeIndex> {
+        &self.node_indices
+    }
+
+    /// Index of each edge ID, in the order the edges were added
+    pub fn edge_indices(&self) -> &OrderedMap<BString, EdgeIndex> {
+        &self.edge_indices
+    }
+
+    /// Groups by ID, in the order they were added
+    pub fn groups(&self) -> &OrderedMap<BString, Group> {
+        &self.groups
+    }
+
+    /// Chains by ID, which are also part of [`GraphSection::groups`]
+    pub fn chains(&self) -> &HashMap<BString, Group> {
+        &self.chains
+    }
+
     /// Add a node to the graph
     pub fn add_node(&mut self, node_data: NodeData) -> Result<NodeIndex> {
         let id = node_data.id.clone();
+        self.invalidate_sorted_indices();
 
         // Check if node already exists
         if let Some(&idx) = self.node_indices.get(&id) {
@@ -90,13 +158,34 @@ impl GraphSection {
     }
 
     /// Add an edge to the graph
+    ///
+    /// An edge between nodes that another edge already connects is kept as a parallel
+    /// edge; see [`GraphSection::add_edge_with_policy`] for the alternatives. Adding an edge
+    /// with the ID of an existing edge between the same nodes updates its data.
     pub fn add_edge(
         &mut self,
         source_id: &BStr,
         sink_id: &BStr,
         edge_data: EdgeData,
+    ) -> Result<EdgeIndex> {
+        self.add_edge_with_policy(source_id, sink_id, edge_data, EdgeConflictPolicy::Keep)
+    }
+
+    /// Add an edge to the graph, resolving conflicts with edges between the same nodes
+    /// by `policy`
+    ///
+    /// A replaced edge is the one given by [`GraphSection::first_edge_between`]. Its groups
+    /// follow it to the new ID, which must not be used by a node or group; use
+    /// [`TSGraph::add_edge_with_policy`] to have the links of the graph follow it too.
+    pub fn add_edge_with_policy(
+        &mut self,
+        source_id: &BStr,
+        sink_id: &BStr,
+        edge_data: EdgeData,
+        policy: EdgeConflictPolicy,
     ) -> Result<EdgeIndex> {
         let id = edge_data.id.clone();
+        self.invalidate_sorted_indices();
 
         // Get source node index or create it if it doesn't exist
         let source_idx = match self.node_indices.get(source_id) {
@@ -124,17 +213,61 @@ impl GraphSection {
             }
         };
 
-        // petgraph provide update_edge method to update edge data
-        let edge_idx = self._graph.update_edge(source_idx, sink_idx, edge_data);
+        if let Some(&edge_idx) = self.edge_indices.get(&id) {
+            if self._graph.edge_endpoints(edge_idx) != Some((source_idx, sink_idx)) {
+                return Err(anyhow!(
+                    "Edge with ID {} already connects other nodes in graph {}",
+                    id,
+                    self.id
+                ));
+            }
+            self._graph[edge_idx] = edge_data;
+            return Ok(edge_idx);
+        }
+
+        let existing = self.first_edge_between(source_idx, sink_idx);
+        let edge_idx = match (existing, policy) {
+            (Some(edge_idx), EdgeConflictPolicy::Replace) => {
+                self.check_new_id(&id)?;
+                let old = std::mem::replace(&mut self._graph[edge_idx], edge_data);
+                rename_key(&mut self.edge_indices, old.id.as_ref(), id.clone());
+                self.rename_element(old.id.as_ref(), &id);
+                return Ok(edge_idx);
+            }
+            (Some(edge_idx), EdgeConflictPolicy::Error) => {
+                return Err(anyhow!(
+                    "Nodes {} and {} are already connected by edge {} in graph {}",
+                    source_id,
+                    sink_id,
+                    self._graph[edge_idx].id,
+                    self.id
+                ));
+            }
+            _ => self._graph.add_edge(source_idx, sink_idx, edge_data),
+        };
 
         self.edge_indices.insert(id, edge_idx);
         Ok(edge_idx)
     }
 
+    /// The earliest added of the edges from `source` to `sink`
+    ///
+    /// This is the edge that [`EdgeConflictPolicy::Replace`] overwrites and that a path
+    /// listing only its nodes is taken to follow when parallel edges connect them.
+    pub fn first_edge_between(&self, source: NodeIndex, sink: NodeIndex) -> Option<EdgeIndex> {
+        self._graph
+            .edges_connecting(source, sink)
+            .map(|edge| edge.id())
+            .min_by_key(|&idx| self.edge_indices.get_index_of(&self._graph[idx].id))
+    }
+
     // Methods from old TSGraph that should now belong to GraphSection
 
     /// Build graph based on the current state
-    fn ensure_graph_is_built(&mut self) -> Result<()> {
+    ///
+    /// Nodes and edges missing from the graph are created from its chains; build failures
+    /// are reported at the line of the chain that caused them.
+    fn ensure_graph_is_built(&mut self, lines: &RecordLines) -> Result<(), TsgError> {
         // If we already have nodes and edges, assume the graph is properly constructed
         if !self.node_indices.is_empty() && !self.edge_indices.is_empty() {
             return Ok(());
@@ -142,38 +275,18 @@ impl GraphSection {
 
         // If nodes and edges are missing, build them from chains
         if !self.chains.is_empty() {
-            for group in self.chains.clone().values() {
+            let mut chains: Vec<(BString, Group)> = self
+                .chains
+                .iter()
+                .map(|(id, group)| (id.clone(), group.clone()))
+                .collect();
+            chains.sort_by(|a, b| a.0.cmp(&b.0));
+            for (chain_id, group) in chains {
                 if let Group::Chain { elements, .. } = group {
-                    // Process each element in the chain
-                    for (i, element_id) in elements.iter().enumerate() {
-                        if i % 2 == 0 {
-                            // It's a node - add it if it doesn't exist
-                            if !self.node_indices.contains_key(element_id) {
-                                // Create placeholder node
-                                let node_data = NodeData {
-                                    id: element_id.clone(),
-                                    ..Default::default()
-                                };
-                                self.add_node(node_data)?;
-                            }
-                        } else if i + 1 < elements.len() {
-                            // Prevent index out of bounds
-                            // It's an edge - add it if it doesn't exist
-                            if !self.edge_indices.contains_key(element_id) {
-                                // Get connecting nodes
-                                let source_id = &elements[i - 1];
-                                let sink_id = &elements[i + 1];
-
-                                // Create placeholder edge
-                                let edge_data = EdgeData {
-                                    id: element_id.clone(),
-                                    ..Default::default()
-                                };
-
-                                self.add_edge(source_id.as_bstr(), sink_id.as_bstr(), edge_data)?;
-                            }
-                        }
-                    }
+                    self.build_chain(&elements).map_err(|e| {
+                        TsgError::malformed("C", "elements", e)
+                            .at_line(lines.chain_line(&self.id, &chain_id))
+                    })?;
                 }
             }
             return Ok(());
@@ -194,6 +307,260 @@ impl GraphSection {
         Ok(())
     }
 
+    /// Add the nodes and edges of a chain that are missing from the graph
+    fn build_chain(&mut self, elements: &[BString]) -> Result<()> {
+        for (i, element_id) in elements.iter().enumerate() {
+            if i % 2 == 0 {
+                // It's a node - add it if it doesn't exist
+                if !self.node_indices.contains_key(element_id) {
+                    // Create placeholder node
+                    let node_data = NodeData {
+                        id: element_id.clone(),
+                        ..Default::default()
+                    };
+                    self.add_node(node_data)?;
+                }
+            } else if i + 1 < elements.len() {
+                // Prevent index out of bounds
+                // It's an edge - add it if it doesn't exist
+                let source_id = &elements[i - 1];
+                let sink_id = &elements[i + 1];
+                if let Some(&edge_idx) = self.edge_indices.get(element_id) {
+                    let (source, sink) = self._graph.edge_endpoints(edge_idx).unwrap();
+                    if self._graph[source].id != *source_id || self._graph[sink].id != *sink_id {
+                        return Err(anyhow!(
+                            "edge {} connects {} to {}, not {} to {}",
+                            element_id,
+                            self._graph[source].id,
+                            self._graph[sink].id,
+                            source_id,
+                            sink_id
+                        ));
+                    }
+                } else {
+                    // Create placeholder edge
+                    let edge_data = EdgeData {
+                        id: element_id.clone(),
+                        ..Default::default()
+                    };
+                    self.add_edge(source_id.as_bstr(), sink_id.as_bstr(), edge_data)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a group under the given ID, replacing any group with the same ID
+    ///
+    /// Chains are also registered in [`GraphSection::chains`].
+    pub fn add_group(&mut self, id: BString, group: Group) {
+        if let Group::Chain { .. } = group {
+            self.chains.insert(id.clone(), group.clone());
+        }
+        self.groups.insert(id, group);
+    }
+
+    /// Forget the sorted order after nodes or edges were added, removed or renamed
+    fn invalidate_sorted_indices(&mut self) {
+        self.sorted_indices.take();
+    }
+
+    /// Node and edge indices in sorted order, see [`OrderingPolicy::Sorted`]
+    ///
+    /// Sorted edges follow the sorted order of their source and then their target node.
+    fn sorted_indices(&self) -> &(Vec<NodeIndex>, Vec<EdgeIndex>) {
+        self.sorted_indices.get_or_init(|| {
+            let mut nodes: Vec<_> = self._graph.node_indices().collect();
+            nodes.sort_by(|&a, &b| {
+                node_position_key(&self._graph[a]).cmp(&node_position_key(&self._graph[b]))
+            });
+
+            let mut rank = vec![0; self._graph.node_count()];
+            for (position, idx) in nodes.iter().enumerate() {
+                rank[idx.index()] = position;
+            }
+            let mut edges: Vec<_> = self._graph.edge_indices().collect();
+            edges.sort_by_key(|&idx| {
+                let (source, target) = self._graph.edge_endpoints(idx).unwrap();
+                (
+                    rank[source.index()],
+                    rank[target.index()],
+                    &self._graph[idx].id,
+                )
+            });
+            (nodes, edges)
+        })
+    }
+
+    /// Node indices in the given order
+    ///
+    /// The insertion order survives removals and renames, which reuse petgraph indices.
+    pub fn ordered_node_indices(&self, ordering: OrderingPolicy) -> Vec<NodeIndex> {
+        match ordering {
+            OrderingPolicy::Sorted => self.sorted_indices().0.clone(),
+            OrderingPolicy::Insertion => self.node_indices.values().copied().collect(),
+        }
+    }
+
+    /// Edge indices in the given order
+    ///
+    /// Sorted edges follow the sorted order of their source and then their target node.
+    /// The insertion order survives removals and renames, which reuse petgraph indices.
+    pub fn ordered_edge_indices(&self, ordering: OrderingPolicy) -> Vec<EdgeIndex> {
+        match ordering {
+            OrderingPolicy::Sorted => self.sorted_indices().1.clone(),
+            OrderingPolicy::Insertion => self.edge_indices.values().copied().collect(),
+        }
+    }
+
+    /// Groups in the given order
+    pub fn ordered_groups(&self, ordering: OrderingPolicy) -> Vec<(&BString, &Group)> {
+        let mut groups: Vec<_> = self.groups.iter().collect();
+        if ordering == OrderingPolicy::Sorted {
+            groups.sort_by(|a, b| a.0.cmp(b.0));
+        }
+        groups
+    }
+
+    /// Whether the section has no nodes, edges, groups or attributes
+    fn is_empty(&self) -> bool {
+        self.node_indices.is_empty()
+            && self.edge_indices.is_empty()
+            && self.groups.is_empty()
+            && self.attributes.is_empty()
+    }
+
+    /// Whether a node, edge or group with the given ID exists in this graph
+    pub(crate) fn contains_element(&self, id: &BString) -> bool {
+        self.node_indices.contains_key(id)
+            || self.edge_indices.contains_key(id)
+            || self.groups.contains_key(id)
+    }
+
+    /// The underlying petgraph graph, for encoders that need the graph topology
+    pub(crate) fn inner_graph(&self) -> &DiGraph<NodeData, EdgeData> {
+        &self._graph
+    }
+
+    /// Write the records of this graph section
+    ///
+    /// The default graph is written without a `G` record so that it is picked up as the
+    /// records preceding the first graph section; its attributes are written as `A G` records.
+    pub fn to_writer<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        self.to_writer_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Write the records of this graph section in the order given by `options`
+    pub fn to_writer_with_options<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<()> {
+        let is_default = self.id == DEFAULT_GRAPH_ID;
+        // Skip writing the default graph if it's empty
+        if is_default
+            && self.node_indices.is_empty()
+            && self.groups.is_empty()
+            && self.comments.is_empty()
+        {
+            return Ok(());
+        }
+
+        writeln!(writer, "\n# Graph: {}", self.id)?;
+        if !is_default {
+            write!(writer, "G\t{}", self.id)?;
+            for attr in sorted_attributes(&self.attributes) {
+                write!(writer, "\t{}", attr)?;
+            }
+            writeln!(writer)?;
+        }
+        for comment in &self.comments {
+            writeln!(writer, "#{}", comment)?;
+        }
+
+        let nodes: Vec<_> = self
+            .ordered_node_indices(options.ordering)
+            .into_iter()
+            .map(|idx| &self._graph[idx])
+            .collect();
+        let edges = self.ordered_edge_indices(options.ordering);
+        let groups = self.ordered_groups(options.ordering);
+
+        // Write nodes
+        writeln!(writer, "# Nodes")?;
+        for node in &nodes {
+            writeln!(writer, "{}", node)?;
+        }
+
+        // Write edges
+        writeln!(writer, "# Edges")?;
+        for &edge_idx in &edges {
+            let edge = &self._graph[edge_idx];
+            let (source, sink) = self._graph.edge_endpoints(edge_idx).unwrap();
+            writeln!(
+                writer,
+                "E\t{}\t{}\t{}\t{}",
+                edge.id, self._graph[source].id, self._graph[sink].id, edge.sv
+            )?;
+        }
+
+        // Write groups
+        writeln!(writer, "# Groups")?;
+        for (id, group) in &groups {
+            match group {
+                Group::Unordered { elements, .. } => {
+                    writeln!(writer, "U\t{}\t{}", id, elements.join(&b" "[..]).as_bstr())?;
+                }
+                Group::Ordered { elements, .. } => {
+                    let elements_str: Vec<String> =
+                        elements.iter().map(|e| e.to_string()).collect();
+                    writeln!(writer, "P\t{}\t{}", id, elements_str.join(" "))?;
+                }
+                Group::Chain { elements, .. } => {
+                    writeln!(writer, "C\t{}\t{}", id, elements.join(&b" "[..]).as_bstr())?;
+                }
+            }
+        }
+
+        // Write attributes for this graph section
+        writeln!(writer, "# Attributes")?;
+
+        if is_default {
+            for attr in sorted_attributes(&self.attributes) {
+                writeln!(writer, "A\tG\t{}\t{}", self.id, attr)?;
+            }
+        }
+
+        // Write attributes for nodes
+        for node in nodes {
+            for attr in sorted_attributes(&node.attributes) {
+                writeln!(writer, "A\tN\t{}\t{}", node.id, attr)?;
+            }
+        }
+
+        // Write attributes for edges
+        for edge in edges.into_iter().map(|idx| &self._graph[idx]) {
+            for attr in sorted_attributes(&edge.attributes) {
+                writeln!(writer, "A\tE\t{}\t{}", edge.id, attr)?;
+            }
+        }
+
+        // Write attributes for groups
+        for (id, group) in groups {
+            let (group_type, attributes) = match group {
+                Group::Unordered { attributes, .. } => ("U", attributes),
+                Group::Ordered { attributes, .. } => ("P", attributes),
+                Group::Chain { attributes, .. } => ("C", attributes),
+            };
+
+            for attr in sorted_attributes(attributes) {
+                writeln!(writer, "A\t{}\t{}\t{}", group_type, id, attr)?;
+            }
+        }
+
+        Ok(())
+    }
+
     // Additional GraphSection methods...
     pub fn node_by_idx(&self, node_idx: NodeIndex) -> Option<&NodeData> {
         self._graph.node_weight(node_idx)
@@ -213,17 +580,25 @@ impl GraphSection {
         self._graph.edge_weight(edge_idx)
     }
 
+    /// All nodes, ordered by genomic position and then ID
+    ///
+    /// The order is computed once and reused until the graph changes.
     pub fn nodes(&self) -> Vec<&NodeData> {
-        self.node_indices
-            .values()
-            .map(|&idx| self._graph.node_weight(idx).unwrap())
+        self.sorted_indices()
+            .0
+            .iter()
+            .map(|&idx| &self._graph[idx])
             .collect()
     }
 
+    /// All edges, ordered by their source and target nodes
+    ///
+    /// The order is computed once and reused until the graph changes.
     pub fn edges(&self) -> Vec<&EdgeData> {
-        self.edge_indices
-            .values()
-            .map(|&idx| self._graph.edge_weight(idx).unwrap())
+        self.sorted_indices()
+            .1
+            .iter()
+            .map(|&idx| &self._graph[idx])
             .collect()
     }
 
@@ -254,7 +629,14 @@ impl GraphSection {
     /// These paths would be invalid:
     /// - n1 -> n3 -> n5 (invalid because n1 and n5 don't share a common read)
     /// - n2 -> n3 -> n4 (invalid because n2 and n4 don't share a common read)
-    pub fn traverse(&self) -> Result<Vec<TSGPath>> {
+    ///
+    /// Paths are returned ordered by ID; see [`GraphSection::traverse_with_ordering`].
+    pub fn traverse(&self) -> Result<Vec<TSGPath<'_>>> {
+        self.traverse_with_ordering(OrderingPolicy::Sorted)
+    }
+
+    /// Traverse the graph, returning paths sorted by ID or in the order they were found
+    pub fn traverse_with_ordering(&self, ordering: OrderingPolicy) -> Result<Vec<TSGPath<'_>>> {
         // Find all source nodes (nodes with no incoming edges)
         let source_nodes: Vec<NodeIndex> = self
             ._graph
@@ -381,81 +763,100 @@ impl GraphSection {
             }
         }
 
+        if ordering == OrderingPolicy::Sorted {
+            all_paths.sort_by_cached_key(|path| path.id().unwrap_or_default());
+        }
         Ok(all_paths)
     }
 
-    pub fn to_dot(&self, node_label: bool, edge_label: bool) -> Result<String> {
-        let mut config = vec![];
-        if node_label {
-            config.push(Config::NodeIndexLabel);
-        }
-        if edge_label {
-            config.push(Config::EdgeIndexLabel);
-        }
-
-        let dot = Dot::with_config(&self._graph, &config);
-        Ok(format!("{:?}", dot))
+    /// Number of reads shared by the source and target of an edge
+    pub fn edge_weight(&self, edge_idx: EdgeIndex) -> usize {
+        let Some((source, target)) = self._graph.edge_endpoints(edge_idx) else {
+            return 0;
+        };
+        let source_reads: HashSet<&BString> =
+            self._graph[source].reads.iter().map(|r| &r.id).collect();
+        self._graph[target]
+            .reads
+            .iter()
+            .map(|r| &r.id)
+            .collect::<HashSet<_>>()
+            .intersection(&source_reads)
+            .count()
     }
 
     pub fn to_json(&self) -> Result<serde_json::Value> {
+        self.to_json_with_options(&WriteOptions::default())
+    }
+
+    /// Convert the graph to Cytoscape JSON, listing elements in the order of `options`
+    ///
+    /// Besides their TSG fields, elements carry styling hints that the bundled `style`
+    /// sheet maps to Cytoscape.js properties: nodes have a `read_count`, a `size` growing
+    /// with it and a `color` by strand, and edges a `width` growing with their `weight`,
+    /// the number of reads shared by both ends.
+    pub fn to_json_with_options(&self, options: &WriteOptions) -> Result<serde_json::Value> {
         let mut nodes = Vec::new();
         let mut edges = Vec::new();
 
+        let max_reads = self
+            ._graph
+            .node_weights()
+            .map(|node| node.reads.len())
+            .max()
+            .unwrap_or_default();
+
         // Process all nodes
-        for node_idx in self._graph.node_indices() {
-            if let Some(node) = self._graph.node_weight(node_idx) {
-                if let Ok(node_json) = node.to_json(None) {
-                    nodes.push(node_json);
+        for node_idx in self.ordered_node_indices(options.ordering) {
+            if let Some(node) = self._graph.node_weight(node_idx)
+                && let Ok(mut node_json) = node.to_json(None)
+            {
+                let read_count = node.reads.len();
+                node_json["data"]["read_count"] = read_count.into();
+                node_json["data"]["size"] =
+                    scale_style(read_count, max_reads, NODE_SIZE_RANGE).into();
+                node_json["data"]["color"] = match node.strand {
+                    Strand::Forward => FORWARD_COLOR,
+                    Strand::Reverse => REVERSE_COLOR,
                 }
+                .into();
+                nodes.push(node_json);
             }
         }
 
         // Process all edges
-        for edge_idx in self._graph.edge_indices() {
-            if let Some(edge) = self._graph.edge_weight(edge_idx) {
-                let edge_endpoints = self._graph.edge_endpoints(edge_idx);
-
-                if let Some((source, target)) = edge_endpoints {
-                    let source_id = self.find_node_id_by_idx(source);
-                    let target_id = self.find_node_id_by_idx(target);
-
-                    // get reads from source node and target node
-                    // the weight will be the intersection of reads
-                    let source_data = self.node_by_idx(source).unwrap();
-                    let target_data = self.node_by_idx(target).unwrap();
-
-                    // get the intersection of reads
-                    let source_reads = source_data
-                        .reads
-                        .iter()
-                        .map(|r| r.id.clone())
-                        .collect::<HashSet<_>>();
-                    let target_reads = target_data
-                        .reads
-                        .iter()
-                        .map(|r| r.id.clone())
-                        .collect::<HashSet<_>>();
-                    let edge_weight = source_reads
-                        .intersection(&target_reads)
-                        .collect::<HashSet<_>>()
-                        .len();
-
-                    if let (Some(source_id), Some(target_id)) = (source_id, target_id) {
-                        let edge_data = json!({
-                            "data": {
-                                "id": edge.id.to_str().unwrap(),
-                                "source": source_id.to_str().unwrap(),
-                                "target": target_id.to_str().unwrap(),
-                                "weight": edge_weight,
-                                "breakpoints": format!("{}", edge.sv)
-                            }
-                        });
-                        edges.push(edge_data);
-                    }
-                }
+        let mut weighted_edges = Vec::new();
+        for edge_idx in self.ordered_edge_indices(options.ordering) {
+            if let Some(edge) = self._graph.edge_weight(edge_idx)
+                && let Some((source, target)) = self._graph.edge_endpoints(edge_idx)
+                && let (Some(source_id), Some(target_id)) = (
+                    self.find_node_id_by_idx(source),
+                    self.find_node_id_by_idx(target),
+                )
+            {
+                weighted_edges.push((edge, source_id, target_id, self.edge_weight(edge_idx)));
             }
         }
 
+        let max_weight = weighted_edges
+            .iter()
+            .map(|(_, _, _, weight)| *weight)
+            .max()
+            .unwrap_or_default();
+        for (edge, source_id, target_id, edge_weight) in weighted_edges {
+            let edge_data = json!({
+                "data": {
+                    "id": edge.id.to_str().unwrap(),
+                    "source": source_id.to_str().unwrap(),
+                    "target": target_id.to_str().unwrap(),
+                    "weight": edge_weight,
+                    "width": scale_style(edge_weight, max_weight, EDGE_WIDTH_RANGE),
+                    "breakpoints": format!("{}", edge.sv)
+                }
+            });
+            edges.push(edge_data);
+        }
+
         // Combine nodes and edges into a Cytoscape-compatible format
         let elements = json!({
             "directed": true,
@@ -463,12 +864,105 @@ impl GraphSection {
             "elements": {
             "nodes": nodes,
             "edges": edges
-            }
+            },
+            "style": cytoscape_style()
         });
 
         Ok(elements)
     }
 
+    /// Build a graph section from the Cytoscape JSON written by [`GraphSection::to_json`]
+    ///
+    /// Nodes keep their locus, exons and reads, and edges their endpoints and structural
+    /// variant. The derived coordinates, weights and styling hints are dropped, and any
+    /// other field becomes an attribute typed after its JSON value.
+    pub fn from_cytoscape_json(id: BString, json: &serde_json::Value) -> Result<Self> {
+        let elements = json
+            .get("elements")
+            .ok_or_else(|| anyhow!("Missing elements in Cytoscape JSON"))?;
+        let data = |element: &'_ serde_json::Value| {
+            element
+                .get("data")
+                .and_then(serde_json::Value::as_object)
+                .cloned()
+                .ok_or_else(|| anyhow!("Missing data of Cytoscape element"))
+        };
+        let text = |data: &serde_json::Map<String, serde_json::Value>, key: &str| {
+            data.get(key)
+                .and_then(serde_json::Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("Missing {} of Cytoscape element", key))
+        };
+        let empty = Vec::new();
+        let mut graph = GraphSection::new(id);
+
+        let nodes = elements["nodes"].as_array().unwrap_or(&empty);
+        for node in nodes {
+            let mut data = data(node)?;
+            let id = text(&data, "id")?;
+            let exons = text(&data, "exons")?;
+            let mut node_data = NodeData {
+                id: id.as_str().into(),
+                reference_id: text(&data, "chrom")?.into(),
+                strand: text(&data, "strand")?.parse()?,
+                exons: exons.trim_matches(['[', ']']).parse()?,
+                ..Default::default()
+            };
+            for read in data["reads"].as_array().unwrap_or(&empty) {
+                let read = read
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Invalid read of node {}", id))?;
+                node_data.reads.push(read.parse()?);
+            }
+            for key in [
+                "id",
+                "chrom",
+                "strand",
+                "exons",
+                "reads",
+                "ref_start",
+                "ref_end",
+                "read_count",
+                "size",
+                "color",
+            ] {
+                data.remove(key);
+            }
+            node_data.attributes = data
+                .iter()
+                .map(|(tag, value)| (tag.as_str().into(), json_attribute(tag, value)))
+                .collect();
+            graph.add_node(node_data)?;
+        }
+
+        let edges = elements["edges"].as_array().unwrap_or(&empty);
+        for edge in edges {
+            let mut data = data(edge)?;
+            let id = text(&data, "id")?;
+            let source = text(&data, "source")?;
+            let target = text(&data, "target")?;
+            for endpoint in [&source, &target] {
+                if !graph.node_indices.contains_key(endpoint.as_bytes()) {
+                    return Err(anyhow!("Edge {} refers to unknown node {}", id, endpoint));
+                }
+            }
+            let sv = text(&data, "breakpoints")?.parse()?;
+            for key in ["id", "source", "target", "breakpoints", "weight", "width"] {
+                data.remove(key);
+            }
+            let edge_data = EdgeData {
+                id: id.into(),
+                sv,
+                attributes: data
+                    .iter()
+                    .map(|(tag, value)| (tag.as_str().into(), json_attribute(tag, value)))
+                    .collect(),
+            };
+            graph.add_edge(source.as_str().into(), target.as_str().into(), edge_data)?;
+        }
+        Ok(graph)
+    }
+
     pub fn annotate_node_with_sequence<P: AsRef<Path>>(
         &mut self,
         reference_genome_path: P,
@@ -478,6 +972,10 @@ impl GraphSection {
 
         for node_idx in self.node_indices.values() {
             let node_data = self._graph.node_weight_mut(*node_idx).unwrap();
+            // Nodes without exons have no region to look up
+            if node_data.exons.is_empty() {
+                continue;
+            }
 
             let region = format!(
                 "{}:{}-{}",
@@ -494,43 +992,277 @@ impl GraphSection {
 }
 
 /// Represents a link between elements in different graphs
-#[derive(Debug, Clone, Default, Builder)]
+#[derive(Debug, Clone, Default, Builder, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterGraphLink {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub id: BString,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub source_graph: BString,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub source_element: BString,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub target_graph: BString,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub target_element: BString,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub link_type: BString,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, with = "crate::graph::serialization::attributes")
+    )]
     pub attributes: HashMap<BString, Attribute>,
 }
 
+impl fmt::Display for InterGraphLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "L\t{}\t{}:{}\t{}:{}\t{}",
+            self.id,
+            self.source_graph,
+            self.source_element,
+            self.target_graph,
+            self.target_element,
+            self.link_type
+        )?;
+        for attr in sorted_attributes(&self.attributes) {
+            write!(f, "\t{}", attr)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for InterGraphLink {
+    type Err = TsgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // L  <id>  <source_graph>:<element>  <target_graph>:<element>  <type>  [attributes]
+        let fields = split_fields(s);
+        if fields.len() < 5 {
+            return Err(TsgError::malformed(
+                "L",
+                "fields",
+                format!("expected at least 5 fields, found {}", fields.len()),
+            ));
+        }
+
+        let id: BString = fields[1].into();
+
+        // Parse source and target references (format: graph_id:element_id)
+        let (source_graph, source_element) = fields[2].split_once(':').ok_or_else(|| {
+            TsgError::malformed(
+                "L",
+                "source",
+                format!("expected <graph_id>:<element_id>, found {}", fields[2]),
+            )
+        })?;
+        let (target_graph, target_element) = fields[3].split_once(':').ok_or_else(|| {
+            TsgError::malformed(
+                "L",
+                "target",
+                format!("expected <graph_id>:<element_id>, found {}", fields[3]),
+            )
+        })?;
+
+        let mut link = InterGraphLink {
+            id,
+            source_graph: source_graph.into(),
+            source_element: source_element.into(),
+            target_graph: target_graph.into(),
+            target_element: target_element.into(),
+            link_type: fields[4].into(),
+            attributes: HashMap::new(),
+        };
+
+        // Parse optional attributes
+        for attr_str in &fields[5..] {
+            let attr = parse_record_attribute("L", attr_str)?;
+            link.attributes.insert(attr.tag.clone(), attr);
+        }
+
+        Ok(link)
+    }
+}
+
+/// Line numbers of the records that are only checked once the whole file is parsed,
+/// and the node IDs declared by `N` records of each graph
+#[derive(Debug, Default)]
+struct RecordLines {
+    paths: HashMap<(BString, BString), usize>,
+    chains: HashMap<(BString, BString), usize>,
+    links: Vec<usize>,
+    nodes: HashSet<(BString, BString)>,
+}
+
+impl RecordLines {
+    fn chain_line(&self, graph_id: &BString, chain_id: &BString) -> usize {
+        self.chains
+            .get(&(graph_id.clone(), chain_id.clone()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn path_line(&self, graph_id: &BString, path_id: &BString) -> usize {
+        self.paths
+            .get(&(graph_id.clone(), path_id.clone()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn link_line(&self, idx: usize) -> usize {
+        self.links.get(idx).copied().unwrap_or_default()
+    }
+}
+
+/// Parse an optional `tag:type:value` attribute of a record
+fn parse_record_attribute(record: &str, s: &str) -> Result<Attribute, TsgError> {
+    s.parse::<Attribute>()
+        .map_err(|e| TsgError::malformed(record, "attributes", e))
+}
+
+/// Node colours of the Cytoscape JSON and DOT by strand
+pub(crate) const FORWARD_COLOR: &str = "#1f77b4";
+pub(crate) const REVERSE_COLOR: &str = "#d62728";
+/// Range of node sizes and edge widths of the Cytoscape JSON, in pixels
+const NODE_SIZE_RANGE: (f64, f64) = (20.0, 60.0);
+const EDGE_WIDTH_RANGE: (f64, f64) = (1.0, 10.0);
+
+/// Scale a count linearly into a styling range, relative to the largest count
+pub(crate) fn scale_style(count: usize, max: usize, (low, high): (f64, f64)) -> f64 {
+    if max == 0 {
+        return low;
+    }
+    let value = low + (high - low) * count as f64 / max as f64;
+    (value * 10.0).round() / 10.0
+}
+
+/// Cytoscape.js style sheet mapping the styling hints of the elements
+fn cytoscape_style() -> serde_json::Value {
+    json!([
+        {
+            "selector": "node",
+            "style": {
+                "label": "data(id)",
+                "width": "data(size)",
+                "height": "data(size)",
+                "background-color": "data(color)"
+            }
+        },
+        {
+            "selector": "edge",
+            "style": {
+                "label": "data(breakpoints)",
+                "width": "data(width)",
+                "curve-style": "bezier",
+                "target-arrow-shape": "triangle"
+            }
+        }
+    ])
+}
+
+/// Attribute typed after a JSON value
+fn json_attribute(tag: &str, value: &serde_json::Value) -> Attribute {
+    let (attribute_type, value) = match value {
+        serde_json::Value::Number(number) if number.is_f64() => ('f', number.to_string()),
+        serde_json::Value::Number(number) => ('i', number.to_string()),
+        serde_json::Value::String(text) => ('Z', text.clone()),
+        other => ('J', other.to_string()),
+    };
+    Attribute::builder()
+        .tag(tag)
+        .attribute_type(attribute_type)
+        .value(value)
+        .build()
+}
+
+/// Attributes ordered by tag, for deterministic output
+fn sorted_attributes(attributes: &HashMap<BString, Attribute>) -> Vec<&Attribute> {
+    let mut attributes: Vec<_> = attributes.values().collect();
+    attributes.sort_by(|a, b| a.tag.cmp(&b.tag));
+    attributes
+}
+
+/// Section labels written by [`TSGraph::to_writer`], dropped when parsing so they are not
+/// kept as comments and repeated on every round-trip
+const WRITER_COMMENTS: [&str; 6] = [
+    "# Global header",
+    "# Nodes",
+    "# Edges",
+    "# Groups",
+    "# Attributes",
+    "# Inter-graph links",
+];
+
+fn is_writer_comment(line: &str) -> bool {
+    WRITER_COMMENTS.contains(&line) || line.starts_with("# Graph: ")
+}
+
 /// The complete transcript segment graph containing multiple graph sections
 #[derive(Debug, Clone, Default, Builder)]
 pub struct TSGraph {
     pub headers: Vec<Header>,
-    pub graphs: HashMap<BString, GraphSection>,
+    /// Graph sections in the order they were added
+    pub graphs: OrderedMap<BString, GraphSection>,
     pub links: Vec<InterGraphLink>,
+    /// Comment lines before the first `G` record without their leading `#`,
+    /// written after the headers
+    #[builder(default)]
+    pub comments: Vec<BString>,
     current_graph_id: Option<BString>, // Tracks which graph is currently active during parsing
 }
 
 impl TSGraph {
     /// Create a new empty TSGraph
     pub fn new() -> Self {
-        let graph = GraphSection::default_graph();
-        let mut graphs = HashMap::new();
-        graphs.insert(graph.id.clone(), graph);
-        Self {
-            graphs,
+        let mut tsgraph = Self {
             current_graph_id: Some(DEFAULT_GRAPH_ID.into()),
             ..Default::default()
+        };
+        tsgraph.add_graph(GraphSection::default_graph());
+        tsgraph
+    }
+
+    /// Add a graph section, replacing any section with the same ID
+    pub fn add_graph(&mut self, graph: GraphSection) {
+        self.graphs.insert(graph.id.clone(), graph);
+    }
+
+    /// Graph sections in the given order
+    pub fn ordered_graphs(&self, ordering: OrderingPolicy) -> Vec<&GraphSection> {
+        let mut graphs: Vec<_> = self.graphs.values().collect();
+        if ordering == OrderingPolicy::Sorted {
+            graphs.sort_by(|a, b| a.id.cmp(&b.id));
         }
+        graphs
     }
 
     /// Parse a header line
-    fn parse_header_line(&mut self, fields: &[&str]) -> Result<()> {
+    fn parse_header_line(&mut self, fields: &[&str]) -> Result<(), TsgError> {
         if fields.len() < 3 {
-            return Err(anyhow!("Invalid header line format"));
+            return Err(TsgError::malformed(
+                "H",
+                "fields",
+                format!("expected at least 3 fields, found {}", fields.len()),
+            ));
         }
 
         self.headers.push(Header {
@@ -542,19 +1274,27 @@ impl TSGraph {
     }
 
     /// Parse a graph section line
-    fn parse_graph_line(&mut self, fields: &[&str]) -> Result<()> {
+    fn parse_graph_line(&mut self, fields: &[&str]) -> Result<(), TsgError> {
+        // Records of a rejected section must not fall into the previous one, so they are
+        // skipped until the next valid G record
+        self.current_graph_id = None;
+
         if fields.len() < 2 {
-            return Err(anyhow!("Invalid graph line format"));
+            return Err(TsgError::malformed(
+                "G",
+                "fields",
+                format!("expected at least 2 fields, found {}", fields.len()),
+            ));
         }
 
         let graph_id: BString = fields[1].into();
 
-        // Check if graph with this ID already exists
-        if self.graphs.contains_key(&graph_id) {
-            return Err(anyhow!(
-                "Graph with ID {} already exists",
-                graph_id.to_str().unwrap_or("")
-            ));
+        // Check if graph with this ID already exists; the implicit default graph
+        // may still be declared explicitly as long as nothing was added to it
+        if let Some(existing) = self.graphs.get(&graph_id)
+            && (graph_id != DEFAULT_GRAPH_ID || !existing.is_empty())
+        {
+            return Err(TsgError::duplicate_id("G", "graph_id", graph_id));
         }
 
         // Create new graph section
@@ -563,99 +1303,100 @@ impl TSGraph {
         // Parse optional attributes
         if fields.len() > 2 {
             for attr_str in &fields[2..] {
-                let attr = attr_str.parse::<Attribute>()?;
+                let attr = parse_record_attribute("G", attr_str)?;
                 graph_section.attributes.insert(attr.tag.clone(), attr);
             }
         }
 
         // Update current graph ID and add to graphs map
-        self.current_graph_id = Some(graph_id.clone());
-        self.graphs.insert(graph_id, graph_section);
+        self.current_graph_id = Some(graph_id);
+        self.add_graph(graph_section);
 
         Ok(())
     }
 
-    /// Get the current graph section (or error if none is active)
-    fn current_graph_mut(&mut self) -> Result<&mut GraphSection> {
-        if let Some(graph_id) = &self.current_graph_id {
-            if let Some(graph) = self.graphs.get_mut(graph_id) {
-                return Ok(graph);
-            }
+    /// Keep a comment line: comments before the first `G` record belong to the file,
+    /// later ones to the current graph section
+    fn push_comment(&mut self, comment: BString) {
+        let section = self
+            .current_graph_id
+            .as_ref()
+            .filter(|id| *id != DEFAULT_GRAPH_ID)
+            .and_then(|id| self.graphs.get_mut(id));
+        match section {
+            Some(section) => section.comments.push(comment),
+            None => self.comments.push(comment),
         }
-        Err(anyhow!("No active graph section"))
     }
 
-    /// Parse an inter-graph link line
-    fn parse_link_line(&mut self, fields: &[&str]) -> Result<()> {
-        if fields.len() < 5 {
-            return Err(anyhow!("Invalid link line format"));
-        }
-
-        let id: BString = fields[1].into();
-
-        // Parse source and target references (format: graph_id:element_id)
-        let source_ref: Vec<&str> = fields[2].splitn(2, ':').collect();
-        let target_ref: Vec<&str> = fields[3].splitn(2, ':').collect();
-
-        if source_ref.len() != 2 || target_ref.len() != 2 {
-            return Err(anyhow!("Invalid element reference format in link line"));
+    /// Get the current graph section (or error if none is active)
+    fn current_graph_mut(&mut self, record: &str) -> Result<&mut GraphSection, TsgError> {
+        if let Some(graph_id) = &self.current_graph_id
+            && let Some(graph) = self.graphs.get_mut(graph_id)
+        {
+            return Ok(graph);
         }
+        Err(TsgError::outside_graph(record))
+    }
 
-        let source_graph: BString = source_ref[0].into();
-        let source_element: BString = source_ref[1].into();
-        let target_graph: BString = target_ref[0].into();
-        let target_element: BString = target_ref[1].into();
+    /// Parse an inter-graph link line
+    fn parse_link_line(&mut self, line: &str) -> Result<(), TsgError> {
+        let link = InterGraphLink::from_str(line)?;
 
         // Verify the referenced graphs exist
-        if !self.graphs.contains_key(&source_graph) {
-            return Err(anyhow!(
-                "Source graph {} not found",
-                source_graph.to_str().unwrap_or("")
+        if !self.graphs.contains_key(&link.source_graph) {
+            return Err(TsgError::dangling_reference(
+                "L",
+                "source",
+                &link.source_graph,
             ));
         }
-        if !self.graphs.contains_key(&target_graph) {
-            return Err(anyhow!(
-                "Target graph {} not found",
-                target_graph.to_str().unwrap_or("")
+        if !self.graphs.contains_key(&link.target_graph) {
+            return Err(TsgError::dangling_reference(
+                "L",
+                "target",
+                &link.target_graph,
             ));
         }
 
-        let link_type: BString = fields[4].into();
-
-        let mut link = InterGraphLink {
-            id,
-            source_graph,
-            source_element,
-            target_graph,
-            target_element,
-            link_type,
-            attributes: HashMap::new(),
-        };
-
-        // Parse optional attributes
-        if fields.len() > 5 {
-            for attr_str in &fields[5..] {
-                let attr = attr_str.parse::<Attribute>()?;
-                link.attributes.insert(attr.tag.clone(), attr);
-            }
-        }
-
         self.links.push(link);
         Ok(())
     }
 
     /// Parse a node line
-    fn parse_node_line(&mut self, fields: &str) -> Result<()> {
+    ///
+    /// `declared` holds the graph and node IDs of the `N` records parsed so far. Nodes
+    /// first seen as edge endpoints are placeholders that their `N` record fills in.
+    fn parse_node_line(
+        &mut self,
+        fields: &str,
+        declared: &mut HashSet<(BString, BString)>,
+    ) -> Result<(), TsgError> {
         let node_data = NodeData::from_str(fields)?;
-        let graph = self.current_graph_mut()?;
-        graph.add_node(node_data)?;
+        let graph = self.current_graph_mut("N")?;
+
+        if !declared.insert((graph.id.clone(), node_data.id.clone())) {
+            return Err(TsgError::duplicate_id("N", "node_id", &node_data.id));
+        }
+
+        graph
+            .add_node(node_data)
+            .map_err(|e| TsgError::malformed("N", "node_id", e))?;
         Ok(())
     }
 
     /// Parse an edge line
-    fn parse_edge_line(&mut self, fields: &[&str]) -> Result<()> {
+    fn parse_edge_line(
+        &mut self,
+        fields: &[&str],
+        policy: EdgeConflictPolicy,
+    ) -> Result<(), TsgError> {
         if fields.len() < 5 {
-            return Err(anyhow!("Invalid edge line format"));
+            return Err(TsgError::malformed(
+                "E",
+                "fields",
+                format!("expected at least 5 fields, found {}", fields.len()),
+            ));
         }
 
         let id: BString = fields[1].into();
@@ -669,23 +1410,40 @@ impl TSGraph {
             attributes: HashMap::new(),
         };
 
-        let graph = self.current_graph_mut()?;
-        graph.add_edge(source_id.as_bstr(), sink_id.as_bstr(), edge_data)?;
+        let graph = self.current_graph_mut("E")?;
+        if graph.edge_indices.contains_key(&edge_data.id) {
+            return Err(TsgError::duplicate_id("E", "edge_id", &edge_data.id));
+        }
+
+        // The links parsed so far follow a replaced edge to its new ID, as its groups do
+        let graph_id = graph.id.to_string();
+        self.add_edge_with_policy(
+            &graph_id,
+            source_id.as_bstr(),
+            sink_id.as_bstr(),
+            edge_data,
+            policy,
+        )
+        .map_err(|e| TsgError::malformed("E", "edge_id", e))?;
         Ok(())
     }
 
     /// Parse an unordered group line
-    fn parse_unordered_group_line(&mut self, fields: &[&str]) -> Result<()> {
+    fn parse_unordered_group_line(&mut self, fields: &[&str]) -> Result<(), TsgError> {
         if fields.len() < 3 {
-            return Err(anyhow!("Invalid unordered group line format"));
+            return Err(TsgError::malformed(
+                "U",
+                "fields",
+                format!("expected at least 3 fields, found {}", fields.len()),
+            ));
         }
 
         let id: BString = fields[1].into();
-        let graph = self.current_graph_mut()?;
+        let graph = self.current_graph_mut("U")?;
 
         // Check for duplicate group name
         if graph.groups.contains_key(&id) {
-            return Err(anyhow!("Group with ID {} already exists", id));
+            return Err(TsgError::duplicate_id("U", "group_id", id));
         }
 
         // Parse element IDs (space-separated)
@@ -701,22 +1459,26 @@ impl TSGraph {
             attributes: HashMap::new(),
         };
 
-        graph.groups.insert(id, group);
+        graph.add_group(id, group);
         Ok(())
     }
 
     /// Parse a path line
-    fn parse_path_line(&mut self, fields: &[&str]) -> Result<()> {
+    fn parse_path_line(&mut self, fields: &[&str]) -> Result<(), TsgError> {
         if fields.len() < 3 {
-            return Err(anyhow!("Invalid path line format"));
+            return Err(TsgError::malformed(
+                "P",
+                "fields",
+                format!("expected at least 3 fields, found {}", fields.len()),
+            ));
         }
 
         let id: BString = fields[1].into();
-        let graph = self.current_graph_mut()?;
+        let graph = self.current_graph_mut("P")?;
 
         // Check for duplicate group name
         if graph.groups.contains_key(&id) {
-            return Err(anyhow!("Group with ID {} already exists", id));
+            return Err(TsgError::duplicate_id("P", "group_id", id));
         }
 
         // Parse oriented element IDs (space-separated)
@@ -732,22 +1494,26 @@ impl TSGraph {
             attributes: HashMap::new(),
         };
 
-        graph.groups.insert(id, group);
+        graph.add_group(id, group);
         Ok(())
     }
 
     /// Parse a chain line
-    fn parse_chain_line(&mut self, fields: &[&str]) -> Result<()> {
+    fn parse_chain_line(&mut self, fields: &[&str]) -> Result<(), TsgError> {
         if fields.len() < 3 {
-            return Err(anyhow!("Invalid chain line format"));
+            return Err(TsgError::malformed(
+                "C",
+                "fields",
+                format!("expected at least 3 fields, found {}", fields.len()),
+            ));
         }
 
         let id: BString = fields[1].into();
-        let graph = self.current_graph_mut()?;
+        let graph = self.current_graph_mut("C")?;
 
         // Check for duplicate group name
         if graph.groups.contains_key(&id) {
-            return Err(anyhow!("Group with ID {} already exists", id));
+            return Err(TsgError::duplicate_id("C", "group_id", id));
         }
 
         // Parse element IDs (space-separated)
@@ -756,12 +1522,18 @@ impl TSGraph {
 
         // Validate chain structure: must start and end with nodes, and have alternating nodes/edges
         if elements.is_empty() {
-            return Err(anyhow!("Chain must contain at least one element"));
+            return Err(TsgError::malformed(
+                "C",
+                "elements",
+                "Chain must contain at least one element",
+            ));
         }
 
-        if elements.len() % 2 == 0 {
-            return Err(anyhow!(
-                "Chain must have an odd number of elements (starting and ending with nodes)"
+        if elements.len().is_multiple_of(2) {
+            return Err(TsgError::malformed(
+                "C",
+                "elements",
+                "Chain must have an odd number of elements (starting and ending with nodes)",
             ));
         }
 
@@ -773,55 +1545,51 @@ impl TSGraph {
         };
 
         // Store the chain in both maps
-        graph.chains.insert(id.clone(), group.clone());
-        graph.groups.insert(id, group);
+        graph.add_group(id, group);
         Ok(())
     }
 
     /// Parse an attribute line
-    fn parse_attribute_line(&mut self, fields: &[&str]) -> Result<()> {
+    fn parse_attribute_line(&mut self, fields: &[&str]) -> Result<(), TsgError> {
         if fields.len() < 4 {
-            return Err(anyhow!("Invalid attribute line format"));
+            return Err(TsgError::malformed(
+                "A",
+                "fields",
+                format!("expected at least 4 fields, found {}", fields.len()),
+            ));
         }
 
         let element_type = fields[1];
         let element_id: BString = fields[2].into();
-        let graph = self.current_graph_mut()?;
+        let graph = self.current_graph_mut("A")?;
 
         let attrs: Vec<Attribute> = fields
             .iter()
             .skip(3)
-            .map(|s| s.parse())
-            .collect::<Result<Vec<_>>>()
-            .context("invalidate attribute line")?;
+            .map(|s| parse_record_attribute("A", s))
+            .collect::<Result<Vec<_>, _>>()?;
 
         match element_type {
             "N" => {
-                if let Some(&node_idx) = graph.node_indices.get(&element_id) {
-                    if let Some(node_data) = graph._graph.node_weight_mut(node_idx) {
-                        for attr in attrs {
-                            let tag = attr.tag.clone();
-                            node_data.attributes.insert(tag, attr);
-                        }
-                    } else {
-                        return Err(anyhow!("Node with ID {} not found in graph", element_id));
-                    }
-                } else {
-                    return Err(anyhow!("Node with ID {} not found", element_id));
+                let node_data = graph
+                    .node_indices
+                    .get(&element_id)
+                    .and_then(|&node_idx| graph._graph.node_weight_mut(node_idx))
+                    .ok_or_else(|| TsgError::dangling_reference("A", "element_id", &element_id))?;
+                for attr in attrs {
+                    let tag = attr.tag.clone();
+                    node_data.attributes.insert(tag, attr);
                 }
             }
             "E" => {
-                if let Some(&edge_idx) = graph.edge_indices.get(&element_id) {
-                    if let Some(edge_data) = graph._graph.edge_weight_mut(edge_idx) {
-                        for attr in attrs {
-                            let tag = attr.tag.clone();
-                            edge_data.attributes.insert(tag, attr);
-                        }
-                    } else {
-                        return Err(anyhow!("Edge with ID {} not found in graph", element_id));
-                    }
-                } else {
-                    return Err(anyhow!("Edge with ID {} not found", element_id));
+                let edge_data = graph
+                    .edge_indices
+                    .get(&element_id)
+                    .and_then(|&edge_idx| graph._graph.edge_weight_mut(edge_idx))
+                    .ok_or_else(|| TsgError::dangling_reference("A", "element_id", &element_id))?;
+                for attr in attrs {
+                    let tag = attr.tag.clone();
+                    edge_data.attributes.insert(tag, attr);
                 }
             }
             "U" | "P" | "C" => {
@@ -836,8 +1604,13 @@ impl TSGraph {
                             }
                         }
                     }
+                    // Chains are also kept by ID, which must see the same attributes
+                    if let Group::Chain { .. } = group {
+                        let chain = group.clone();
+                        graph.chains.insert(element_id, chain);
+                    }
                 } else {
-                    return Err(anyhow!("Group with ID {} not found", element_id));
+                    return Err(TsgError::dangling_reference("A", "element_id", element_id));
                 }
             }
             "G" => {
@@ -848,94 +1621,140 @@ impl TSGraph {
                         graph_section.attributes.insert(tag, attr);
                     }
                 } else {
-                    return Err(anyhow!("Graph with ID {} not found", element_id));
+                    return Err(TsgError::dangling_reference("A", "element_id", element_id));
                 }
             }
             _ => {
-                return Err(anyhow!("Unknown element type: {}", element_type));
+                return Err(TsgError::unknown_record_type(
+                    "A",
+                    "element_type",
+                    element_type,
+                ));
             }
         }
 
         Ok(())
     }
 
+    /// Check that both ends of an inter-graph link exist
+    fn check_link(&self, link: &InterGraphLink) -> Result<(), TsgError> {
+        // Check source element exists
+        let source_graph = self
+            .graphs
+            .get(&link.source_graph)
+            .ok_or_else(|| TsgError::dangling_reference("L", "source", &link.source_graph))?;
+
+        if !source_graph.contains_element(&link.source_element) {
+            return Err(TsgError::dangling_reference(
+                "L",
+                "source",
+                format!("{}:{}", link.source_graph, link.source_element),
+            ));
+        }
+
+        // Check target element exists
+        let target_graph = self
+            .graphs
+            .get(&link.target_graph)
+            .ok_or_else(|| TsgError::dangling_reference("L", "target", &link.target_graph))?;
+
+        if !target_graph.contains_element(&link.target_element) {
+            return Err(TsgError::dangling_reference(
+                "L",
+                "target",
+                format!("{}:{}", link.target_graph, link.target_element),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Validate all graphs and their paths
-    fn validate(&self) -> Result<()> {
-        // Validate each graph section
+    ///
+    /// `lines` maps each path and link back to the line it was defined on,
+    /// so that dangling references can be reported with their location.
+    /// In lenient mode invalid paths and links are dropped and returned as diagnostics.
+    fn validate(
+        &mut self,
+        lines: &RecordLines,
+        options: &ParseOptions,
+    ) -> Result<Vec<TsgError>, TsgError> {
+        let mut diagnostics = Vec::new();
+        let mut invalid_paths = Vec::new();
+
+        // Validate paths against each graph section
         for (graph_id, graph) in &self.graphs {
-            // Validate paths against the graph
             for (id, group) in &graph.groups {
-                if let Group::Ordered { elements, .. } = group {
-                    // Validate that all elements in the path exist in the graph
-                    for element in elements {
-                        let element_exists = graph.node_indices.contains_key(&element.id)
-                            || graph.edge_indices.contains_key(&element.id)
-                            || graph.groups.contains_key(&element.id);
-
-                        if !element_exists {
-                            return Err(anyhow!(
-                                "Path {} in graph {} references non-existent element {}",
-                                id,
-                                graph_id,
-                                element.id
-                            ));
-                        }
+                // Validate that all elements in the path exist in the graph
+                if let Group::Ordered { elements, .. } = group
+                    && let Some(element) = elements
+                        .iter()
+                        .find(|element| !graph.contains_element(&element.id))
+                {
+                    let err = TsgError::dangling_reference("P", "elements", &element.id)
+                        .at_line(lines.path_line(graph_id, id));
+                    if !options.is_lenient() {
+                        return Err(err);
                     }
+                    diagnostics.push(err);
+                    invalid_paths.push((graph_id.clone(), id.clone()));
                 }
             }
         }
 
-        // Validate all inter-graph links
-        for link in &self.links {
-            // Check source element exists
-            let source_graph = self.graphs.get(&link.source_graph).ok_or_else(|| {
-                anyhow!(
-                    "Link {} references non-existent graph {}",
-                    link.id,
-                    link.source_graph
-                )
-            })?;
-
-            let source_exists = source_graph.node_indices.contains_key(&link.source_element)
-                || source_graph.edge_indices.contains_key(&link.source_element)
-                || source_graph.groups.contains_key(&link.source_element);
-
-            if !source_exists {
-                return Err(anyhow!(
-                    "Link {} references non-existent element {}:{}",
-                    link.id,
-                    link.source_graph,
-                    link.source_element
-                ));
+        for (graph_id, id) in invalid_paths {
+            if let Some(graph) = self.graphs.get_mut(&graph_id) {
+                graph.groups.shift_remove(&id);
             }
+        }
 
-            // Check target element exists
-            let target_graph = self.graphs.get(&link.target_graph).ok_or_else(|| {
-                anyhow!(
-                    "Link {} references non-existent graph {}",
-                    link.id,
-                    link.target_graph
-                )
-            })?;
-
-            let target_exists = target_graph.node_indices.contains_key(&link.target_element)
-                || target_graph.edge_indices.contains_key(&link.target_element)
-                || target_graph.groups.contains_key(&link.target_element);
-
-            if !target_exists {
-                return Err(anyhow!(
-                    "Link {} references non-existent element {}:{}",
-                    link.id,
-                    link.target_graph,
-                    link.target_element
-                ));
+        // Validate all inter-graph links
+        let links = std::mem::take(&mut self.links);
+        for (idx, link) in links.into_iter().enumerate() {
+            match self.check_link(&link) {
+                Ok(()) => self.links.push(link),
+                Err(err) => {
+                    let err = err.at_line(lines.link_line(idx));
+                    if !options.is_lenient() {
+                        return Err(err);
+                    }
+                    diagnostics.push(err);
+                }
             }
         }
 
-        Ok(())
+        Ok(diagnostics)
+    }
+
+    /// Parse a TSG stream and construct a TSGraph
+    ///
+    /// Errors are returned as [`TsgError`] and carry the line number, record type
+    /// and field of the record that could not be parsed.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, TsgError> {
+        let (tsgraph, _) = Self::from_reader_with_options(reader, &ParseOptions::strict())?;
+        Ok(tsgraph)
+    }
+
+    /// Parse a TSG stream with the given options
+    ///
+    /// In strict mode this behaves like [`TSGraph::from_reader`] and the returned diagnostics are empty.
+    /// In lenient mode malformed records are skipped and their errors are returned, ordered by line,
+    /// next to the graph built from the remaining records. I/O errors are always fatal.
+    pub fn from_reader_with_options<R: BufRead>(
+        reader: R,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<TsgError>), TsgError> {
+        Self::from_reader_at_line(reader, options, 1)
     }
 
-    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+    /// Parse a TSG stream whose first line is line `first_line` of the original file
+    ///
+    /// Used to parse a slice of a larger file while keeping line numbers in errors accurate.
+    pub(crate) fn from_reader_at_line<R: BufRead>(
+        reader: R,
+        options: &ParseOptions,
+        first_line: usize,
+    ) -> Result<(Self, Vec<TsgError>), TsgError> {
         let mut tsgraph = TSGraph::new();
 
         // Create a default graph if needed for backward compatibility
@@ -947,70 +1766,132 @@ impl TSGraph {
 
         tsgraph.current_graph_id = Some(default_graph_id);
 
+        let mut lines = RecordLines::default();
+        let mut diagnostics = Vec::new();
+
         // First pass: Parse all record types
-        for line in reader.lines() {
+        for (idx, line) in reader.lines().enumerate() {
+            let line_number = first_line + idx;
             let line = line?;
-            if line.is_empty() || line.starts_with('#') {
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                if !is_writer_comment(&line) {
+                    tsgraph.push_comment(comment.into());
+                }
                 continue;
             }
-            let fields: Vec<&str> = line.split_whitespace().collect();
+            let fields = split_fields(&line);
             if fields.is_empty() {
                 continue;
             }
 
+            let parsed = match fields[0] {
+                "H" => tsgraph.parse_header_line(&fields),
+                "G" => tsgraph.parse_graph_line(&fields),
+                "N" => tsgraph.parse_node_line(&line, &mut lines.nodes),
+                "E" => tsgraph.parse_edge_line(&fields, options.edge_conflict),
+                "U" => tsgraph.parse_unordered_group_line(&fields),
+                "P" => tsgraph.parse_path_line(&fields),
+                "C" => tsgraph.parse_chain_line(&fields),
+                "A" => tsgraph.parse_attribute_line(&fields),
+                "L" => tsgraph.parse_link_line(&line),
+                record => Err(TsgError::unknown_record_type(record, "record_type", record)),
+            };
+
+            if let Err(err) = parsed {
+                let err = err.at_line(line_number);
+                if !options.is_lenient() {
+                    return Err(err);
+                }
+                debug!("Skipping malformed record: {}", err);
+                diagnostics.push(err);
+                continue;
+            }
+
             match fields[0] {
-                "H" => tsgraph.parse_header_line(&fields)?,
-                "G" => tsgraph.parse_graph_line(&fields)?,
-                "N" => tsgraph.parse_node_line(&line)?,
-                "E" => tsgraph.parse_edge_line(&fields)?,
-                "U" => tsgraph.parse_unordered_group_line(&fields)?,
-                "P" => tsgraph.parse_path_line(&fields)?,
-                "C" => tsgraph.parse_chain_line(&fields)?,
-                "A" => tsgraph.parse_attribute_line(&fields)?,
-                "L" => tsgraph.parse_link_line(&fields)?,
-                _ => {
-                    // ignore unknown record types
-                    debug!("Ignoring unknown record type: {}", fields[0]);
+                "P" => {
+                    if let Some(graph_id) = &tsgraph.current_graph_id {
+                        lines
+                            .paths
+                            .insert((graph_id.clone(), fields[1].into()), line_number);
+                    }
+                }
+                "C" => {
+                    if let Some(graph_id) = &tsgraph.current_graph_id {
+                        lines
+                            .chains
+                            .insert((graph_id.clone(), fields[1].into()), line_number);
+                    }
                 }
+                "L" => lines.links.push(line_number),
+                _ => {}
             }
         }
 
         // Second pass: Ensure all graphs are built and validate
         for graph_section in tsgraph.graphs.values_mut() {
-            // Populate chains hash map from groups if needed
-            for (id, group) in &graph_section.groups {
-                if let Group::Chain { .. } = group {
-                    if !graph_section.chains.contains_key(id) {
-                        graph_section.chains.insert(id.clone(), group.clone());
-                    }
+            // Ensure graph is built
+            if let Err(err) = graph_section.ensure_graph_is_built(&lines) {
+                if !options.is_lenient() {
+                    return Err(err);
                 }
+                diagnostics.push(err);
             }
-
-            // Ensure graph is built
-            graph_section.ensure_graph_is_built()?;
         }
 
         // Validate all graphs and links
-        tsgraph.validate()?;
+        diagnostics.extend(tsgraph.validate(&lines, options)?);
+        diagnostics.sort_by_key(|err| err.line());
 
         // pop the default graph if it's empty
-        if let Some(default_graph) = tsgraph.graph(DEFAULT_GRAPH_ID) {
-            if default_graph.node_indices.is_empty() {
-                tsgraph.graphs.remove(&BString::from(DEFAULT_GRAPH_ID));
-            }
+        if let Some(default_graph) = tsgraph.graph(DEFAULT_GRAPH_ID)
+            && default_graph.node_indices.is_empty()
+        {
+            tsgraph
+                .graphs
+                .shift_remove(&BString::from(DEFAULT_GRAPH_ID));
         }
-        Ok(tsgraph)
+        Ok((tsgraph, diagnostics))
     }
 
     /// Parse a TSG file and construct a TSGraph
+    ///
+    /// Gzip, BGZF and zstd compressed files are detected from their magic bytes
+    /// and decompressed transparently. Parse failures can be downcast to [`TsgError`].
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        Self::from_reader(reader)
+        let reader = open_reader(path)?;
+        Ok(Self::from_reader(reader)?)
+    }
+
+    /// Parse a TSG file with the given options, returning the graph and any diagnostics
+    pub fn from_file_with_options<P: AsRef<Path>>(
+        path: P,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<TsgError>)> {
+        let reader = open_reader(path)?;
+        Ok(Self::from_reader_with_options(reader, options)?)
     }
 
     /// Write the TSGraph to writer
+    ///
+    /// The output can be parsed back into an identical graph: every record is re-emitted
+    /// with its attributes, and the output is deterministic. The default graph is written
+    /// first without a `G` record, followed by the other graphs in ID order. Nodes are written
+    /// by genomic position, edges by source and target, groups by ID and attributes by tag.
+    /// Comments are kept with the file or the graph section they appeared in: they are
+    /// written after the headers or after the `G` record, as records are reordered.
     pub fn to_writer<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        self.to_writer_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Write the TSGraph to writer, ordering graphs and their elements as given by `options`
+    pub fn to_writer_with_options<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<()> {
         // Write global headers
         writeln!(writer, "# Global header")?;
         for header in &self.headers {
@@ -1018,160 +1899,27 @@ impl TSGraph {
         }
 
         let new_header = Header::builder().tag("PG").value("tsg").build();
-        writeln!(writer, "{}", new_header)?;
-
-        // Write each graph section
-        for (graph_id, graph) in &self.graphs {
-            // Skip writing the default graph header if it's empty
-            if graph_id == &BString::from(DEFAULT_GRAPH_ID) && graph.node_indices.is_empty() {
-                continue;
-            }
-
-            writeln!(writer, "\n# Graph: {}", graph_id)?;
-
-            // Write graph section header
-            write!(writer, "G\t{}", graph_id)?;
-            for attr in graph.attributes.values() {
-                write!(writer, "\t{}", attr)?;
-            }
-            writeln!(writer)?;
-
-            // Write nodes
-            writeln!(writer, "# Nodes")?;
-            for node_idx in graph._graph.node_indices() {
-                if let Some(node_data) = graph._graph.node_weight(node_idx) {
-                    writeln!(writer, "{}", node_data)?;
-                }
-            }
-
-            // Write edges
-            writeln!(writer, "# Edges")?;
-            for edge_ref in graph._graph.edge_references() {
-                let edge = edge_ref.weight();
-                let source_idx = edge_ref.source();
-                let sink_idx = edge_ref.target();
-
-                if let (Some(source), Some(sink)) = (
-                    graph._graph.node_weight(source_idx),
-                    graph._graph.node_weight(sink_idx),
-                ) {
-                    writeln!(
-                        writer,
-                        "E\t{}\t{}\t{}\t{}",
-                        edge.id, source.id, sink.id, edge.sv
-                    )?;
-                }
-            }
-
-            // Write groups
-            writeln!(writer, "# Groups")?;
-            let mut seen_chain_ids = HashSet::new();
-
-            for group in graph.groups.values() {
-                match group {
-                    Group::Unordered { id, elements, .. } => {
-                        let elements_str: Vec<String> = elements
-                            .par_iter()
-                            .map(|e| e.to_str().unwrap_or("").to_string())
-                            .collect();
-                        writeln!(
-                            writer,
-                            "U\t{}\t{}",
-                            id.to_str().unwrap_or(""),
-                            elements_str.join(" ")
-                        )?;
-                    }
-                    Group::Ordered { id, elements, .. } => {
-                        let elements_str: Vec<String> =
-                            elements.par_iter().map(|e| e.to_string()).collect();
-                        writeln!(
-                            writer,
-                            "P\t{}\t{}",
-                            id.to_str().unwrap_or(""),
-                            elements_str.join(" ")
-                        )?;
-                    }
-                    Group::Chain { id, elements, .. } => {
-                        // Skip writing chains that are duplicated with groups
-                        if seen_chain_ids.contains(id) {
-                            continue;
-                        }
-                        seen_chain_ids.insert(id);
-
-                        let elements_str: Vec<String> = elements
-                            .par_iter()
-                            .map(|e| e.to_str().unwrap_or("").to_string())
-                            .collect();
-                        writeln!(
-                            writer,
-                            "C\t{}\t{}",
-                            id.to_str().unwrap_or(""),
-                            elements_str.join(" ")
-                        )?;
-                    }
-                }
-            }
-
-            // Write attributes for this graph section
-            writeln!(writer, "# Attributes")?;
-
-            // Write attributes for nodes
-            for node_idx in graph._graph.node_indices() {
-                if let Some(node) = graph._graph.node_weight(node_idx) {
-                    for attr in node.attributes.values() {
-                        writeln!(writer, "A\tN\t{}\t{}", node.id, attr)?;
-                    }
-                }
-            }
-
-            // Write attributes for edges
-            for edge_idx in graph._graph.edge_indices() {
-                if let Some(edge) = graph._graph.edge_weight(edge_idx) {
-                    for attr in edge.attributes.values() {
-                        writeln!(writer, "A\tE\t{}\t{}", edge.id, attr)?;
-                    }
-                }
-            }
+        if !self.headers.contains(&new_header) {
+            writeln!(writer, "{}", new_header)?;
+        }
+        for comment in &self.comments {
+            writeln!(writer, "#{}", comment)?;
+        }
 
-            // Write attributes for groups
-            for (id, group) in &graph.groups {
-                let (group_type, attributes) = match group {
-                    Group::Unordered { attributes, .. } => ("U", attributes),
-                    Group::Ordered { attributes, .. } => ("P", attributes),
-                    Group::Chain { attributes, .. } => ("C", attributes),
-                };
+        // The default graph has no G record, so it must come before any other section
+        let mut graphs = self.ordered_graphs(options.ordering);
+        graphs.sort_by_key(|graph| graph.id != DEFAULT_GRAPH_ID);
 
-                for attr in attributes.values() {
-                    writeln!(
-                        writer,
-                        "A\t{}\t{}\t{}",
-                        group_type,
-                        id.to_str().unwrap_or(""),
-                        attr
-                    )?;
-                }
-            }
+        // Write each graph section
+        for graph in graphs {
+            graph.to_writer_with_options(writer, options)?;
         }
 
         // Write inter-graph links
         if !self.links.is_empty() {
             writeln!(writer, "\n# Inter-graph links")?;
             for link in &self.links {
-                write!(
-                    writer,
-                    "L\t{}\t{}:{}\t{}:{}\t{}",
-                    link.id,
-                    link.source_graph,
-                    link.source_element,
-                    link.target_graph,
-                    link.target_element,
-                    link.link_type
-                )?;
-
-                for attr in link.attributes.values() {
-                    write!(writer, "\t{}", attr)?;
-                }
-                writeln!(writer)?;
+                writeln!(writer, "{}", link)?;
             }
         }
 
@@ -1180,10 +1928,23 @@ impl TSGraph {
     }
 
     /// Write the TSGraph to a TSG file
+    ///
+    /// The output is compressed when the path ends in `.gz` (gzip), `.bgz` (BGZF)
+    /// or `.zst` (zstd).
     pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        let file = File::create(path)?;
-        let mut writer = BufWriter::new(file);
-        self.to_writer(&mut writer)
+        self.to_file_with_options(path, &WriteOptions::default())
+    }
+
+    /// Write the TSGraph to a TSG file with the given options
+    pub fn to_file_with_options<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &WriteOptions,
+    ) -> Result<()> {
+        let mut writer = create_writer(path)?;
+        self.to_writer_with_options(&mut writer, options)?;
+        writer.finish()?;
+        Ok(())
     }
 
     // Helper methods for accessing graph elements
@@ -1289,43 +2050,33 @@ impl TSGraph {
         graph.edge_by_idx(edge_idx)
     }
 
-    /// Get all nodes in the graph
+    /// Get all nodes in the graph, ordered by genomic position and then ID
     pub fn nodes(&self, graph_id: &str) -> Vec<&NodeData> {
         let graph = self.graphs.get(&BString::from(graph_id)).unwrap();
-        graph
-            .node_indices
-            .values()
-            .filter_map(|&idx| graph._graph.node_weight(idx))
-            .collect()
+        graph.nodes()
     }
 
-    /// Get all edges in the graph
+    /// Get all edges in the graph, ordered by their source and target nodes
     pub fn edges(&self, graph_id: &str) -> Vec<&EdgeData> {
         let graph = self.graphs.get(&BString::from(graph_id)).unwrap();
-        graph
-            .edge_indices
-            .values()
-            .filter_map(|&idx| graph._graph.edge_weight(idx))
-            .collect()
+        graph.edges()
     }
 
     /// Traverse the graph and return all valid paths from source nodes to sink nodes.
-    pub fn traverse_by_id(&self, graph_id: &str) -> Result<Vec<TSGPath>> {
+    pub fn traverse_by_id(&self, graph_id: &str) -> Result<Vec<TSGPath<'_>>> {
         let graph = self.graphs.get(&BString::from(graph_id)).unwrap();
         graph.traverse()
     }
 
-    /// traverse all graphs
-    pub fn traverse_all_graphs(&self) -> Result<Vec<TSGPath>> {
-        let all_paths = self
-            .graphs
-            .values()
+    /// traverse all graphs, in graph ID order
+    pub fn traverse_all_graphs(&self) -> Result<Vec<TSGPath<'_>>> {
+        self.ordered_graphs(OrderingPolicy::Sorted)
+            .into_iter()
             .try_fold(Vec::new(), |mut all_paths, graph| {
                 let paths = graph.traverse()?;
                 all_paths.extend(paths);
                 Ok(all_paths)
-            });
-        all_paths
+            })
     }
 
     pub fn to_dot_by_id(
@@ -1345,8 +2096,8 @@ impl TSGraph {
 }
 
 impl FromStr for TSGraph {
-    type Err = anyhow::Error;
-    fn from_str(s: &str) -> Result<Self> {
+    type Err = TsgError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         let reader = BufReader::new(s.as_bytes());
         Self::from_reader(reader)
     }
@@ -1418,6 +2169,90 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_add_parallel_edges() -> Result<()> {
+        let tsg_string = "N\tn1\tchr1:+:100-200\tr1:SO,r2:SO
+N\tn2\tchr1:+:300-400\tr1:SI,r2:SI
+E\te1\tn1\tn2\tchr1,chr1,200,300,splice
+E\te2\tn1\tn2\tchr1,chr1,200,300,DUP
+P\tvia_dup\tn1+ e2+ n2+
+";
+        let tsg = TSGraph::from_str(tsg_string)?;
+        let graph = tsg.default_graph().unwrap();
+        assert_eq!(graph.edges().len(), 2);
+        assert_eq!(graph.edge_by_id("e1").unwrap().sv.sv_type, "splice");
+        assert_eq!(graph.edge_by_id("e2").unwrap().sv.sv_type, "DUP");
+        let paths = graph.traverse()?;
+        assert_eq!(paths.len(), 2, "traversal branches on each parallel edge");
+        assert_ne!(paths[0].id()?, paths[1].id()?);
+
+        let replace = ParseOptions::builder()
+            .edge_conflict(EdgeConflictPolicy::Replace)
+            .build();
+        let (tsg, _) = TSGraph::from_reader_with_options(tsg_string.as_bytes(), &replace)?;
+        let graph = tsg.default_graph().unwrap();
+        assert_eq!(graph.edges().len(), 1);
+        assert!(graph.edge_by_id("e1").is_none());
+        assert_eq!(graph.edge_by_id("e2").unwrap().sv.sv_type, "DUP");
+        assert_eq!(graph.edge_indices.len(), 1);
+
+        // Links parsed before the replacing edge follow it to its new ID
+        let linked = "G\tg2\nN\tm1\tchr2:+:100-200\tr1:SO\n\
+            G\tg1\nN\tn1\tchr1:+:100-200\tr1:SO\nN\tn2\tchr1:+:300-400\tr1:SI\n\
+            E\te1\tn1\tn2\tchr1,chr1,200,300,splice\n\
+            L\tl1\tg1:e1\tg2:m1\tfusion\n\
+            E\te2\tn1\tn2\tchr1,chr1,200,300,DUP\n";
+        let (tsg, _) = TSGraph::from_reader_with_options(linked.as_bytes(), &replace)?;
+        assert_eq!(tsg.links.len(), 1);
+        assert_eq!(tsg.links[0].source_element, "e2");
+
+        // So do the links of edges replaced through the API, unless the ID is taken
+        let mut tsg = TSGraph::from_str(linked)?;
+        let edge = |id: &str| EdgeData {
+            id: id.into(),
+            ..Default::default()
+        };
+        let policy = EdgeConflictPolicy::Replace;
+        assert!(
+            tsg.add_edge_with_policy("g1", "n1".into(), "n2".into(), edge("n1"), policy)
+                .is_err()
+        );
+        tsg.add_edge_with_policy("g1", "n1".into(), "n2".into(), edge("e2"), policy)?;
+        assert_eq!(tsg.links[0].source_element, "e1");
+        tsg.add_edge_with_policy("g1", "n1".into(), "n2".into(), edge("e3"), policy)?;
+        assert_eq!(tsg.links[0].source_element, "e3");
+        assert!(tsg.graph("g1").unwrap().edge_by_id("e1").is_none());
+
+        // Of parallel edges, the earliest added is replaced
+        let mut tsg = TSGraph::from_str(tsg_string)?;
+        let graph = tsg.default_graph_mut().unwrap();
+        let edge = EdgeData {
+            id: "e3".into(),
+            ..Default::default()
+        };
+        graph.add_edge_with_policy("n1".into(), "n2".into(), edge, EdgeConflictPolicy::Replace)?;
+        let edge_ids: Vec<_> = graph.edge_indices().keys().collect();
+        assert_eq!(edge_ids, ["e3", "e2"]);
+
+        let error = ParseOptions::builder()
+            .edge_conflict(EdgeConflictPolicy::Error)
+            .build();
+        let err = TSGraph::from_reader_with_options(tsg_string.as_bytes(), &error).unwrap_err();
+        assert_eq!(err.line(), Some(4));
+
+        // Re-adding an edge under its own ID updates it in place
+        let mut tsg = TSGraph::from_str(tsg_string)?;
+        let graph = tsg.default_graph_mut().unwrap();
+        let edge = EdgeData {
+            id: "e1".into(),
+            ..Default::default()
+        };
+        graph.add_edge("n1".into(), "n2".into(), edge.clone())?;
+        assert_eq!(graph.edges().len(), 2);
+        assert!(graph.add_edge("n2".into(), "n1".into(), edge).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_parse_header_line() -> Result<()> {
         let mut graph = TSGraph::new();
@@ -1437,7 +2272,7 @@ mod tests {
         let mut graph = TSGraph::new();
         let line = "N\tnode1\tchr1:+:100-200\tread1:SO,read2:IN\tACGT";
 
-        graph.parse_node_line(line)?;
+        graph.parse_node_line(line, &mut HashSet::default())?;
 
         // let node = graph.get_node("defaul, "node1").unwrap();
         let node = graph.default_graph().unwrap().node_by_id("node1").unwrap();
@@ -1489,11 +2324,235 @@ mod tests {
         assert_eq!(graph.nodes(DEFAULT_GRAPH_ID).len(), 5);
         assert_eq!(graph.edges(DEFAULT_GRAPH_ID).len(), 4);
 
-        graph.to_file("tests/data/test_write.tsg")?;
+        graph.to_file(std::env::temp_dir().join("tsg_graph_test_write.tsg"))?;
 
         Ok(())
     }
 
+    #[test]
+    fn test_read_write_compressed() -> Result<()> {
+        let graph = TSGraph::from_file("tests/data/test_mul.tsg")?;
+
+        for ext in ["gz", "bgz", "zst"] {
+            let path = std::env::temp_dir().join(format!("tsg_graph_test.tsg.{}", ext));
+            graph.to_file(&path)?;
+            let reloaded = TSGraph::from_file(&path)?;
+            assert_eq!(reloaded.headers[..2], graph.headers[..]);
+            assert_eq!(reloaded.graphs.len(), 2);
+            assert_eq!(reloaded.nodes("gene_a").len(), 3);
+            assert_eq!(reloaded.edges("gene_b").len(), 2);
+            assert_eq!(reloaded.links.len(), 1);
+            std::fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Edges of a graph by ID, with the IDs of their endpoints
+    fn edges_with_endpoints(graph: &GraphSection) -> HashMap<&BString, (&EdgeData, &BStr, &BStr)> {
+        let inner = graph.inner_graph();
+        inner
+            .edge_references()
+            .map(|edge| {
+                let source = inner[edge.source()].id.as_bstr();
+                let target = inner[edge.target()].id.as_bstr();
+                (&edge.weight().id, (edge.weight(), source, target))
+            })
+            .collect()
+    }
+
+    /// Assert that two graphs hold the same records, field by field
+    fn assert_same_content(actual: &TSGraph, expected: &TSGraph, context: &str) {
+        // The writer adds a PG header when it is missing
+        let pg = Header::builder().tag("PG").value("tsg").build();
+        let headers: Vec<_> = actual
+            .headers
+            .iter()
+            .filter(|header| **header != pg || expected.headers.contains(&pg))
+            .collect();
+        assert_eq!(
+            headers,
+            expected.headers.iter().collect::<Vec<_>>(),
+            "{}",
+            context
+        );
+        assert_eq!(actual.comments, expected.comments, "{}", context);
+        assert_eq!(actual.links, expected.links, "{}", context);
+
+        let mut graph_ids: Vec<_> = actual.graphs.keys().collect();
+        graph_ids.sort();
+        let mut expected_ids: Vec<_> = expected.graphs.keys().collect();
+        expected_ids.sort();
+        assert_eq!(graph_ids, expected_ids, "{}", context);
+
+        for (id, graph) in &actual.graphs {
+            let expected = &expected.graphs[id];
+            let context = format!("{}: graph {}", context, id);
+            assert_eq!(graph.attributes, expected.attributes, "{}", context);
+            assert_eq!(graph.comments, expected.comments, "{}", context);
+            assert_eq!(graph.nodes(), expected.nodes(), "{}", context);
+            assert_eq!(
+                edges_with_endpoints(graph),
+                edges_with_endpoints(expected),
+                "{}",
+                context
+            );
+            assert_eq!(graph.groups, expected.groups, "{}", context);
+            assert_eq!(graph.chains, expected.chains, "{}", context);
+        }
+    }
+
+    #[test]
+    fn test_write_roundtrip_all_data() -> Result<()> {
+        for entry in std::fs::read_dir("tests/data")? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "tsg") {
+                continue;
+            }
+
+            let tsg = TSGraph::from_file(&path)?;
+            let mut first = Vec::new();
+            tsg.to_writer(&mut first)?;
+
+            let reparsed = TSGraph::from_reader(first.as_slice())?;
+            let mut second = Vec::new();
+            reparsed.to_writer(&mut second)?;
+
+            assert_eq!(
+                first.as_bstr(),
+                second.as_bstr(),
+                "{}: output is not stable",
+                path.display()
+            );
+            assert_same_content(&reparsed, &tsg, &path.display().to_string());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_roundtrip_comments() -> Result<()> {
+        let content = "# file comment\n\
+            H\tTSG\t1.0\n\
+            G\tg1\n\
+            # section comment\n\
+            N\tn1\tchr1:+:100-200\tr1:SO\n\
+            #no space\n";
+        let tsg = TSGraph::from_str(content)?;
+        assert_eq!(tsg.comments, vec![BString::from(" file comment")]);
+        assert_eq!(
+            tsg.graph("g1").unwrap().comments,
+            vec![BString::from(" section comment"), BString::from("no space")]
+        );
+
+        let mut buf = Vec::new();
+        tsg.to_writer(&mut buf)?;
+        let reparsed = TSGraph::from_reader(buf.as_slice())?;
+        assert_same_content(&reparsed, &tsg, "comments");
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_ordering() -> Result<()> {
+        let content = "G\tg2\n\
+            N\tb\tchr1:+:300-400\tr1:SI\n\
+            N\ta\tchr1:+:100-200\tr1:SO\n\
+            E\te1\ta\tb\tchr1,chr1,200,300,splice\n\
+            P\tp2\ta+ e1+ b+\n\
+            P\tp1\ta+ e1+ b+\n\
+            G\tg1\n\
+            N\tc\tchr2:+:100-200\tr2:SO\n";
+        let tsg = TSGraph::from_str(content)?;
+
+        let record_ids = |options: &WriteOptions| -> Result<Vec<String>> {
+            let mut buf = Vec::new();
+            tsg.to_writer_with_options(&mut buf, options)?;
+            Ok(String::from_utf8(buf)?
+                .lines()
+                .filter(|line| matches!(line.chars().next(), Some('G' | 'N' | 'P')))
+                .map(|line| line.split('\t').nth(1).unwrap().to_string())
+                .collect())
+        };
+
+        assert_eq!(
+            record_ids(&WriteOptions::sorted())?,
+            ["g1", "c", "g2", "a", "b", "p1", "p2"]
+        );
+        assert_eq!(
+            record_ids(&WriteOptions::insertion())?,
+            ["g2", "b", "a", "p2", "p1", "g1", "c"]
+        );
+
+        let node_ids: Vec<_> = tsg.nodes("g2").iter().map(|node| &node.id).collect();
+        assert_eq!(node_ids, ["a", "b"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_direct_inserts_keep_insertion_order() -> Result<()> {
+        let mut tsg = TSGraph::from_str("G\tg2\nN\tb\tchr1:+:300-400\tr1:SI\n")?;
+        tsg.graphs
+            .insert("g1".into(), GraphSection::new("g1".into()));
+        let graph = tsg.graph_mut("g2").unwrap();
+        graph.add_group(
+            "u0".into(),
+            Group::Unordered {
+                id: "u0".into(),
+                elements: vec!["b".into()],
+                attributes: Default::default(),
+            },
+        );
+        assert_eq!(graph.nodes()[0].id, "b");
+
+        // The sorted node order is refreshed after the graph changes
+        graph.add_node("N\ta\tchr1:+:100-200\tr1:SO".parse()?)?;
+        let node_ids: Vec<_> = graph.nodes().iter().map(|node| &node.id).collect();
+        assert_eq!(node_ids, ["a", "b"]);
+
+        let mut buf = Vec::new();
+        tsg.to_writer_with_options(&mut buf, &WriteOptions::insertion())?;
+        let record_ids: Vec<_> = String::from_utf8(buf)?
+            .lines()
+            .filter(|line| matches!(line.chars().next(), Some('G' | 'N' | 'U')))
+            .map(|line| line.split('\t').nth(1).unwrap().to_string())
+            .collect();
+        assert_eq!(record_ids, ["g2", "b", "a", "u0", "g1"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_insertion_order_survives_edits() -> Result<()> {
+        let tsg_string = "N\ta\tchr1:+:100-200\tr1:SO\n\
+            N\tb\tchr1:+:300-400\tr1:IN\n\
+            N\tc\tchr1:+:500-600\tr1:IN\n\
+            N\td\tchr1:+:700-800\tr1:SI\n\
+            E\te1\ta\tb\tchr1,chr1,200,300,splice\n\
+            E\te2\tb\tc\tchr1,chr1,400,500,splice\n\
+            E\te3\tc\td\tchr1,chr1,600,700,splice\n";
+        let mut tsg = TSGraph::from_str(tsg_string)?;
+        let graph = tsg.default_graph_mut().unwrap();
+
+        // petgraph moves the last node and edge into the indices freed by the removal
+        graph.remove_node("a").unwrap();
+        graph.rename_node("c", "x")?;
+        graph.rename_edge("e2", "e4")?;
+
+        let node_ids: Vec<_> = graph
+            .ordered_node_indices(OrderingPolicy::Insertion)
+            .into_iter()
+            .map(|idx| graph.node_by_idx(idx).unwrap().id.to_string())
+            .collect();
+        assert_eq!(node_ids, ["b", "x", "d"]);
+        let edge_ids: Vec<_> = graph
+            .ordered_edge_indices(OrderingPolicy::Insertion)
+            .into_iter()
+            .map(|idx| graph.edge_by_idx(idx).unwrap().id.to_string())
+            .collect();
+        assert_eq!(edge_ids, ["e4", "e3"]);
+        let keys: Vec<_> = graph.node_indices().keys().collect();
+        assert_eq!(keys, ["b", "x", "d"]);
+        Ok(())
+    }
+
     #[test]
     fn test_from_str() -> Result<()> {
         let tsg_string = r#"H	VN	1.0
@@ -1541,6 +2600,115 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_from_str_errors() {
+        let err = TSGraph::from_str("H\tVN\t1.0\nN\tnode1\tchr1:+:200-x\tread1:SO\n").unwrap_err();
+        assert!(matches!(err, TsgError::MalformedNode { .. }));
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.field(), Some("exons"));
+
+        let err = TSGraph::from_str("N\tnode1\tchr1:+:100-200\tread1:XX\n").unwrap_err();
+        assert_eq!(err.field(), Some("reads"));
+
+        let err = TSGraph::from_str("E\tedge1\tnode1\tnode2\tchr1,chr1,1700\n").unwrap_err();
+        assert!(matches!(err, TsgError::MalformedEdge { .. }));
+        assert_eq!(err.field(), Some("sv"));
+
+        let err = TSGraph::from_str("G\tg1\nG\tg2\nG\tg1\n").unwrap_err();
+        assert!(matches!(err, TsgError::DuplicateId { ref id, .. } if id == "g1"));
+        assert_eq!(err.line(), Some(3));
+
+        let err = TSGraph::from_str("N\tnode1\tchr1:+:100-200\tread1:SO\nA\tX\tnode1\tcov:i:1\n")
+            .unwrap_err();
+        assert!(matches!(err, TsgError::UnknownRecordType { ref value, .. } if value == "X"));
+
+        let tsg_string = "N\tnode1\tchr1:+:100-200\tread1:SO\n\nP\tpath1\tnode1+ node9+\n";
+        let err = TSGraph::from_str(tsg_string).unwrap_err();
+        assert!(matches!(err, TsgError::DanglingReference { ref id, .. } if id == "node9"));
+        assert_eq!(err.line(), Some(3));
+        assert_eq!(err.record(), Some("P"));
+
+        let tsg_string = "N\tnode1\tchr1:+:100-200\tread1:SO\nX\tsomething\n";
+        let err = TSGraph::from_str(tsg_string).unwrap_err();
+        assert!(matches!(err, TsgError::UnknownRecordType { ref value, .. } if value == "X"));
+        assert_eq!(err.line(), Some(2));
+        let (_, diagnostics) =
+            TSGraph::from_reader_with_options(tsg_string.as_bytes(), &ParseOptions::lenient())
+                .unwrap();
+        assert_eq!(diagnostics.len(), 1);
+
+        // A node first seen as an edge endpoint may still be declared only once
+        let tsg_string = "E\te1\tn1\tn2\tchr1,chr1,200,300,splice\n\
+            N\tn1\tchr1:+:100-200\tread1:SO\n\
+            N\tn1\tchr1:+:100-200\tread1:SO\n";
+        let err = TSGraph::from_str(tsg_string).unwrap_err();
+        assert!(matches!(err, TsgError::DuplicateId { ref id, .. } if id == "n1"));
+        assert_eq!(err.line(), Some(3));
+
+        // Graphs built from chains report the chain that contradicts the others
+        let tsg_string = "G\tg1\nC\tc1\tn1\te1\tn2\nC\tc2\tn2\te1\tn3\n";
+        let err = TSGraph::from_str(tsg_string).unwrap_err();
+        assert_eq!(err.record(), Some("C"));
+        assert_eq!(err.line(), Some(3));
+    }
+
+    #[test]
+    fn test_from_reader_lenient() -> Result<()> {
+        let tsg_string = "N\tnode1\tchr1:+:100-200\tread1:SO
+N\tnode2\tchr1:+:300-x\tread1:SO
+N\tnode3\tchr1:+:500-600\tread1:SO
+E\tedge1\tnode1\tnode3\tchr1,chr1,200,500,SPLICE
+A\tQ\tnode1\tcov:i:1
+P\tpath1\tnode1+ edge1+ node9+
+";
+        assert!(TSGraph::from_str(tsg_string).is_err());
+
+        let (graph, diagnostics) =
+            TSGraph::from_reader_with_options(tsg_string.as_bytes(), &ParseOptions::lenient())?;
+
+        assert_eq!(graph.nodes(DEFAULT_GRAPH_ID).len(), 2);
+        assert_eq!(graph.edges(DEFAULT_GRAPH_ID).len(), 1);
+        assert!(graph.default_graph().unwrap().groups.is_empty());
+
+        let lines: Vec<_> = diagnostics.iter().map(|err| err.line()).collect();
+        assert_eq!(lines, vec![Some(2), Some(5), Some(6)]);
+        assert_eq!(diagnostics[0].record(), Some("N"));
+        assert_eq!(diagnostics[1].field(), Some("element_type"));
+        assert_eq!(diagnostics[2].record(), Some("P"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_reader_lenient_rejected_graph() -> Result<()> {
+        let tsg_string = "G\tg1
+N\tn1\tchr1:+:100-200\tr1:SO
+N\tn2\tchr1:+:300-400\tr1:SI
+E\te1\tn1\tn2\tchr1,chr1,200,300,splice
+G\tg1
+N\tn3\tchr1:+:500-600\tr2:SO
+G\tg2\tbad_attr
+N\tn4\tchr1:+:700-800\tr3:SO
+G\tg3
+N\tn5\tchr1:+:900-1000\tr4:SO
+";
+        let (graph, diagnostics) =
+            TSGraph::from_reader_with_options(tsg_string.as_bytes(), &ParseOptions::lenient())?;
+
+        // Records after a rejected G record are skipped rather than merged into g1
+        let ids: Vec<_> = graph.graphs.keys().map(|id| id.to_string()).collect();
+        assert_eq!(ids, ["g1", "g3"]);
+        assert_eq!(graph.nodes("g1").len(), 2);
+        assert_eq!(graph.nodes("g3").len(), 1);
+
+        let lines: Vec<_> = diagnostics.iter().map(|err| err.line()).collect();
+        assert_eq!(lines, [Some(5), Some(6), Some(7), Some(8)]);
+        assert_eq!(diagnostics[0].record(), Some("G"));
+        assert_eq!(diagnostics[1].record(), Some("N"));
+        assert_eq!(diagnostics[1].field(), Some("graph_id"));
+        Ok(())
+    }
+
     #[test]
     fn test_traverse() -> Result<()> {
         let file = "tests/data/test.tsg";
@@ -1562,6 +2730,7 @@ mod tests {
 
         let dot = graph.to_dot_by_id(DEFAULT_GRAPH_ID, true, true)?;
         println!("{}", dot);
+        assert!(dot.contains("\"n3\" -> \"n4\" [id=\"e2\", label=\"DUP\""));
 
         Ok(())
     }
@@ -1573,6 +2742,29 @@ mod tests {
 
         let json = graph.to_json_by_id(DEFAULT_GRAPH_ID)?;
         println!("{}", json);
+
+        // n3 has the most reads and e1, e2 and e4 the most shared ones
+        let nodes = json["elements"]["nodes"].as_array().unwrap();
+        let n3 = nodes
+            .iter()
+            .find(|node| node["data"]["id"] == "n3")
+            .unwrap();
+        assert_eq!(n3["data"]["read_count"], 4);
+        assert_eq!(n3["data"]["size"], 60.0);
+        assert_eq!(n3["data"]["color"], FORWARD_COLOR);
+        let n4 = nodes
+            .iter()
+            .find(|node| node["data"]["id"] == "n4")
+            .unwrap();
+        assert_eq!(n4["data"]["size"], 40.0);
+        assert_eq!(n4["data"]["color"], REVERSE_COLOR);
+        let edges = json["elements"]["edges"].as_array().unwrap();
+        let e3 = edges
+            .iter()
+            .find(|edge| edge["data"]["id"] == "e3")
+            .unwrap();
+        assert_eq!(e3["data"]["width"], 5.5);
+        assert_eq!(json["style"][0]["selector"], "node");
         Ok(())
     }
 }
@@ -1,38 +1,241 @@
-use crate::graph::TSGraph;
+use super::{GraphSection, Group, OrderingPolicy, node_position_key};
+use ahash::{HashSet, HashSetExt};
 use anyhow::Result;
+use bstr::BString;
+use petgraph::Direction;
+use petgraph::algo::{connected_components, is_cyclic_directed};
 use petgraph::graph::NodeIndex;
+use std::collections::BTreeMap;
 
-// TODO this module is not used yet, but it will be used in the future
-
-#[allow(dead_code)]
+/// Structural analyses of a graph section
 pub trait GraphAnalysis {
+    /// Whether every node is reachable from every other, ignoring edge directions
     fn is_connected(&self) -> bool;
     fn is_cyclic(&self) -> bool;
+    /// Alternative splicing bubbles, each listed as its source node, the nodes inside it
+    /// by genomic position, and its sink node last
     fn detect_bubbles(&self) -> Vec<Vec<NodeIndex>>;
     fn is_directed_acyclic_graph(&self) -> bool {
         self.is_connected() && !self.is_cyclic()
     }
-    fn summarize(&self) -> Result<()>;
+    fn summarize(&self) -> Result<GraphSummary>;
+}
+
+/// Structured report of the shape of a graph section
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GraphSummary {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
+    pub id: BString,
+    pub nodes: usize,
+    pub edges: usize,
+    /// Number of paths (`P` groups)
+    pub paths: usize,
+    pub chains: usize,
+    /// Number of distinct reads over all nodes
+    pub reads: usize,
+    /// Number of weakly connected components
+    pub components: usize,
+    pub is_connected: bool,
+    pub is_cyclic: bool,
+    /// Nodes without incoming edges, and without outgoing edges for sinks
+    pub sources: usize,
+    pub sinks: usize,
+    pub bubbles: usize,
+    /// Number of edges by structural variant type
+    pub sv_types: BTreeMap<String, usize>,
 }
 
-impl GraphAnalysis for TSGraph {
+impl GraphSection {
+    /// Sink of the bubble opened by `source`, with the nodes visited up to it
+    ///
+    /// This is the superbubble search of Onodera et al. (2013): the nodes reached from
+    /// `source` are visited once all their parents are, and the bubble closes when a
+    /// single node remains that every branch has reached. Tips and cycles through the
+    /// source end the search.
+    fn bubble_from(&self, source: NodeIndex) -> Option<(NodeIndex, Vec<NodeIndex>)> {
+        let graph = self.inner_graph();
+        let mut visited = HashSet::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![source];
+        seen.insert(source);
+
+        while let Some(node) = stack.pop() {
+            visited.insert(node);
+            seen.remove(&node);
+            let mut children = graph
+                .neighbors_directed(node, Direction::Outgoing)
+                .peekable();
+            children.peek()?;
+            for child in children {
+                if child == source || visited.contains(&child) {
+                    return None;
+                }
+                seen.insert(child);
+                if graph
+                    .neighbors_directed(child, Direction::Incoming)
+                    .all(|parent| visited.contains(&parent))
+                    && !stack.contains(&child)
+                {
+                    stack.push(child);
+                }
+            }
+            if stack.len() == 1 && seen.len() == 1 && seen.contains(&stack[0]) {
+                let sink = stack[0];
+                if graph.contains_edge(sink, source) {
+                    return None;
+                }
+                visited.remove(&source);
+                return Some((sink, visited.into_iter().collect()));
+            }
+        }
+        None
+    }
+}
+
+impl GraphAnalysis for GraphSection {
     fn is_connected(&self) -> bool {
-        // Implementation here
-        unimplemented!()
+        connected_components(self.inner_graph()) <= 1
     }
 
     fn is_cyclic(&self) -> bool {
-        // Implementation here
-        unimplemented!()
+        is_cyclic_directed(self.inner_graph())
     }
 
     fn detect_bubbles(&self) -> Vec<Vec<NodeIndex>> {
-        // Implementation here
-        unimplemented!()
+        let graph = self.inner_graph();
+        let position = |idx: &NodeIndex| node_position_key(&graph[*idx]);
+        self.ordered_node_indices(OrderingPolicy::Sorted)
+            .into_iter()
+            .filter(|&node| {
+                graph
+                    .neighbors_directed(node, Direction::Outgoing)
+                    .collect::<HashSet<_>>()
+                    .len()
+                    > 1
+            })
+            .filter_map(|source| {
+                let (sink, mut inner) = self.bubble_from(source)?;
+                inner.sort_by(|a, b| position(a).cmp(&position(b)));
+                let mut bubble = Vec::with_capacity(inner.len() + 2);
+                bubble.push(source);
+                bubble.extend(inner);
+                bubble.push(sink);
+                Some(bubble)
+            })
+            .collect()
     }
 
-    fn summarize(&self) -> Result<()> {
-        // Implementation here
-        unimplemented!()
+    fn summarize(&self) -> Result<GraphSummary> {
+        let graph = self.inner_graph();
+        let components = connected_components(graph);
+        let reads: HashSet<&BString> = graph
+            .node_weights()
+            .flat_map(|node| node.reads.iter().map(|read| &read.id))
+            .collect();
+        let degree_zero = |direction| {
+            graph
+                .node_indices()
+                .filter(|&node| graph.neighbors_directed(node, direction).next().is_none())
+                .count()
+        };
+        let mut sv_types = BTreeMap::new();
+        for edge in graph.edge_weights() {
+            *sv_types.entry(edge.sv.sv_type.to_string()).or_default() += 1;
+        }
+
+        Ok(GraphSummary {
+            id: self.id.clone(),
+            nodes: graph.node_count(),
+            edges: graph.edge_count(),
+            paths: self
+                .groups
+                .values()
+                .filter(|group| matches!(group, Group::Ordered { .. }))
+                .count(),
+            chains: self.chains.len(),
+            reads: reads.len(),
+            components,
+            is_connected: components <= 1,
+            is_cyclic: self.is_cyclic(),
+            sources: degree_zero(Direction::Incoming),
+            sinks: degree_zero(Direction::Outgoing),
+            bubbles: self.detect_bubbles().len(),
+            sv_types,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::TSGraph;
+    use std::str::FromStr;
+
+    /// Exon skipping from a to d, and the alternative b or c in the skipped part
+    const BUBBLE_TSG: &str = "N\ta\tchr1:+:100-200\tr1:SO,r2:SO,r3:SO\n\
+        N\tb\tchr1:+:300-400\tr1:IN\n\
+        N\tc\tchr1:+:500-600\tr2:IN\n\
+        N\td\tchr1:+:700-800\tr1:SI,r2:SI,r3:SI\n\
+        E\te1\ta\tb\tchr1,chr1,200,300,splice\n\
+        E\te2\ta\tc\tchr1,chr1,200,500,splice\n\
+        E\te3\tb\td\tchr1,chr1,400,700,splice\n\
+        E\te4\tc\td\tchr1,chr1,600,700,splice\n\
+        E\te5\ta\td\tchr1,chr1,200,700,splice\n";
+
+    #[test]
+    fn test_detect_bubbles() -> Result<()> {
+        let tsg = TSGraph::from_str(BUBBLE_TSG)?;
+        let graph = tsg.default_graph().unwrap();
+        let ids = |bubble: &Vec<NodeIndex>| -> Vec<String> {
+            bubble
+                .iter()
+                .map(|&idx| graph.node_by_idx(idx).unwrap().id.to_string())
+                .collect()
+        };
+        let bubbles = graph.detect_bubbles();
+        assert_eq!(bubbles.len(), 1);
+        assert_eq!(ids(&bubbles[0]), ["a", "b", "c", "d"]);
+        assert!(graph.is_connected());
+        assert!(!graph.is_cyclic());
+        assert!(graph.is_directed_acyclic_graph());
+
+        let tsg = TSGraph::from_file("tests/data/test.tsg")?;
+        let graph = tsg.default_graph().unwrap();
+        assert!(
+            graph.detect_bubbles().is_empty(),
+            "n3 branches into two sinks without reconverging"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_summarize() -> Result<()> {
+        let tsg = TSGraph::from_file("tests/data/test.tsg")?;
+        let summary = tsg.default_graph().unwrap().summarize()?;
+        assert_eq!(summary.nodes, 5);
+        assert_eq!(summary.edges, 4);
+        assert_eq!(summary.paths, 2);
+        assert_eq!(summary.chains, 2);
+        assert_eq!(summary.reads, 5);
+        assert_eq!(summary.components, 1);
+        assert!(summary.is_connected && !summary.is_cyclic);
+        assert_eq!((summary.sources, summary.sinks), (2, 2));
+        assert_eq!(summary.bubbles, 0);
+        assert_eq!(summary.sv_types["DUP"], 2);
+
+        let cyclic = TSGraph::from_str(&format!(
+            "{}E\te6\td\ta\tchr1,chr1,800,100,DUP\nN\tz\tchr2:+:1-10\tr9:SO\n",
+            BUBBLE_TSG
+        ))?;
+        let summary = cyclic.default_graph().unwrap().summarize()?;
+        assert!(summary.is_cyclic);
+        assert!(!summary.is_connected);
+        assert_eq!(summary.components, 2);
+        assert_eq!(summary.bubbles, 0, "the cycle runs back into the source");
+        Ok(())
     }
 }
@@ -16,12 +16,22 @@ pub enum AttributeValue {
 }
 
 /// Represents an optional attribute
-#[derive(Debug, Clone, Builder, Default)]
+#[derive(Debug, Clone, Builder, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[builder(on(BString, into))]
 pub struct Attribute {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub tag: BString,
     #[builder(default = 'Z')]
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub attribute_type: char,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub value: BString,
 }
 
@@ -194,6 +204,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_attribute_as_float() {
         let attr = Attribute {
             tag: "ptf".into(),
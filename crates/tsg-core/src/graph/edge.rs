@@ -1,49 +1,58 @@
+use std::fmt;
 use std::str::FromStr;
-use std::{fmt, io};
 
 use ahash::HashMap;
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use bon::Builder;
-use bstr::{BString, ByteVec};
+use bstr::BString;
 
-use super::Attribute;
+use super::utils::{bed_score, vcf_escape};
+use super::{Attribute, Strand, TsgError};
 
-#[derive(Debug, Builder, Clone, Default)]
+#[derive(Debug, Builder, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[builder(on(BString, into))]
 pub struct StructuralVariant {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub reference_name1: BString,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub reference_name2: BString,
     pub breakpoint1: usize,
     pub breakpoint2: usize,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub sv_type: BString,
 }
 
 impl FromStr for StructuralVariant {
-    type Err = io::Error;
+    type Err = TsgError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // E  <id>  <source_id>  <sink_id>  <SV>
         let parts: Vec<&str> = s.split(',').collect();
         if parts.len() != 5 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
+            return Err(TsgError::malformed(
+                "E",
+                "sv",
                 format!("Invalid SV format: {}", s),
             ));
         }
 
-        let breakpoint1 = parts[2].parse::<usize>().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Invalid breakpoint1: {}", e),
-            )
-        })?;
+        let breakpoint1 = parts[2]
+            .parse::<usize>()
+            .map_err(|e| TsgError::malformed("E", "sv", format!("Invalid breakpoint1: {}", e)))?;
 
-        let breakpoint2 = parts[3].parse::<usize>().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Invalid breakpoint2: {}", e),
-            )
-        })?;
+        let breakpoint2 = parts[3]
+            .parse::<usize>()
+            .map_err(|e| TsgError::malformed("E", "sv", format!("Invalid breakpoint2: {}", e)))?;
 
         Ok(StructuralVariant {
             reference_name1: parts[0].into(),
@@ -70,39 +79,216 @@ impl fmt::Display for StructuralVariant {
 }
 
 /// Edge in the transcript segment graph
-#[derive(Debug, Clone, Builder, Default)]
+#[derive(Debug, Clone, Builder, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[builder(on(BString, into))]
 pub struct EdgeData {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub id: BString,
     pub sv: StructuralVariant,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, with = "crate::graph::serialization::attributes")
+    )]
     pub attributes: HashMap<BString, Attribute>,
 }
 
 impl EdgeData {
-    pub fn to_vcf(&self, attributes: Option<&[Attribute]>) -> Result<BString> {
-        let mut vcf = BString::from("");
-        vcf.push_str(format!(
-            "{}\t{}\t{}\t.\t<{}>\t.\t.\tCHR2={};SVEND={};",
+    /// Convert the edge to a BED6 record named after the edge
+    ///
+    /// The record spans the bases strictly between the two breakpoints, which is the intron
+    /// of a splice junction. Edges joining two references cannot be written as BED.
+    pub fn to_bed6(&self, strand: Strand, score_attribute: Option<&str>) -> Result<BString> {
+        if self.sv.reference_name1 != self.sv.reference_name2 {
+            return Err(anyhow!(
+                "Edge {} joins {} and {}",
+                self.id,
+                self.sv.reference_name1,
+                self.sv.reference_name2
+            ));
+        }
+        let start = self.sv.breakpoint1.min(self.sv.breakpoint2);
+        let end = self.sv.breakpoint1.max(self.sv.breakpoint2);
+        Ok(format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
             self.sv.reference_name1,
-            self.sv.breakpoint1,
+            start,
+            end.saturating_sub(1).max(start),
             self.id,
-            self.sv.sv_type,
-            self.sv.reference_name2,
-            self.sv.breakpoint2
-        ));
-
-        let mut info = BString::from("");
-        for attr in self.attributes.values() {
-            info.push_str(format!("{}={};", attr.tag, attr.value));
+            bed_score(self.attributes.values(), score_attribute),
+            strand
+        )
+        .into())
+    }
+
+    /// Convert the edge to VCF records, with `N` as the reference base
+    ///
+    /// Edges within one reference are written as a single symbolic allele `<TYPE>` from the
+    /// lower to the higher breakpoint. Translocations (edges joining two references, or of type
+    /// `TRA` or `BND`) and inversions are written as a pair of breakends `<id>_1` and `<id>_2`
+    /// that name each other in `MATEID` and the edge in `EVENT`; edges of another type than
+    /// `BND` keep it in `EVENTTYPE`, which the VCF reader prefers. Attributes become INFO fields
+    /// sorted by tag, except `QUAL` and `FILTER`, which fill their own columns.
+    pub fn to_vcf_records(&self) -> Vec<VcfRecord> {
+        let sv = &self.sv;
+        let mut attributes: Vec<&Attribute> = self.attributes.values().collect();
+        attributes.sort_by(|a, b| a.tag.cmp(&b.tag));
+        let column = |tag: &str| {
+            self.attributes
+                .get(tag.as_bytes())
+                .map_or_else(|| BString::from("."), |attr| attr.value.clone())
+        };
+        let info = |mut fields: Vec<(BString, BString)>| {
+            fields.extend(
+                attributes
+                    .iter()
+                    .filter(|attr| attr.tag != "QUAL" && attr.tag != "FILTER")
+                    .map(|attr| (attr.tag.clone(), attr.value.clone())),
+            );
+            fields
+        };
+        let record = |chrom: &BString, pos: usize, id: BString, alt: String, info| VcfRecord {
+            chrom: chrom.clone(),
+            pos,
+            id,
+            reference: "N".into(),
+            alt: alt.into(),
+            qual: column("QUAL"),
+            filter: column("FILTER"),
+            info,
+        };
+
+        let sv_type = sv.sv_type.to_ascii_uppercase();
+        let translocation =
+            sv.reference_name1 != sv.reference_name2 || sv_type == b"TRA" || sv_type == b"BND";
+        if translocation || sv_type == b"INV" {
+            let id1: BString = format!("{}_1", self.id).into();
+            let id2: BString = format!("{}_2", self.id).into();
+            // t[p[ joins the sequence after the mate, ]p]t the sequence before it, and
+            // t]p] the reverse complement of the sequence before it
+            let (alt1, alt2) = if translocation {
+                (
+                    format!("N[{}:{}[", sv.reference_name2, sv.breakpoint2),
+                    format!("]{}:{}]N", sv.reference_name1, sv.breakpoint1),
+                )
+            } else {
+                (
+                    format!("N]{}:{}]", sv.reference_name2, sv.breakpoint2),
+                    format!("N]{}:{}]", sv.reference_name1, sv.breakpoint1),
+                )
+            };
+            // EVENTTYPE keeps the type of edges other than plain breakends, such as INV
+            let breakend = |mate: &BString| {
+                let mut fields: Vec<(BString, BString)> = vec![
+                    ("SVTYPE".into(), "BND".into()),
+                    ("MATEID".into(), mate.clone()),
+                    ("EVENT".into(), self.id.clone()),
+                ];
+                if sv_type != b"BND" {
+                    fields.push(("EVENTTYPE".into(), sv.sv_type.clone()));
+                }
+                info(fields)
+            };
+            return vec![
+                record(
+                    &sv.reference_name1,
+                    sv.breakpoint1,
+                    id1.clone(),
+                    alt1,
+                    breakend(&id2),
+                ),
+                record(
+                    &sv.reference_name2,
+                    sv.breakpoint2,
+                    id2,
+                    alt2,
+                    breakend(&id1),
+                ),
+            ];
         }
 
-        if let Some(attributes) = attributes {
-            for attr in attributes.iter() {
-                info.push_str(format!("{}={};", attr.tag, attr.value));
+        let start = sv.breakpoint1.min(sv.breakpoint2);
+        let end = sv.breakpoint1.max(sv.breakpoint2);
+        let fields = info(vec![
+            ("SVTYPE".into(), sv.sv_type.clone()),
+            ("END".into(), end.to_string().into()),
+        ]);
+        vec![record(
+            &sv.reference_name1,
+            start,
+            self.id.clone(),
+            format!("<{}>", sv.sv_type),
+            fields,
+        )]
+    }
+
+    /// Convert the edge to VCF lines, see [`EdgeData::to_vcf_records`]
+    ///
+    /// Extra `attributes` are appended to the INFO field of every record.
+    pub fn to_vcf(&self, attributes: Option<&[Attribute]>) -> Result<BString> {
+        let mut records = self.to_vcf_records();
+        for record in records.iter_mut() {
+            for attr in attributes.unwrap_or_default() {
+                record.info.push((attr.tag.clone(), attr.value.clone()));
             }
         }
+        let lines: Vec<String> = records.iter().map(ToString::to_string).collect();
+        Ok(lines.join("\n").into())
+    }
+}
 
-        vcf.push_str(&info);
-        Ok(vcf)
+/// A VCF data line without sample columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct VcfRecord {
+    pub chrom: BString,
+    pub pos: usize,
+    pub id: BString,
+    pub reference: BString,
+    pub alt: BString,
+    pub qual: BString,
+    pub filter: BString,
+    pub info: Vec<(BString, BString)>,
+}
+
+impl VcfRecord {
+    /// Set the reference base, which breakend alleles repeat on their unbracketed side
+    pub fn set_reference_base(&mut self, base: u8) {
+        let base = base.to_ascii_uppercase();
+        if self.alt.contains(&b'[') || self.alt.contains(&b']') {
+            if let Some(first) = self.alt.first_mut().filter(|c| !b"[]".contains(c)) {
+                *first = base;
+            } else if let Some(last) = self.alt.last_mut().filter(|c| !b"[]".contains(c)) {
+                *last = base;
+            }
+        }
+        self.reference = vec![base].into();
+    }
+}
+
+impl fmt::Display for VcfRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t",
+            self.chrom, self.pos, self.id, self.reference, self.alt, self.qual, self.filter
+        )?;
+        if self.info.is_empty() {
+            return write!(f, ".");
+        }
+        for (idx, (key, value)) in self.info.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ";")?;
+            }
+            // Flags are written without a value
+            if value.is_empty() {
+                write!(f, "{}", key)?;
+            } else {
+                write!(f, "{}={}", key, vcf_escape(value))?;
+            }
+        }
+        Ok(())
     }
 }
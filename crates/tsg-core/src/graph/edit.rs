@@ -0,0 +1,446 @@
+use super::{EdgeConflictPolicy, EdgeData, GraphSection, Group, NodeData, TSGraph, rename_key};
+use anyhow::{Result, anyhow, bail};
+use bstr::{BStr, BString, ByteSlice};
+use petgraph::Direction;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+
+impl GraphSection {
+    /// Remove a node and its edges, returning the node data
+    ///
+    /// The node and its edges are dropped from the groups holding them: `U` groups lose
+    /// the elements, while paths and chains running through them are removed.
+    pub fn remove_node(&mut self, id: &str) -> Option<NodeData> {
+        let idx = *self.node_indices.get(&BString::from(id))?;
+        Some(self.remove_node_at(idx))
+    }
+
+    /// Remove an edge, returning its data
+    ///
+    /// The edge is dropped from the groups holding it as with [`GraphSection::remove_node`].
+    /// Paths listing only their nodes are removed too when they step between the endpoints
+    /// of the edge and no other edge connects them.
+    pub fn remove_edge(&mut self, id: &str) -> Option<EdgeData> {
+        let idx = *self.edge_indices.get(&BString::from(id))?;
+        Some(self.remove_edge_at(idx))
+    }
+
+    /// Remove a group, also dropping it from the `U` groups holding it
+    pub fn remove_group(&mut self, id: &str) -> Option<Group> {
+        self.remove_group_by_id(&BString::from(id))
+    }
+
+    /// Remove the nodes for which `keep` returns false, as with [`GraphSection::remove_node`]
+    pub fn retain_nodes<F: FnMut(&NodeData) -> bool>(&mut self, mut keep: F) {
+        let removed: Vec<BString> = self
+            ._graph
+            .node_weights()
+            .filter(|node| !keep(node))
+            .map(|node| node.id.clone())
+            .collect();
+        for id in removed {
+            let idx = self.node_indices[&id];
+            self.remove_node_at(idx);
+        }
+    }
+
+    /// Give a node a new ID, updating the groups referring to it
+    pub fn rename_node(&mut self, id: &str, new_id: &str) -> Result<()> {
+        let (id, new_id) = (BString::from(id), BString::from(new_id));
+        let idx = *self
+            .node_indices
+            .get(&id)
+            .ok_or_else(|| anyhow!("Node with ID {} not found in graph {}", id, self.id))?;
+        if id == new_id {
+            return Ok(());
+        }
+        self.check_new_id(&new_id)?;
+        rename_key(&mut self.node_indices, id.as_ref(), new_id.clone());
+        self.invalidate_sorted_indices();
+        self._graph[idx].id = new_id.clone();
+        self.rename_element(id.as_ref(), &new_id);
+        Ok(())
+    }
+
+    /// Give an edge a new ID, updating the groups referring to it
+    pub fn rename_edge(&mut self, id: &str, new_id: &str) -> Result<()> {
+        let (id, new_id) = (BString::from(id), BString::from(new_id));
+        let idx = *self
+            .edge_indices
+            .get(&id)
+            .ok_or_else(|| anyhow!("Edge with ID {} not found in graph {}", id, self.id))?;
+        if id == new_id {
+            return Ok(());
+        }
+        self.check_new_id(&new_id)?;
+        rename_key(&mut self.edge_indices, id.as_ref(), new_id.clone());
+        self.invalidate_sorted_indices();
+        self._graph[idx].id = new_id.clone();
+        self.rename_element(id.as_ref(), &new_id);
+        Ok(())
+    }
+
+    pub(super) fn check_new_id(&self, new_id: &BString) -> Result<()> {
+        if self.contains_element(new_id) {
+            bail!(
+                "Element with ID {} already exists in graph {}",
+                new_id,
+                self.id
+            );
+        }
+        Ok(())
+    }
+
+    fn remove_group_by_id(&mut self, id: &BString) -> Option<Group> {
+        let group = self.groups.shift_remove(id);
+        let chain = self.chains.remove(id);
+        let group = group.or(chain)?;
+        self.forget_element(id.as_ref());
+        Some(group)
+    }
+
+    fn remove_node_at(&mut self, idx: NodeIndex) -> NodeData {
+        let mut edges: Vec<EdgeIndex> = self
+            ._graph
+            .edges_directed(idx, Direction::Outgoing)
+            .chain(self._graph.edges_directed(idx, Direction::Incoming))
+            .map(|edge| edge.id())
+            .collect();
+        // Removing the highest indices first keeps the others in place
+        edges.sort_unstable_by(|a, b| b.cmp(a));
+        edges.dedup();
+        for edge in edges {
+            self.remove_edge_at(edge);
+        }
+
+        // petgraph moves the last node into the freed index
+        self.invalidate_sorted_indices();
+        let node = self._graph.remove_node(idx).unwrap();
+        self.node_indices.shift_remove(&node.id);
+        if let Some(moved) = self._graph.node_weight(idx) {
+            self.node_indices.insert(moved.id.clone(), idx);
+        }
+        self.forget_element(node.id.as_ref());
+        node
+    }
+
+    fn remove_edge_at(&mut self, idx: EdgeIndex) -> EdgeData {
+        // petgraph moves the last edge into the freed index
+        self.invalidate_sorted_indices();
+        let (source, sink) = self._graph.edge_endpoints(idx).unwrap();
+        let edge = self._graph.remove_edge(idx).unwrap();
+        self.edge_indices.shift_remove(&edge.id);
+        if let Some(moved) = self._graph.edge_weight(idx) {
+            self.edge_indices.insert(moved.id.clone(), idx);
+        }
+        self.forget_element(edge.id.as_ref());
+        if self.first_edge_between(source, sink).is_none() {
+            let (source, sink) = (self._graph[source].id.clone(), self._graph[sink].id.clone());
+            self.forget_step(source.as_ref(), sink.as_ref());
+        }
+        edge
+    }
+
+    /// Drop the paths stepping directly from node `source` to node `sink`, which no edge
+    /// connects any more
+    fn forget_step(&mut self, source: &BStr, sink: &BStr) {
+        let broken: Vec<BString> = self
+            .groups
+            .iter()
+            .filter(|(_, group)| match group {
+                Group::Ordered { elements, .. } => elements
+                    .windows(2)
+                    .any(|pair| pair[0].id == source && pair[1].id == sink),
+                _ => false,
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        for group_id in broken {
+            self.remove_group_by_id(&group_id);
+        }
+    }
+
+    /// Drop a removed element from the groups
+    ///
+    /// `U` groups lose the element and are removed once empty; paths and chains through
+    /// the element no longer describe a walk and are removed.
+    fn forget_element(&mut self, id: &BStr) {
+        let mut broken = Vec::new();
+        for (group_id, group) in self.groups.iter_mut().chain(self.chains.iter_mut()) {
+            let is_broken = match group {
+                Group::Unordered { elements, .. } => {
+                    let len = elements.len();
+                    elements.retain(|element| element != id);
+                    elements.len() < len && elements.is_empty()
+                }
+                Group::Ordered { elements, .. } => elements.iter().any(|element| element.id == id),
+                Group::Chain { elements, .. } => elements.iter().any(|element| element == id),
+            };
+            if is_broken {
+                broken.push(group_id.clone());
+            }
+        }
+        for group_id in broken {
+            self.remove_group_by_id(&group_id);
+        }
+    }
+
+    pub(super) fn rename_element(&mut self, id: &BStr, new_id: &BString) {
+        for group in self.groups.values_mut().chain(self.chains.values_mut()) {
+            match group {
+                Group::Unordered { elements, .. } | Group::Chain { elements, .. } => {
+                    for element in elements.iter_mut().filter(|element| *element == id) {
+                        *element = new_id.clone();
+                    }
+                }
+                Group::Ordered { elements, .. } => {
+                    for element in elements.iter_mut().filter(|element| element.id == id) {
+                        element.id = new_id.clone();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl TSGraph {
+    /// Remove a node of a graph, as with [`GraphSection::remove_node`], and the links
+    /// to the removed elements
+    pub fn remove_node(&mut self, graph_id: &str, node_id: &str) -> Option<NodeData> {
+        let node = self.graph_mut(graph_id)?.remove_node(node_id)?;
+        self.prune_links(graph_id);
+        Some(node)
+    }
+
+    /// Remove an edge of a graph, as with [`GraphSection::remove_edge`], and the links
+    /// to the removed elements
+    pub fn remove_edge(&mut self, graph_id: &str, edge_id: &str) -> Option<EdgeData> {
+        let edge = self.graph_mut(graph_id)?.remove_edge(edge_id)?;
+        self.prune_links(graph_id);
+        Some(edge)
+    }
+
+    /// Remove a group of a graph, as with [`GraphSection::remove_group`], and the links
+    /// to the removed groups
+    pub fn remove_group(&mut self, graph_id: &str, group_id: &str) -> Option<Group> {
+        let group = self.graph_mut(graph_id)?.remove_group(group_id)?;
+        self.prune_links(graph_id);
+        Some(group)
+    }
+
+    /// Remove the nodes of a graph for which `keep` returns false, as with
+    /// [`GraphSection::retain_nodes`], and the links to the removed elements
+    pub fn retain_nodes<F: FnMut(&NodeData) -> bool>(&mut self, graph_id: &str, keep: F) {
+        if let Some(graph) = self.graph_mut(graph_id) {
+            graph.retain_nodes(keep);
+            self.prune_links(graph_id);
+        }
+    }
+
+    /// Rename a node of a graph, as with [`GraphSection::rename_node`], and in the links
+    pub fn rename_node(&mut self, graph_id: &str, node_id: &str, new_id: &str) -> Result<()> {
+        self.graph_mut(graph_id)
+            .ok_or_else(|| anyhow!("Graph with ID {} not found", graph_id))?
+            .rename_node(node_id, new_id)?;
+        self.rename_link_element(graph_id, node_id, new_id);
+        Ok(())
+    }
+
+    /// Rename an edge of a graph, as with [`GraphSection::rename_edge`], and in the links
+    pub fn rename_edge(&mut self, graph_id: &str, edge_id: &str, new_id: &str) -> Result<()> {
+        self.graph_mut(graph_id)
+            .ok_or_else(|| anyhow!("Graph with ID {} not found", graph_id))?
+            .rename_edge(edge_id, new_id)?;
+        self.rename_link_element(graph_id, edge_id, new_id);
+        Ok(())
+    }
+
+    /// Add an edge to a graph, as with [`GraphSection::add_edge_with_policy`], renaming a
+    /// replaced edge in the links as well
+    pub fn add_edge_with_policy(
+        &mut self,
+        graph_id: &str,
+        source_id: &BStr,
+        sink_id: &BStr,
+        edge_data: EdgeData,
+        policy: EdgeConflictPolicy,
+    ) -> Result<EdgeIndex> {
+        let graph = self
+            .graph_mut(graph_id)
+            .ok_or_else(|| anyhow!("Graph with ID {} not found", graph_id))?;
+        let id = edge_data.id.clone();
+        // An edge re-added under its own ID is updated in place rather than replacing another
+        let replaced = match (
+            policy,
+            graph.node_indices.get(source_id),
+            graph.node_indices.get(sink_id),
+        ) {
+            (EdgeConflictPolicy::Replace, Some(&source), Some(&sink))
+                if !graph.edge_indices.contains_key(&id) =>
+            {
+                graph
+                    .first_edge_between(source, sink)
+                    .map(|idx| graph._graph[idx].id.clone())
+            }
+            _ => None,
+        };
+        let idx = graph.add_edge_with_policy(source_id, sink_id, edge_data, policy)?;
+        if let Some(old_id) = replaced {
+            self.rename_link_element(graph_id, &old_id.to_str_lossy(), &id.to_str_lossy());
+        }
+        Ok(idx)
+    }
+
+    /// Drop the links of a graph whose elements no longer exist
+    fn prune_links(&mut self, graph_id: &str) {
+        let links = std::mem::take(&mut self.links);
+        self.links = links
+            .into_iter()
+            .filter(|link| {
+                (link.source_graph != graph_id && link.target_graph != graph_id)
+                    || self.check_link(link).is_ok()
+            })
+            .collect();
+    }
+
+    fn rename_link_element(&mut self, graph_id: &str, id: &str, new_id: &str) {
+        for link in &mut self.links {
+            if link.source_graph == graph_id && link.source_element == id {
+                link.source_element = new_id.into();
+            }
+            if link.target_graph == graph_id && link.target_element == id {
+                link.target_element = new_id.into();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// Whether the ID maps agree with the IDs stored in the graph
+    fn assert_indices_consistent(graph: &GraphSection) {
+        let inner = graph.inner_graph();
+        assert_eq!(graph.node_indices.len(), inner.node_count());
+        assert_eq!(graph.edge_indices.len(), inner.edge_count());
+        for (id, &idx) in &graph.node_indices {
+            assert_eq!(&inner[idx].id, id);
+        }
+        for (id, &idx) in &graph.edge_indices {
+            assert_eq!(&inner[idx].id, id);
+        }
+    }
+
+    #[test]
+    fn test_remove_node() -> Result<()> {
+        let mut tsg = TSGraph::from_file("tests/data/test.tsg")?;
+        let graph = tsg.default_graph_mut().unwrap();
+
+        // n1 and e1 hold the first indices, so the last node and edge move into them
+        let node = graph.remove_node("n1").unwrap();
+        assert_eq!(node.id, "n1");
+        assert_indices_consistent(graph);
+        assert_eq!(graph.node_by_id("n5").unwrap().id, "n5");
+        assert_eq!(graph.edge_by_id("e4").unwrap().id, "e4");
+        assert!(graph.edge_by_id("e1").is_none());
+
+        assert!(!graph.groups.contains_key("chain1".as_bytes()));
+        assert!(!graph.chains.contains_key("chain1".as_bytes()));
+        assert!(!graph.groups.contains_key("transcript1".as_bytes()));
+        assert!(graph.groups.contains_key("transcript2".as_bytes()));
+        match &graph.groups[&BString::from("exon_set")] {
+            Group::Unordered { elements, .. } => assert_eq!(elements, &["n2", "n3"]),
+            group => panic!("unexpected group {:?}", group),
+        }
+        assert_eq!(graph.traverse()?.len(), 1);
+        assert!(graph.remove_node("n1").is_none());
+
+        let mut buf = Vec::new();
+        tsg.to_writer(&mut buf)?;
+        let reparsed = TSGraph::from_reader(buf.as_slice())?;
+        assert_eq!(reparsed.nodes("graph").len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_edge_and_group() -> Result<()> {
+        let mut tsg = TSGraph::from_file("tests/data/test.tsg")?;
+        let graph = tsg.default_graph_mut().unwrap();
+
+        assert_eq!(graph.remove_edge("e2").unwrap().id, "e2");
+        assert_indices_consistent(graph);
+        assert_eq!(graph.nodes().len(), 5);
+        assert!(!graph.groups.contains_key("transcript1".as_bytes()));
+
+        assert!(graph.remove_group("exon_set").is_some());
+        assert!(graph.remove_group("exon_set").is_none());
+        let mut buf = Vec::new();
+        tsg.to_writer(&mut buf)?;
+        assert!(!String::from_utf8(buf)?.contains("exon_set"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_edge_drops_node_only_paths() -> Result<()> {
+        let mut tsg = TSGraph::from_str(
+            "N\tn1\tchr1:+:100-200\tr1:SO,r2:SO\n\
+             N\tn2\tchr1:+:300-400\tr1:SI,r2:SI\n\
+             N\tn3\tchr1:+:500-600\tr3:SI\n\
+             E\te1\tn1\tn2\tchr1,chr1,200,300,splice\n\
+             E\te2\tn1\tn2\tchr1,chr1,200,300,DUP\n\
+             E\te3\tn2\tn3\tchr1,chr1,400,500,splice\n\
+             P\tp1\tn1+ n2+\n\
+             P\tp2\tn1+ n2+ n3+\n\
+             P\tp3\tn2+ n3+\n",
+        )?;
+        let graph = tsg.default_graph_mut().unwrap();
+
+        // e2 still joins n1 to n2
+        graph.remove_edge("e1").unwrap();
+        assert_eq!(graph.groups().len(), 3);
+
+        graph.remove_edge("e2").unwrap();
+        let ids: Vec<_> = graph.groups().keys().collect();
+        assert_eq!(ids, ["p3"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_retain_nodes() -> Result<()> {
+        let mut tsg = TSGraph::from_file("tests/data/test.tsg")?;
+        let graph = tsg.default_graph_mut().unwrap();
+        graph.retain_nodes(|node| node.reads.len() > 2);
+        assert_indices_consistent(graph);
+        assert_eq!(graph.nodes().len(), 1);
+        assert!(graph.edges().is_empty());
+        assert!(graph.groups.len() == 1 && graph.chains.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename() -> Result<()> {
+        let mut tsg = TSGraph::from_file("tests/data/test_mul.tsg")?;
+        tsg.rename_node("gene_a", "n3", "hub")?;
+        tsg.rename_edge("gene_a", "e1", "junction")?;
+        assert!(tsg.rename_node("gene_a", "hub", "n1").is_err());
+        assert!(tsg.rename_node("gene_a", "missing", "other").is_err());
+
+        let graph = tsg.graph("gene_a").unwrap();
+        assert_indices_consistent(graph);
+        assert!(graph.node_by_id("n3").is_none());
+        assert_eq!(graph.node_by_id("hub").unwrap().id, "hub");
+        assert_eq!(graph.edge_by_id("junction").unwrap().id, "junction");
+        assert_eq!(tsg.links[0].source_element, "hub");
+
+        let mut buf = Vec::new();
+        tsg.to_writer(&mut buf)?;
+        let reparsed = TSGraph::from_reader(buf.as_slice())?;
+        assert_eq!(reparsed.links.len(), 1);
+
+        assert!(tsg.remove_node("gene_b", "n1").is_some());
+        assert!(tsg.links.is_empty());
+        Ok(())
+    }
+}
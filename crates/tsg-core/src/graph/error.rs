@@ -0,0 +1,247 @@
+use std::fmt;
+use std::io;
+
+use thiserror::Error;
+
+/// Errors raised while parsing and validating TSG records
+///
+/// Every record-level variant carries the 1-based `line` of the offending record,
+/// its `record` type (`N`, `E`, `G`, ...) and the `field` that could not be interpreted,
+/// so callers can match on the failure and point users at the exact location.
+///
+/// A `line` of `0` means the error was raised outside of a file, e.g. when a single
+/// record is parsed with [`std::str::FromStr`]; the file parser fills it in with [`TsgError::at_line`].
+#[derive(Debug, Error)]
+pub enum TsgError {
+    /// A node (`N`) record or one of its components (location, exons, reads) is malformed
+    #[error("line {line}: malformed {record} record, field `{field}`: {reason}")]
+    MalformedNode {
+        line: usize,
+        record: String,
+        field: String,
+        reason: String,
+    },
+
+    /// An edge (`E`) record or its structural variant is malformed
+    #[error("line {line}: malformed {record} record, field `{field}`: {reason}")]
+    MalformedEdge {
+        line: usize,
+        record: String,
+        field: String,
+        reason: String,
+    },
+
+    /// Any other record (`H`, `G`, `U`, `P`, `C`, `A`, `L`) is malformed
+    #[error("line {line}: malformed {record} record, field `{field}`: {reason}")]
+    MalformedRecord {
+        line: usize,
+        record: String,
+        field: String,
+        reason: String,
+    },
+
+    /// A record or element type that the parser does not understand
+    #[error("line {line}: unknown record type `{value}` in field `{field}` of {record} record")]
+    UnknownRecordType {
+        line: usize,
+        record: String,
+        field: String,
+        value: String,
+    },
+
+    /// A record refers to a graph or element that does not exist
+    #[error(
+        "line {line}: {record} record references non-existent element `{id}` in field `{field}`"
+    )]
+    DanglingReference {
+        line: usize,
+        record: String,
+        field: String,
+        id: String,
+    },
+
+    /// An identifier is defined more than once within the same scope
+    #[error("line {line}: duplicate ID `{id}` in field `{field}` of {record} record")]
+    DuplicateId {
+        line: usize,
+        record: String,
+        field: String,
+        id: String,
+    },
+
+    /// A binary (BTSG) file whose content contradicts its own lengths or offsets
+    #[error("corrupt BTSG file: {reason}")]
+    CorruptBinary { reason: String },
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl TsgError {
+    /// Build a malformed-record error, choosing the variant from the record type
+    pub fn malformed(record: &str, field: &str, reason: impl fmt::Display) -> Self {
+        let (line, field, reason) = (0, field.to_string(), reason.to_string());
+        let record = record.to_string();
+        match record.as_str() {
+            "N" => TsgError::MalformedNode {
+                line,
+                record,
+                field,
+                reason,
+            },
+            "E" => TsgError::MalformedEdge {
+                line,
+                record,
+                field,
+                reason,
+            },
+            _ => TsgError::MalformedRecord {
+                line,
+                record,
+                field,
+                reason,
+            },
+        }
+    }
+
+    pub fn unknown_record_type(record: &str, field: &str, value: impl fmt::Display) -> Self {
+        TsgError::UnknownRecordType {
+            line: 0,
+            record: record.into(),
+            field: field.into(),
+            value: value.to_string(),
+        }
+    }
+
+    pub fn dangling_reference(record: &str, field: &str, id: impl fmt::Display) -> Self {
+        TsgError::DanglingReference {
+            line: 0,
+            record: record.into(),
+            field: field.into(),
+            id: id.to_string(),
+        }
+    }
+
+    pub fn duplicate_id(record: &str, field: &str, id: impl fmt::Display) -> Self {
+        TsgError::DuplicateId {
+            line: 0,
+            record: record.into(),
+            field: field.into(),
+            id: id.to_string(),
+        }
+    }
+
+    /// Build the error for a record skipped because the `G` record before it was rejected
+    pub(crate) fn outside_graph(record: &str) -> Self {
+        Self::malformed(
+            record,
+            "graph_id",
+            "No active graph section: the last G record was rejected",
+        )
+    }
+
+    /// Attach the 1-based line number of the record that caused the error
+    pub fn at_line(mut self, line_number: usize) -> Self {
+        match &mut self {
+            TsgError::MalformedNode { line, .. }
+            | TsgError::MalformedEdge { line, .. }
+            | TsgError::MalformedRecord { line, .. }
+            | TsgError::UnknownRecordType { line, .. }
+            | TsgError::DanglingReference { line, .. }
+            | TsgError::DuplicateId { line, .. } => *line = line_number,
+            TsgError::CorruptBinary { .. } | TsgError::Io(_) => {}
+        }
+        self
+    }
+
+    /// The 1-based line number of the offending record, if known
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            TsgError::MalformedNode { line, .. }
+            | TsgError::MalformedEdge { line, .. }
+            | TsgError::MalformedRecord { line, .. }
+            | TsgError::UnknownRecordType { line, .. }
+            | TsgError::DanglingReference { line, .. }
+            | TsgError::DuplicateId { line, .. } => (*line > 0).then_some(*line),
+            TsgError::CorruptBinary { .. } | TsgError::Io(_) => None,
+        }
+    }
+
+    /// The record type of the offending record, if known
+    pub fn record(&self) -> Option<&str> {
+        match self {
+            TsgError::MalformedNode { record, .. }
+            | TsgError::MalformedEdge { record, .. }
+            | TsgError::MalformedRecord { record, .. }
+            | TsgError::UnknownRecordType { record, .. }
+            | TsgError::DanglingReference { record, .. }
+            | TsgError::DuplicateId { record, .. } => Some(record),
+            TsgError::CorruptBinary { .. } | TsgError::Io(_) => None,
+        }
+    }
+
+    /// The field of the offending record, if known
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            TsgError::MalformedNode { field, .. }
+            | TsgError::MalformedEdge { field, .. }
+            | TsgError::MalformedRecord { field, .. }
+            | TsgError::UnknownRecordType { field, .. }
+            | TsgError::DanglingReference { field, .. }
+            | TsgError::DuplicateId { field, .. } => Some(field),
+            TsgError::CorruptBinary { .. } | TsgError::Io(_) => None,
+        }
+    }
+
+    /// A description of the problem without its location
+    pub fn reason(&self) -> String {
+        match self {
+            TsgError::MalformedNode { reason, .. }
+            | TsgError::MalformedEdge { reason, .. }
+            | TsgError::MalformedRecord { reason, .. } => reason.clone(),
+            TsgError::UnknownRecordType { value, .. } => format!("unknown record type `{value}`"),
+            TsgError::DanglingReference { id, .. } => {
+                format!("references non-existent element `{id}`")
+            }
+            TsgError::DuplicateId { id, .. } => format!("duplicate ID `{id}`"),
+            TsgError::CorruptBinary { reason } => reason.clone(),
+            TsgError::Io(e) => e.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_malformed_variant_by_record() {
+        assert!(matches!(
+            TsgError::malformed("N", "exons", "bad"),
+            TsgError::MalformedNode { .. }
+        ));
+        assert!(matches!(
+            TsgError::malformed("E", "sv", "bad"),
+            TsgError::MalformedEdge { .. }
+        ));
+        assert!(matches!(
+            TsgError::malformed("G", "attributes", "bad"),
+            TsgError::MalformedRecord { .. }
+        ));
+    }
+
+    #[test]
+    fn test_at_line() {
+        let err = TsgError::duplicate_id("G", "graph_id", "gene_a");
+        assert_eq!(err.line(), None);
+
+        let err = err.at_line(12);
+        assert_eq!(err.line(), Some(12));
+        assert_eq!(err.record(), Some("G"));
+        assert_eq!(err.field(), Some("graph_id"));
+        assert_eq!(
+            err.to_string(),
+            "line 12: duplicate ID `gene_a` in field `graph_id` of G record"
+        );
+    }
+}
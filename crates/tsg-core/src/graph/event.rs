@@ -0,0 +1,639 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use super::{GraphSection, OrderingPolicy, Strand, TSGPath};
+use ahash::HashSet;
+use anyhow::Result;
+use bstr::BString;
+
+/// Alternative splicing event types, named by their SUPPA codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EventType {
+    /// Skipped exon
+    SE,
+    /// Alternative 5' splice site (A5SS)
+    A5,
+    /// Alternative 3' splice site (A3SS)
+    A3,
+    /// Retained intron
+    RI,
+    /// Mutually exclusive exons
+    MX,
+    /// Alternative first exon
+    AF,
+    /// Alternative last exon
+    AL,
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = match self {
+            EventType::SE => "SE",
+            EventType::A5 => "A5",
+            EventType::A3 => "A3",
+            EventType::RI => "RI",
+            EventType::MX => "MX",
+            EventType::AF => "AF",
+            EventType::AL => "AL",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+/// Alternative splicing event between the traversed paths of a graph section
+///
+/// The inclusion form is the one with the exon for skipped exons, the longer exon for
+/// alternative splice sites, the intron for retained introns, the upstream exon for
+/// mutually exclusive exons, and the exon farther from the shared splice site for
+/// alternative first and last exons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplicingEvent {
+    /// SUPPA event ID, such as `SE:chr1:200-300:400-500:+`
+    pub id: BString,
+    pub event_type: EventType,
+    pub reference_id: BString,
+    pub strand: Strand,
+    pub start: usize,
+    pub end: usize,
+    pub inclusion_paths: Vec<BString>,
+    pub exclusion_paths: Vec<BString>,
+    /// Distinct reads supporting the inclusion paths
+    pub inclusion_reads: usize,
+    /// Distinct reads supporting the exclusion paths
+    pub exclusion_reads: usize,
+}
+
+impl SplicingEvent {
+    /// Columns of [`SplicingEvent::to_tsv`]
+    pub const TSV_HEADER: &'static str = "graph\tevent_id\ttype\tchrom\tstrand\tstart\tend\tinclusion_paths\texclusion_paths\tinclusion_reads\texclusion_reads\tpsi";
+
+    /// Columns of [`SplicingEvent::to_ioe`], as in SUPPA `.ioe` files
+    pub const IOE_HEADER: &'static str =
+        "seqname\tgene_id\tevent_id\talternative_transcripts\ttotal_transcripts";
+
+    /// Share of the reads of the event supporting its inclusion form
+    pub fn psi(&self) -> Option<f64> {
+        let total = self.inclusion_reads + self.exclusion_reads;
+        (total > 0).then(|| self.inclusion_reads as f64 / total as f64)
+    }
+
+    /// Tab-separated row of the event in the graph `graph_id`
+    pub fn to_tsv(&self, graph_id: &BString) -> String {
+        let join = |ids: &[BString]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            graph_id,
+            self.id,
+            self.event_type,
+            self.reference_id,
+            self.strand,
+            self.start,
+            self.end,
+            join(&self.inclusion_paths).join(","),
+            join(&self.exclusion_paths).join(","),
+            self.inclusion_reads,
+            self.exclusion_reads,
+            self.psi()
+                .map_or("NA".to_string(), |psi| format!("{:.4}", psi))
+        )
+    }
+
+    /// SUPPA `.ioe` row of the event, with the graph as gene
+    ///
+    /// The columns are the reference, the gene, `<gene>;<event_id>`, the inclusion paths
+    /// and all paths of the event.
+    pub fn to_ioe(&self, graph_id: &BString) -> String {
+        let total: Vec<String> = self
+            .inclusion_paths
+            .iter()
+            .chain(&self.exclusion_paths)
+            .map(|id| id.to_string())
+            .collect();
+        let inclusion: Vec<String> = self
+            .inclusion_paths
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        format!(
+            "{}\t{}\t{};{}\t{}\t{}",
+            self.reference_id,
+            graph_id,
+            graph_id,
+            self.id,
+            inclusion.join(","),
+            total.join(",")
+        )
+    }
+}
+
+/// Exon chain of a collinear traversed path, with the reads running through all its nodes
+struct Transcript {
+    id: BString,
+    exons: Vec<(usize, usize)>,
+    reads: HashSet<BString>,
+}
+
+impl Transcript {
+    /// Transcript of a path whose nodes lie on one reference and strand, in order
+    ///
+    /// Reverse strand paths run from high to low coordinates, so their nodes are read
+    /// backwards to keep the exons ascending.
+    fn from_path(graph: &GraphSection, path: &TSGPath) -> Result<Option<(BString, Strand, Self)>> {
+        let mut nodes: Vec<_> = path
+            .nodes
+            .iter()
+            .filter_map(|&idx| graph.node_by_idx(idx))
+            .collect();
+        if nodes
+            .first()
+            .is_some_and(|node| node.strand == Strand::Reverse)
+        {
+            nodes.reverse();
+        }
+        let Some(first) = nodes.first() else {
+            return Ok(None);
+        };
+        let mut exons: Vec<(usize, usize)> = Vec::new();
+        for node in &nodes {
+            if node.reference_id != first.reference_id || node.strand != first.strand {
+                return Ok(None);
+            }
+            for exon in &node.exons.exons {
+                match exons.last_mut() {
+                    // Segments continuing an exon across nodes are one exon
+                    Some(last) if exon.start <= last.1 + 1 && exon.start >= last.0 => {
+                        last.1 = last.1.max(exon.end)
+                    }
+                    Some(last) if exon.start <= last.1 => return Ok(None),
+                    _ => exons.push((exon.start, exon.end)),
+                }
+            }
+        }
+
+        let mut reads: HashSet<BString> = first.reads.iter().map(|r| r.id.clone()).collect();
+        for node in &nodes[1..] {
+            let node_reads: HashSet<&BString> = node.reads.iter().map(|r| &r.id).collect();
+            reads.retain(|read| node_reads.contains(read));
+        }
+        Ok(Some((
+            first.reference_id.clone(),
+            first.strand,
+            Self {
+                id: path.id()?,
+                exons,
+                reads,
+            },
+        )))
+    }
+
+    fn junctions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.exons.windows(2).map(|pair| (pair[0].1, pair[1].0))
+    }
+
+    fn has_junction(&self, junction: (usize, usize)) -> bool {
+        self.junctions().any(|j| j == junction)
+    }
+}
+
+/// Events of the transcripts of one reference and strand, keyed by ID
+struct EventBuilder<'a> {
+    reference_id: &'a BString,
+    strand: Strand,
+    transcripts: &'a [Transcript],
+    events: BTreeMap<BString, SplicingEvent>,
+}
+
+impl<'a> EventBuilder<'a> {
+    fn add(
+        &mut self,
+        event_type: EventType,
+        coordinates: String,
+        (start, end): (usize, usize),
+        inclusion: impl Fn(&Transcript) -> bool,
+        exclusion: impl Fn(&Transcript) -> bool,
+    ) {
+        let id: BString = format!(
+            "{}:{}:{}:{}",
+            event_type, self.reference_id, coordinates, self.strand
+        )
+        .into();
+        if self.events.contains_key(&id) {
+            return;
+        }
+        let select = |predicate: &dyn Fn(&Transcript) -> bool| -> Vec<&'a Transcript> {
+            self.transcripts.iter().filter(|t| predicate(t)).collect()
+        };
+        let (inclusion, exclusion) = (select(&inclusion), select(&exclusion));
+        if inclusion.is_empty() || exclusion.is_empty() {
+            return;
+        }
+        let reads = |transcripts: &[&Transcript]| {
+            transcripts
+                .iter()
+                .flat_map(|t| &t.reads)
+                .collect::<HashSet<_>>()
+                .len()
+        };
+        let ids = |transcripts: &[&Transcript]| transcripts.iter().map(|t| t.id.clone()).collect();
+        let event = SplicingEvent {
+            id: id.clone(),
+            event_type,
+            reference_id: self.reference_id.clone(),
+            strand: self.strand,
+            start,
+            end,
+            inclusion_paths: ids(&inclusion),
+            exclusion_paths: ids(&exclusion),
+            inclusion_reads: reads(&inclusion),
+            exclusion_reads: reads(&exclusion),
+        };
+        self.events.insert(id, event);
+    }
+
+    /// An internal exon with both junctions, against the junction skipping it
+    fn skipped_exons(&mut self) {
+        for t in self.transcripts {
+            for w in t.exons.windows(3) {
+                let (a, x, b) = (w[0].1, w[1], w[2].0);
+                self.add(
+                    EventType::SE,
+                    format!("{}-{}:{}-{}", a, x.0, x.1, b),
+                    (a, b),
+                    |t| t.has_junction((a, x.0)) && t.has_junction((x.1, b)),
+                    |t| t.has_junction((a, b)),
+                );
+            }
+        }
+    }
+
+    /// Two non-overlapping exons between the same splice sites
+    fn mutually_exclusive_exons(&mut self) {
+        let mut middles: BTreeMap<(usize, usize), BTreeSet<(usize, usize)>> = BTreeMap::new();
+        for t in self.transcripts {
+            for w in t.exons.windows(3) {
+                middles.entry((w[0].1, w[2].0)).or_default().insert(w[1]);
+            }
+        }
+        for ((a, b), exons) in middles {
+            let exons: Vec<_> = exons.into_iter().collect();
+            for (i, &x) in exons.iter().enumerate() {
+                for &y in exons[i + 1..].iter().filter(|y| x.1 < y.0) {
+                    self.add(
+                        EventType::MX,
+                        format!("{}-{}:{}-{}:{}-{}:{}-{}", a, x.0, x.1, b, a, y.0, y.1, b),
+                        (a, b),
+                        |t| t.has_junction((a, x.0)) && t.has_junction((x.1, b)),
+                        |t| t.has_junction((a, y.0)) && t.has_junction((y.1, b)),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Junctions sharing one splice site whose other sites lie in overlapping exons
+    fn alternative_splice_sites(&mut self) {
+        let exon_ending = |site: usize| {
+            self.transcripts
+                .iter()
+                .flat_map(|t| &t.exons)
+                .find(|exon| exon.1 == site)
+                .map_or(site, |exon| exon.0)
+        };
+        let exon_starting = |site: usize| {
+            self.transcripts
+                .iter()
+                .flat_map(|t| &t.exons)
+                .find(|exon| exon.0 == site)
+                .map_or(site, |exon| exon.1)
+        };
+        let junctions: BTreeSet<(usize, usize)> = self
+            .transcripts
+            .iter()
+            .flat_map(|t| t.junctions())
+            .collect();
+        let junctions: Vec<_> = junctions.into_iter().collect();
+
+        let mut found = Vec::new();
+        for (i, &j) in junctions.iter().enumerate() {
+            for &k in &junctions[i + 1..] {
+                if j.1 == k.1 && exon_ending(j.0).max(exon_ending(k.0)) <= j.0.min(k.0) {
+                    // Alternative upstream sites, the donor on the forward strand
+                    let event_type = match self.strand {
+                        Strand::Forward => EventType::A5,
+                        Strand::Reverse => EventType::A3,
+                    };
+                    let (inclusion, exclusion) = if j.0 > k.0 { (j, k) } else { (k, j) };
+                    found.push((event_type, inclusion, exclusion));
+                } else if j.0 == k.0 && j.1.max(k.1) <= exon_starting(j.1).min(exon_starting(k.1)) {
+                    let event_type = match self.strand {
+                        Strand::Forward => EventType::A3,
+                        Strand::Reverse => EventType::A5,
+                    };
+                    let (inclusion, exclusion) = if j.1 < k.1 { (j, k) } else { (k, j) };
+                    found.push((event_type, inclusion, exclusion));
+                }
+            }
+        }
+        for (event_type, inclusion, exclusion) in found {
+            self.add(
+                event_type,
+                format!(
+                    "{}-{}:{}-{}",
+                    inclusion.0, inclusion.1, exclusion.0, exclusion.1
+                ),
+                (inclusion.0.min(exclusion.0), inclusion.1.max(exclusion.1)),
+                |t| t.has_junction(inclusion),
+                |t| t.has_junction(exclusion),
+            );
+        }
+    }
+
+    /// An exon covering the intron of a junction
+    fn retained_introns(&mut self) {
+        let mut found = Vec::new();
+        for t in self.transcripts {
+            for (i, w) in t.exons.windows(2).enumerate() {
+                let junction = (w[0].1, w[1].0);
+                let retained = |t: &Transcript| {
+                    t.exons
+                        .iter()
+                        .any(|exon| exon.0 < junction.0 && junction.1 < exon.1)
+                };
+                if self.transcripts.iter().any(retained) {
+                    found.push((t.exons[i].0, junction, t.exons[i + 1].1));
+                }
+            }
+        }
+        for (start, junction, end) in found {
+            self.add(
+                EventType::RI,
+                format!("{}:{}-{}:{}", start, junction.0, junction.1, end),
+                (start, end),
+                |t| {
+                    t.exons
+                        .iter()
+                        .any(|exon| exon.0 < junction.0 && junction.1 < exon.1)
+                },
+                |t| t.has_junction(junction),
+            );
+        }
+    }
+
+    /// Non-overlapping first or last exons spliced to the same site
+    fn alternative_terminal_exons(&mut self) {
+        let (first_type, last_type) = match self.strand {
+            Strand::Forward => (EventType::AF, EventType::AL),
+            Strand::Reverse => (EventType::AL, EventType::AF),
+        };
+        let mut firsts: BTreeMap<usize, BTreeSet<(usize, usize)>> = BTreeMap::new();
+        let mut lasts: BTreeMap<usize, BTreeSet<(usize, usize)>> = BTreeMap::new();
+        for t in self.transcripts.iter().filter(|t| t.exons.len() > 1) {
+            let n = t.exons.len();
+            firsts.entry(t.exons[1].0).or_default().insert(t.exons[0]);
+            lasts
+                .entry(t.exons[n - 2].1)
+                .or_default()
+                .insert(t.exons[n - 1]);
+        }
+
+        for (acceptor, exons) in firsts {
+            let exons: Vec<_> = exons.into_iter().collect();
+            for (i, &x) in exons.iter().enumerate() {
+                for &y in exons[i + 1..].iter().filter(|y| x.1 < y.0) {
+                    // The exon farther upstream of the acceptor is the inclusion form
+                    let first_pair = move |exon: (usize, usize)| {
+                        move |t: &Transcript| {
+                            t.exons.len() > 1 && t.exons[0] == exon && t.exons[1].0 == acceptor
+                        }
+                    };
+                    self.add(
+                        first_type,
+                        format!("{}:{}-{}:{}:{}-{}", x.0, x.1, acceptor, y.0, y.1, acceptor),
+                        (x.0, acceptor),
+                        first_pair(x),
+                        first_pair(y),
+                    );
+                }
+            }
+        }
+
+        for (donor, exons) in lasts {
+            let exons: Vec<_> = exons.into_iter().collect();
+            for (i, &x) in exons.iter().enumerate() {
+                for &y in exons[i + 1..].iter().filter(|y| x.1 < y.0) {
+                    // The exon farther downstream of the donor is the inclusion form
+                    let last_pair = move |exon: (usize, usize)| {
+                        move |t: &Transcript| {
+                            let n = t.exons.len();
+                            n > 1 && t.exons[n - 1] == exon && t.exons[n - 2].1 == donor
+                        }
+                    };
+                    self.add(
+                        last_type,
+                        format!("{}-{}:{}:{}-{}:{}", donor, y.0, y.1, donor, x.0, x.1),
+                        (donor, y.1),
+                        last_pair(y),
+                        last_pair(x),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl GraphSection {
+    /// Alternative splicing events between the traversed paths of the graph section
+    ///
+    /// Each traversed path staying on one reference and strand is read as a transcript,
+    /// the exons of its nodes in order, and the reads supporting it are those present in
+    /// all its nodes. Events are found between the transcripts of each reference and
+    /// strand, and returned ordered by position.
+    pub fn splicing_events(&self) -> Result<Vec<SplicingEvent>> {
+        let mut transcripts: BTreeMap<(BString, Strand), Vec<Transcript>> = BTreeMap::new();
+        for path in self.traverse_with_ordering(OrderingPolicy::Sorted)? {
+            if let Some((reference_id, strand, transcript)) = Transcript::from_path(self, &path)? {
+                transcripts
+                    .entry((reference_id, strand))
+                    .or_default()
+                    .push(transcript);
+            }
+        }
+
+        let mut events = Vec::new();
+        for ((reference_id, strand), transcripts) in &transcripts {
+            let mut builder = EventBuilder {
+                reference_id,
+                strand: *strand,
+                transcripts,
+                events: BTreeMap::new(),
+            };
+            builder.skipped_exons();
+            builder.alternative_splice_sites();
+            builder.retained_introns();
+            builder.mutually_exclusive_exons();
+            builder.alternative_terminal_exons();
+            events.extend(builder.events.into_values());
+        }
+        events.sort_by(|a, b| {
+            (&a.reference_id, a.start, a.end, a.event_type, &a.id).cmp(&(
+                &b.reference_id,
+                b.start,
+                b.end,
+                b.event_type,
+                &b.id,
+            ))
+        });
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::TSGraph;
+    use ahash::HashMap;
+    use std::str::FromStr;
+
+    /// Three transcripts on the forward strand:
+    /// A B C D E1 F G, read by r1 and r4
+    /// A C D E2 F G, read by r2
+    /// A2 B CL D E1 FG, read by r3
+    /// The reads shared by A, C and E1 also make A C D E1 F G a traversed path.
+    const EVENTS_TSG: &str = "N\tA\tchr1:+:100-200\tr1:SO,r2:SO,r4:SO\n\
+        N\tA2\tchr1:+:50-80\tr3:SO\n\
+        N\tB\tchr1:+:300-400\tr1:IN,r3:IN,r4:IN\n\
+        N\tC\tchr1:+:500-600\tr1:IN,r2:IN,r4:IN\n\
+        N\tCL\tchr1:+:500-650\tr3:IN\n\
+        N\tD\tchr1:+:800-900\tr1:IN,r2:IN,r3:IN,r4:IN\n\
+        N\tE1\tchr1:+:1000-1100\tr1:IN,r3:IN,r4:IN\n\
+        N\tE2\tchr1:+:1200-1300\tr2:IN\n\
+        N\tF\tchr1:+:1400-1500\tr1:IN,r2:IN,r4:IN\n\
+        N\tG\tchr1:+:1600-1700\tr1:SI,r2:SI,r4:SI\n\
+        N\tFG\tchr1:+:1400-1700\tr3:SI\n\
+        E\te1\tA\tB\tchr1,chr1,200,300,splice\n\
+        E\te2\tB\tC\tchr1,chr1,400,500,splice\n\
+        E\te3\tC\tD\tchr1,chr1,600,800,splice\n\
+        E\te4\tD\tE1\tchr1,chr1,900,1000,splice\n\
+        E\te5\tE1\tF\tchr1,chr1,1100,1400,splice\n\
+        E\te6\tF\tG\tchr1,chr1,1500,1600,splice\n\
+        E\te7\tA\tC\tchr1,chr1,200,500,splice\n\
+        E\te8\tD\tE2\tchr1,chr1,900,1200,splice\n\
+        E\te9\tE2\tF\tchr1,chr1,1300,1400,splice\n\
+        E\te10\tA2\tB\tchr1,chr1,80,300,splice\n\
+        E\te11\tB\tCL\tchr1,chr1,400,500,splice\n\
+        E\te12\tCL\tD\tchr1,chr1,650,800,splice\n\
+        E\te13\tE1\tFG\tchr1,chr1,1100,1400,splice\n";
+
+    #[test]
+    fn test_splicing_events() -> Result<()> {
+        let tsg = TSGraph::from_str(EVENTS_TSG)?;
+        let graph = tsg.default_graph().unwrap();
+        let names: HashMap<BString, String> = graph
+            .traverse()?
+            .iter()
+            .map(|path| {
+                let nodes: Vec<String> = path
+                    .nodes
+                    .iter()
+                    .map(|&idx| graph.node_by_idx(idx).unwrap().id.to_string())
+                    .collect();
+                (path.id().unwrap(), nodes.join("-"))
+            })
+            .collect();
+        let events = graph.splicing_events()?;
+        let summary = events
+            .iter()
+            .map(|event| {
+                let names = |ids: &[BString]| {
+                    let mut names: Vec<&str> = ids.iter().map(|id| names[id].as_str()).collect();
+                    names.sort();
+                    names
+                };
+                (
+                    event.id.to_string(),
+                    names(&event.inclusion_paths),
+                    names(&event.exclusion_paths),
+                    event.inclusion_reads,
+                    event.exclusion_reads,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            [
+                (
+                    "AF:chr1:50:80-300:100:200-300:+".to_string(),
+                    vec!["A2-B-CL-D-E1-FG"],
+                    vec!["A-B-C-D-E1-F-G"],
+                    1,
+                    2
+                ),
+                (
+                    "SE:chr1:200-300:400-500:+".to_string(),
+                    vec!["A-B-C-D-E1-F-G"],
+                    vec!["A-C-D-E1-F-G", "A-C-D-E2-F-G"],
+                    2,
+                    3
+                ),
+                (
+                    "A5:chr1:650-800:600-800:+".to_string(),
+                    vec!["A2-B-CL-D-E1-FG"],
+                    vec!["A-B-C-D-E1-F-G", "A-C-D-E1-F-G", "A-C-D-E2-F-G"],
+                    1,
+                    3
+                ),
+                (
+                    "MX:chr1:900-1000:1100-1400:900-1200:1300-1400:+".to_string(),
+                    vec!["A-B-C-D-E1-F-G", "A-C-D-E1-F-G", "A2-B-CL-D-E1-FG"],
+                    vec!["A-C-D-E2-F-G"],
+                    3,
+                    1
+                ),
+                (
+                    "RI:chr1:1400:1500-1600:1700:+".to_string(),
+                    vec!["A2-B-CL-D-E1-FG"],
+                    vec!["A-B-C-D-E1-F-G", "A-C-D-E1-F-G", "A-C-D-E2-F-G"],
+                    1,
+                    3
+                ),
+            ]
+        );
+
+        let se = &events[1];
+        assert_eq!(se.psi(), Some(0.4));
+        assert!(
+            se.to_tsv(&graph.id)
+                .starts_with("graph\tSE:chr1:200-300:400-500:+\tSE\tchr1\t+\t200\t500\t")
+        );
+        let ioe = se.to_ioe(&graph.id);
+        assert!(ioe.starts_with("chr1\tgraph\tgraph;SE:chr1:200-300:400-500:+\t"));
+        assert_eq!(ioe.split('\t').nth(4).unwrap().split(',').count(), 3);
+
+        // Reverse strand paths run from the highest exon down
+        let tsg = TSGraph::from_str(
+            "N\tX3\tchr1:-:500-600\tr1:SO,r2:SO\n\
+             N\tX2\tchr1:-:300-400\tr1:IN\n\
+             N\tX1\tchr1:-:100-200\tr1:SI,r2:SI\n\
+             E\te1\tX3\tX2\tchr1,chr1,500,400,splice\n\
+             E\te2\tX2\tX1\tchr1,chr1,300,200,splice\n\
+             E\te3\tX3\tX1\tchr1,chr1,500,200,splice\n",
+        )?;
+        let events = tsg.default_graph().unwrap().splicing_events()?;
+        let ids: Vec<_> = events.iter().map(|event| event.id.to_string()).collect();
+        assert_eq!(ids, ["SE:chr1:200-300:400-500:-"]);
+        assert_eq!(
+            (events[0].inclusion_reads, events[0].exclusion_reads),
+            (1, 2)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_splicing_events_skip_rearranged_paths() -> Result<()> {
+        let tsg = TSGraph::from_file("tests/data/test.tsg")?;
+        let events = tsg.default_graph().unwrap().splicing_events()?;
+        assert!(events.is_empty());
+        Ok(())
+    }
+}
@@ -1,27 +1,39 @@
 use std::fmt;
-use std::{io, str::FromStr};
+use std::str::FromStr;
 
 use ahash::HashMap;
 use bstr::BString;
 
-use super::Attribute;
+use super::{Attribute, TsgError};
 
 /// Orientation of an element in an ordered group
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Orientation {
+    #[cfg_attr(feature = "serde", serde(rename = "+"))]
     Forward,
+    #[cfg_attr(feature = "serde", serde(rename = "-"))]
     Reverse,
 }
 
 /// Reference to a graph element with optional orientation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OrientedElement {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub id: BString,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub orientation: Option<Orientation>,
 }
 
 impl FromStr for OrientedElement {
-    type Err = io::Error;
+    type Err = TsgError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if let Some(stripped) = s.strip_suffix('+') {
@@ -54,21 +66,55 @@ impl fmt::Display for OrientedElement {
 }
 
 /// Group in the transcript segment graph (ordered, unordered, or chain)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "lowercase"))]
 pub enum Group {
     Unordered {
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::graph::serialization::bstring")
+        )]
         id: BString,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::graph::serialization::bstrings")
+        )]
         elements: Vec<BString>,
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, with = "crate::graph::serialization::attributes")
+        )]
         attributes: HashMap<BString, Attribute>,
     },
     Ordered {
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::graph::serialization::bstring")
+        )]
         id: BString,
         elements: Vec<OrientedElement>,
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, with = "crate::graph::serialization::attributes")
+        )]
         attributes: HashMap<BString, Attribute>,
     },
     Chain {
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::graph::serialization::bstring")
+        )]
         id: BString,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::graph::serialization::bstrings")
+        )]
         elements: Vec<BString>, // Alternating node and edge IDs, starting and ending with nodes
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, with = "crate::graph::serialization::attributes")
+        )]
         attributes: HashMap<BString, Attribute>,
     },
 }
@@ -5,9 +5,18 @@ use bstr::BString;
 
 /// Header information in the TSG file
 #[derive(Debug, Clone, PartialEq, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[builder(on(BString, into))]
 pub struct Header {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub tag: BString,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub value: BString,
 }
 
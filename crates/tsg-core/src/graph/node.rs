@@ -1,17 +1,15 @@
 use std::fmt;
 use std::str::FromStr;
 
-use crate::graph::Attribute;
+use crate::graph::utils::{bed_score, bed12_record};
+use crate::graph::{Attribute, TsgError, split_fields};
 use ahash::HashMap;
-use anyhow::Context;
 use anyhow::Result;
 use bon::Builder;
-use bon::builder;
 use bstr::BString;
 use bstr::ByteSlice;
 use rayon::prelude::*;
 use serde_json::json;
-use std::io;
 use tracing::debug;
 
 /// Represents a simple interval with start and end positions.
@@ -21,7 +19,8 @@ use tracing::debug;
 /// - `end`: The exclusive ending position of the interval
 ///
 /// The interval spans from `start` (inclusive) to `end` (exclusive).
-#[derive(Debug, Builder, Clone)]
+#[derive(Debug, Builder, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Interval {
     pub start: usize,
     pub end: usize,
@@ -40,36 +39,33 @@ impl Interval {
 }
 
 impl FromStr for Interval {
-    type Err = io::Error;
+    type Err = TsgError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parts: Vec<&str> = s.split('-').collect();
         if parts.len() != 2 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
+            return Err(TsgError::malformed(
+                "N",
+                "exons",
                 format!("Invalid exon coordinates format: {}", s),
             ));
         }
 
         let start = parts[0].parse::<usize>().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Invalid start coordinate: {}", e),
-            )
+            TsgError::malformed("N", "exons", format!("Invalid start coordinate: {}", e))
         })?;
 
         let end = parts[1].parse::<usize>().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Invalid end coordinate: {}", e),
-            )
+            TsgError::malformed("N", "exons", format!("Invalid end coordinate: {}", e))
         })?;
 
         Ok(Self { start, end })
     }
 }
 
-#[derive(Debug, Builder, Clone, Default)]
+#[derive(Debug, Builder, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 /// Represents a collection of exons, which are contiguous regions within genomic sequences.
 ///
 /// Exons are the parts of a gene's DNA that code for proteins, and they're separated by
@@ -83,8 +79,12 @@ pub struct Exons {
 }
 
 impl FromStr for Exons {
-    type Err = io::Error;
+    type Err = TsgError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Nodes without a genomic location, such as imported GFA segments, have no exons
+        if s.is_empty() {
+            return Ok(Exons::default());
+        }
         let exons = s
             .split(',')
             .map(|x| x.parse())
@@ -192,9 +192,14 @@ impl Exons {
 
 #[allow(clippy::duplicated_attributes)]
 #[derive(Debug, Clone, Builder, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[builder(on(BString, into))] // This enables automatic conversion to BString from string types
 #[builder(on(ReadIdentity, into))] // This enables automatic conversion to ReadIdentity from strings
 pub struct ReadData {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub id: BString,
     pub identity: ReadIdentity,
 }
@@ -206,15 +211,16 @@ impl fmt::Display for ReadData {
 }
 
 impl FromStr for ReadData {
-    type Err = io::Error;
+    type Err = TsgError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // <id>:<identity>
         let fields: Vec<&str> = s.split(':').collect();
         if fields.len() != 2 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Invalid read line format: {}", s),
+            return Err(TsgError::malformed(
+                "N",
+                "reads",
+                format!("Invalid read format: {}", s),
             ));
         }
 
@@ -225,6 +231,7 @@ impl FromStr for ReadData {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ReadIdentity {
     SO, // source
     IN, // intermediate
@@ -242,15 +249,16 @@ impl fmt::Display for ReadIdentity {
 }
 
 impl FromStr for ReadIdentity {
-    type Err = io::Error;
+    type Err = TsgError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "SO" => Ok(ReadIdentity::SO),
             "IN" => Ok(ReadIdentity::IN),
             "SI" => Ok(ReadIdentity::SI),
-            _ => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
+            _ => Err(TsgError::malformed(
+                "N",
+                "reads",
                 format!("Invalid read identity: {}", s),
             )),
         }
@@ -264,21 +272,28 @@ impl From<&str> for ReadIdentity {
 }
 
 /// Represents DNA strand orientation
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Strand {
     #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "+"))]
     Forward,
+    #[cfg_attr(feature = "serde", serde(rename = "-"))]
     Reverse,
 }
 
 impl FromStr for Strand {
-    type Err = anyhow::Error;
+    type Err = TsgError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "+" => Ok(Strand::Forward),
             "-" => Ok(Strand::Reverse),
-            _ => Err(anyhow::anyhow!("Invalid strand: {}", s)),
+            _ => Err(TsgError::malformed(
+                "N",
+                "strand",
+                format!("Invalid strand: {}", s),
+            )),
         }
     }
 }
@@ -293,15 +308,38 @@ impl fmt::Display for Strand {
 }
 
 /// Node in the transcript segment graph
-#[derive(Debug, Clone, Default, Builder)]
+#[derive(Debug, Clone, Default, Builder, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[builder(on(BString, into))]
 pub struct NodeData {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub id: BString,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::graph::serialization::bstring")
+    )]
     pub reference_id: BString,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub strand: Strand,
     pub exons: Exons,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub reads: Vec<ReadData>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::graph::serialization::option_bstring"
+        )
+    )]
     pub sequence: Option<BString>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, with = "crate::graph::serialization::attributes")
+    )]
     pub attributes: HashMap<BString, Attribute>,
 }
 
@@ -322,8 +360,8 @@ impl NodeData {
     pub fn to_json(&self, attributes: Option<&[Attribute]>) -> Result<serde_json::Value> {
         let mut data = json!({
             "chrom": self.reference_id.to_str().unwrap(),
-            "ref_start": self.reference_start(),
-            "ref_end": self.reference_end(),
+            "ref_start": self.exons.exons.first().map(|exon| exon.start),
+            "ref_end": self.exons.exons.last().map(|exon| exon.end),
             "strand": self.strand.to_string(),
             "exons": format!("[{}]",  self.exons.to_string()),
             "reads": self.reads.par_iter().map(|r| format!("{}", r) ).collect::<Vec<_>>(),
@@ -351,6 +389,21 @@ impl NodeData {
         Ok(json)
     }
 
+    /// Convert the node to a BED12 record named after the node
+    ///
+    /// Blocks are the exons of the node and the score is read from the numeric
+    /// attribute `score_attribute`, if given.
+    pub fn to_bed12(&self, score_attribute: Option<&str>) -> Result<BString> {
+        let exons: Vec<&Interval> = self.exons.exons.iter().collect();
+        bed12_record(
+            self.reference_id.as_bstr(),
+            self.id.as_bstr(),
+            bed_score(self.attributes.values(), score_attribute),
+            self.strand,
+            &exons,
+        )
+    }
+
     pub fn to_gtf(&self, attributes: Option<&[Attribute]>) -> Result<BString> {
         // chr1    scannls exon    173867960       173867991       .       -       .       exon_id "001"; segment_id "0001"; ptc "1"; ptf "1.0"; transcript_id "3x1"; gene_id "3";
         let mut res = vec![];
@@ -399,15 +452,17 @@ impl fmt::Display for NodeData {
 }
 
 impl FromStr for NodeData {
-    type Err = io::Error;
+    type Err = TsgError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // N  <rid>:<id>  <chrom>:<strand>:<exons>  <reads>  [<seq>]
-        let fields: Vec<&str> = s.split_whitespace().collect();
-        if fields.len() < 4 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Invalid node line format: {}", s),
+        // Tab-separated records keep an empty reads field, as written for nodes without reads
+        let fields = split_fields(s);
+        if fields.len() < 3 {
+            return Err(TsgError::malformed(
+                "N",
+                "fields",
+                format!("expected at least 3 fields, found {}", fields.len()),
             ));
         }
 
@@ -415,24 +470,24 @@ impl FromStr for NodeData {
         let id: BString = fields[1].into();
 
         let reference_and_exons: Vec<&str> = fields[2].split(":").collect();
+        if reference_and_exons.len() != 3 {
+            return Err(TsgError::malformed(
+                "N",
+                "genomic_location",
+                format!("expected <chrom>:<strand>:<exons>, found {}", fields[2]),
+            ));
+        }
         let reference_id = reference_and_exons[0].into();
-        let strand = reference_and_exons[1].parse().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Failed to parse strand: {}", e),
-            )
-        })?;
-        let exons = reference_and_exons[2].parse().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Failed to parse exons: {}", e),
-            )
-        })?;
-
-        let reads = fields[3]
-            .split(',')
-            .map(|s| s.parse().context("failed to parse reads").unwrap())
-            .collect::<Vec<_>>();
+        let strand = reference_and_exons[1].parse()?;
+        let exons = reference_and_exons[2].parse()?;
+
+        let reads = match fields.get(3) {
+            Some(reads) if !reads.is_empty() => reads
+                .split(',')
+                .map(|s| s.parse())
+                .collect::<Result<Vec<ReadData>, _>>()?,
+            _ => Vec::new(),
+        };
 
         let sequence = if fields.len() > 4 && !fields[4].is_empty() {
             Some(fields[4].into())
@@ -461,6 +516,16 @@ mod tests {
     fn test_node_from_str() {
         let node1 = NodeData::from_str("N\tn1\tchr1:+:1000-2000\tread1:SO").unwrap();
         assert_eq!(node1.id, "n1");
+
+        // nodes without reads are written with an empty reads field
+        let node2 = NodeData::from_str("N\tn2\tchr1:+:2500-2500\t\t").unwrap();
+        assert!(node2.reads.is_empty());
+        assert_eq!(node2.to_string(), "N\tn2\tchr1:+:2500-2500\t\t");
+
+        // nodes without a location keep their empty reference and exons
+        let node3 = NodeData::from_str("N\tn3\t:+:\t\tACGT").unwrap();
+        assert!(node3.reference_id.is_empty() && node3.exons.is_empty());
+        assert_eq!(node3.to_string(), "N\tn3\t:+:\t\tACGT");
     }
 
     #[test]
@@ -0,0 +1,131 @@
+use bon::Builder;
+use bstr::BString;
+
+/// How the parser reacts to records it cannot interpret
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Stop at the first bad record and return its error
+    #[default]
+    Strict,
+    /// Skip bad records and collect their errors as diagnostics
+    Lenient,
+}
+
+/// What happens when an edge is added between two nodes that an edge already connects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EdgeConflictPolicy {
+    /// Keep both as parallel edges
+    #[default]
+    Keep,
+    /// Overwrite the data and ID of the existing edge, the earliest added one if there are
+    /// several; the groups and links parsed so far follow it to its new ID
+    Replace,
+    /// Fail to add the edge
+    Error,
+}
+
+/// Options controlling how a TSG file is parsed
+#[derive(Debug, Clone, Default, Builder)]
+pub struct ParseOptions {
+    #[builder(default)]
+    pub mode: ParseMode,
+    /// How `E` records between already connected nodes are handled
+    #[builder(default)]
+    pub edge_conflict: EdgeConflictPolicy,
+}
+
+impl ParseOptions {
+    /// Options that abort on the first malformed record
+    pub fn strict() -> Self {
+        Self {
+            mode: ParseMode::Strict,
+            ..Default::default()
+        }
+    }
+
+    /// Options that skip malformed records and report them as diagnostics
+    pub fn lenient() -> Self {
+        Self {
+            mode: ParseMode::Lenient,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_lenient(&self) -> bool {
+        self.mode == ParseMode::Lenient
+    }
+}
+
+/// Order in which writers emit graphs, nodes, edges, groups and paths
+///
+/// With the `clap` feature the policy can be used directly as a command line value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum OrderingPolicy {
+    /// Graphs and groups by ID, nodes by genomic position then ID,
+    /// edges by source then target and paths by ID
+    #[default]
+    Sorted,
+    /// The order in which elements were read from the input or added, kept through removals
+    /// and renames
+    Insertion,
+}
+
+/// Options controlling how graphs are written
+#[derive(Debug, Clone, Default, Builder)]
+pub struct WriteOptions {
+    #[builder(default)]
+    pub ordering: OrderingPolicy,
+}
+
+impl WriteOptions {
+    /// Options that write elements in a stable, sorted order
+    pub fn sorted() -> Self {
+        Self {
+            ordering: OrderingPolicy::Sorted,
+        }
+    }
+
+    /// Options that keep the order of the input
+    pub fn insertion() -> Self {
+        Self {
+            ordering: OrderingPolicy::Insertion,
+        }
+    }
+}
+
+/// Options controlling the DOT rendering of a graph section
+#[derive(Debug, Clone, Builder)]
+pub struct DotOptions {
+    /// Label nodes with their ID, locus and read count instead of their ID only
+    #[builder(default = true)]
+    pub node_label: bool,
+    /// Label edges with their structural variant type
+    #[builder(default = true)]
+    pub edge_label: bool,
+    /// Paths drawn in colour, while the other elements are greyed out
+    #[builder(default)]
+    pub highlight_paths: Vec<BString>,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Options controlling the SVG plot of a graph section
+#[derive(Debug, Clone, Builder)]
+pub struct PlotOptions {
+    /// Width of the image in pixels
+    #[builder(default = 1200)]
+    pub width: usize,
+    /// Shrink introns and other gaps between exons to at most this many base pairs
+    pub max_intron: Option<usize>,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
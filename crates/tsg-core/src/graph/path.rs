@@ -2,14 +2,15 @@ use std::fmt;
 
 use super::Attribute;
 use super::GraphSection;
-use super::utils::to_hash_identifier;
+use super::utils::{bed_score, bed12_record, gff3_escape, gff3_record, to_hash_identifier};
+use super::{Interval, NodeData, Strand};
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
 use bon::Builder;
-use bstr::BString;
 use bstr::ByteSlice;
 use bstr::ByteVec;
+use bstr::{BStr, BString};
 use petgraph::graph::{EdgeIndex, NodeIndex};
 use tracing::debug;
 
@@ -96,26 +97,35 @@ impl<'a> TSGPath<'a> {
         self.nodes.is_empty()
     }
 
+    /// Hash of the node IDs of the path
+    ///
+    /// Where the path takes one of several parallel edges, the ID of that edge is hashed
+    /// before the node it leads to, so that paths differing only in those edges get
+    /// distinct IDs.
     pub fn id(&self) -> Result<BString> {
         if self.nodes.is_empty() {
             return Err(anyhow!("No nodes in path"));
         }
+        let graph = self.graph.ok_or_else(|| anyhow!("Graph not available"))?;
+        let inner = graph.inner_graph();
 
-        let node_id_string = self
-            .nodes
-            .iter()
-            .map(|node_idx| {
-                let node_data = self
-                    .graph
-                    .ok_or_else(|| anyhow!("Graph not available"))
-                    .unwrap()
-                    .node_by_idx(*node_idx)
-                    .context(format!("Node not found for index: {}", node_idx.index()))
-                    .unwrap();
-                node_data.id.to_str().unwrap()
-            })
-            .collect::<Vec<&str>>()
-            .join("-");
+        let mut ids: Vec<&str> = Vec::with_capacity(self.nodes.len());
+        for (i, node_idx) in self.nodes.iter().enumerate() {
+            if i > 0
+                && let Some(&edge_idx) = self.edges.get(i - 1)
+                && inner
+                    .edges_connecting(self.nodes[i - 1], *node_idx)
+                    .nth(1)
+                    .is_some()
+            {
+                ids.push(inner[edge_idx].id.to_str()?);
+            }
+            let node_data = graph
+                .node_by_idx(*node_idx)
+                .context(format!("Node not found for index: {}", node_idx.index()))?;
+            ids.push(node_data.id.to_str()?);
+        }
+        let node_id_string = ids.join("-");
 
         debug!("Node ID string: {}", node_id_string);
         let id = to_hash_identifier(&node_id_string, Some(16))?;
@@ -129,15 +139,49 @@ impl<'a> TSGPath<'a> {
         Ok(())
     }
 
+    /// Data of the nodes of the path, in path order
+    fn node_data(&self) -> Result<Vec<&NodeData>> {
+        let graph = self.graph.ok_or_else(|| anyhow!("Graph not available"))?;
+        self.nodes
+            .iter()
+            .map(|node_idx| {
+                graph
+                    .node_by_idx(*node_idx)
+                    .with_context(|| format!("Node not found for index: {}", node_idx.index()))
+            })
+            .collect()
+    }
+
+    /// Reference, start, end and strand of the path
+    ///
+    /// The reference and strand are those of the first node with exons, and the
+    /// span covers the exons of all nodes on that reference.
+    pub fn location(&self) -> Result<(BString, usize, usize, Strand)> {
+        let nodes = self.node_data()?;
+        let first = nodes
+            .iter()
+            .find(|node| !node.exons.is_empty())
+            .ok_or_else(|| anyhow!("No exons in path"))?;
+        let exons = nodes
+            .iter()
+            .filter(|node| node.reference_id == first.reference_id)
+            .flat_map(|node| &node.exons.exons);
+        let (start, end) = exons.fold((usize::MAX, 0), |(start, end), exon| {
+            (start.min(exon.start), end.max(exon.end))
+        });
+        Ok((first.reference_id.clone(), start, end, first.strand))
+    }
+
     pub fn to_gtf(&self) -> Result<BString> {
         let id = self.id()?;
+        let (reference_id, start, end, strand) = self.location()?;
         let mut transcript = format!(
-            ".\ttsg\ttranscript\t.\t.\t.\t.\t.\ttranscript_id \"{}\";",
-            id
+            "{}\ttsg\ttranscript\t{}\t{}\t.\t{}\t.\ttranscript_id \"{}\";",
+            reference_id, start, end, strand, id
         );
 
         for attr in &self.attributes {
-            let attr_str = format!("{} \"{}\";", attr.tag, attr.value);
+            let attr_str = format!(" {} \"{}\";", attr.tag, attr.value);
             transcript.push_str(&attr_str);
         }
 
@@ -169,6 +213,195 @@ impl<'a> TSGPath<'a> {
         Ok(exon_strs.join("\n").into())
     }
 
+    /// Convert the path to GFF3 records: an `mRNA` under `parent` and its `exon`s
+    ///
+    /// The mRNA is identified as `<graph_id>.<path_id>` so that paths of different graphs
+    /// stay distinct, and each exon as `<mRNA ID>.exon<n>`, numbered along the strand.
+    /// Overlapping or adjacent exons of several nodes are merged into one exon listing all
+    /// their `node_id`s. The edges of the path are listed in path order in the
+    /// `edge_ids`, `sv_types` and `sv_breakpoints` attributes of the mRNA.
+    ///
+    /// The exons of an mRNA share its reference and strand, so paths whose nodes change
+    /// reference or strand are rejected.
+    pub fn to_gff3(&self, parent: &BStr) -> Result<BString> {
+        let graph = self.graph.ok_or_else(|| anyhow!("Graph not available"))?;
+        let path_id = self.id()?;
+        let mrna_id = format!("{}.{}", graph.id, path_id);
+        let (reference_id, start, end, strand) = self.location()?;
+
+        let nodes: Vec<_> = self
+            .node_data()?
+            .into_iter()
+            .filter(|node| !node.exons.is_empty())
+            .collect();
+        if let Some(node) = nodes
+            .iter()
+            .find(|node| node.reference_id != reference_id || node.strand != strand)
+        {
+            return Err(anyhow!(
+                "Path {} changes from {}:{} to {}:{} at node {}",
+                path_id,
+                reference_id,
+                strand,
+                node.reference_id,
+                node.strand,
+                node.id
+            ));
+        }
+
+        let mut attributes = vec![
+            ("ID".to_string(), gff3_escape(mrna_id.as_bytes())),
+            ("Parent".to_string(), gff3_escape(parent)),
+            ("Name".to_string(), gff3_escape(&path_id)),
+        ];
+        let edges = self
+            .edges
+            .iter()
+            .map(|edge_idx| {
+                graph
+                    .edge_by_idx(*edge_idx)
+                    .with_context(|| format!("Edge not found for index: {}", edge_idx.index()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if !edges.is_empty() {
+            let join = |values: Vec<String>| values.join(",");
+            attributes.push((
+                "edge_ids".to_string(),
+                join(edges.iter().map(|edge| gff3_escape(&edge.id)).collect()),
+            ));
+            attributes.push((
+                "sv_types".to_string(),
+                join(
+                    edges
+                        .iter()
+                        .map(|edge| gff3_escape(&edge.sv.sv_type))
+                        .collect(),
+                ),
+            ));
+            attributes.push((
+                "sv_breakpoints".to_string(),
+                join(
+                    edges
+                        .iter()
+                        .map(|edge| {
+                            gff3_escape(
+                                format!(
+                                    "{}:{}-{}:{}",
+                                    edge.sv.reference_name1,
+                                    edge.sv.breakpoint1,
+                                    edge.sv.reference_name2,
+                                    edge.sv.breakpoint2
+                                )
+                                .as_bytes(),
+                            )
+                        })
+                        .collect(),
+                ),
+            ));
+        }
+        attributes.extend(
+            self.attributes
+                .iter()
+                .map(|attr| (attr.tag.to_string(), gff3_escape(&attr.value))),
+        );
+
+        let mut lines = vec![gff3_record(
+            reference_id.as_bstr(),
+            "mRNA",
+            start,
+            end,
+            strand,
+            &attributes,
+        )];
+
+        // Exons by position, each with the nodes it comes from
+        let mut blocks: Vec<_> = nodes
+            .iter()
+            .flat_map(|node| node.exons.exons.iter().map(move |exon| (exon, *node)))
+            .collect();
+        blocks.sort_by_key(|(exon, _)| (exon.start, exon.end));
+        let mut exons: Vec<(usize, usize, Vec<&NodeData>)> = Vec::new();
+        for (exon, node) in blocks {
+            match exons.last_mut() {
+                Some((_, end, nodes)) if exon.start <= *end + 1 => {
+                    *end = (*end).max(exon.end);
+                    if !nodes.iter().any(|n| n.id == node.id) {
+                        nodes.push(node);
+                    }
+                }
+                _ => exons.push((exon.start, exon.end, vec![node])),
+            }
+        }
+        if strand == Strand::Reverse {
+            exons.reverse();
+        }
+
+        for (exon_number, (exon_start, exon_end, nodes)) in exons.into_iter().enumerate() {
+            let node_ids: Vec<String> = nodes.iter().map(|node| gff3_escape(&node.id)).collect();
+            let mut attributes = vec![
+                (
+                    "ID".to_string(),
+                    gff3_escape(format!("{}.exon{}", mrna_id, exon_number + 1).as_bytes()),
+                ),
+                ("Parent".to_string(), gff3_escape(mrna_id.as_bytes())),
+                ("node_id".to_string(), node_ids.join(",")),
+            ];
+            // Merged nodes contribute the attributes not already set by an earlier node
+            for node in nodes {
+                let mut node_attributes: Vec<_> = node.attributes.values().collect();
+                node_attributes.sort_by(|a, b| a.tag.cmp(&b.tag));
+                for attr in node_attributes {
+                    let tag = attr.tag.to_string();
+                    if !attributes.iter().any(|(existing, _)| *existing == tag) {
+                        attributes.push((tag, gff3_escape(&attr.value)));
+                    }
+                }
+            }
+            lines.push(gff3_record(
+                reference_id.as_bstr(),
+                "exon",
+                exon_start,
+                exon_end,
+                strand,
+                &attributes,
+            ));
+        }
+        Ok(lines.join("\n").into())
+    }
+
+    /// Convert the path to a BED12 record named after the path ID
+    ///
+    /// Blocks are the exons of all nodes of the path and the strand is that of the first node.
+    /// The score is read from the numeric path attribute `score_attribute`, if given.
+    /// TSG coordinates are 1-based and inclusive, as in GTF, and are converted to BED's
+    /// 0-based, half-open coordinates. Paths across several references cannot be written as BED.
+    pub fn to_bed12(&self, score_attribute: Option<&str>) -> Result<BString> {
+        let id = self.id()?;
+        let nodes = self.node_data()?;
+
+        let first = nodes[0];
+        if let Some(node) = nodes
+            .iter()
+            .find(|node| node.reference_id != first.reference_id)
+        {
+            return Err(anyhow!(
+                "Path {} spans {} and {}",
+                id,
+                first.reference_id,
+                node.reference_id
+            ));
+        }
+
+        let exons: Vec<&Interval> = nodes.iter().flat_map(|node| &node.exons.exons).collect();
+        bed12_record(
+            first.reference_id.as_bstr(),
+            id.as_bstr(),
+            bed_score(&self.attributes, score_attribute),
+            first.strand,
+            &exons,
+        )
+    }
+
     pub fn to_vcf(&self) -> Result<BString> {
         let _id = self.id()?;
         let mut edges = vec![];
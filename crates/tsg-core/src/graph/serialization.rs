@@ -0,0 +1,417 @@
+//! Serde support for the graph model, enabled by the `serde` feature
+//!
+//! The schema mirrors the TSG records and is kept stable across releases:
+//!
+//! ```text
+//! TSGraph        { "headers": [Header], "comments": [str] (optional), "graphs": [GraphSection],
+//!                  "links": [InterGraphLink] }
+//! Header         { "tag": str, "value": str }
+//! GraphSection   { "id": str, "attributes": [Attribute], "comments": [str] (optional),
+//!                  "nodes": [NodeData], "edges": [Edge], "groups": [Group] }
+//! NodeData       { "id": str, "reference_id": str, "strand": "+" | "-",
+//!                  "exons": [{ "start": int, "end": int }],
+//!                  "reads": [{ "id": str, "identity": "SO" | "IN" | "SI" }],
+//!                  "sequence": str (optional), "attributes": [Attribute] }
+//! Edge           { "id": str, "source": str, "target": str,
+//!                  "sv": { "reference_name1": str, "reference_name2": str,
+//!                          "breakpoint1": int, "breakpoint2": int, "sv_type": str },
+//!                  "attributes": [Attribute] }
+//! Group          { "type": "unordered" | "chain", "id": str, "elements": [str],
+//!                  "attributes": [Attribute] }
+//!              | { "type": "ordered", "id": str,
+//!                  "elements": [{ "id": str, "orientation": "+" | "-" (optional) }],
+//!                  "attributes": [Attribute] }
+//! InterGraphLink { "id": str, "source_graph": str, "source_element": str,
+//!                  "target_graph": str, "target_element": str, "link_type": str,
+//!                  "attributes": [Attribute] }
+//! Attribute      { "tag": str, "type": "i" | "f" | "Z" | "J" | "H" | "B", "value": str }
+//! ```
+//!
+//! Graphs, nodes, edges and groups are listed in sorted order, or in the order given to
+//! [`TSGraph::serialize_with_ordering`]; attributes are sorted by tag. Attribute values
+//! keep their TSG text so that no precision is lost; `type` tells how to interpret them.
+
+use std::borrow::Cow;
+
+use ahash::{HashMap, HashMapExt};
+use bstr::{BString, ByteSlice};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    Attribute, EdgeData, GraphSection, Group, Header, InterGraphLink, NodeData, OrderingPolicy,
+    TSGraph,
+};
+
+/// Byte strings as (lossily decoded) UTF-8 strings
+pub(crate) mod bstring {
+    use super::*;
+
+    pub fn serialize<S: Serializer, T: AsRef<[u8]>>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.as_ref().to_str_lossy())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BString, D::Error> {
+        Cow::<str>::deserialize(deserializer).map(|value| BString::from(value.as_ref()))
+    }
+}
+
+/// Optional byte strings as optional strings
+pub(crate) mod option_bstring {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<BString>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value
+            .as_ref()
+            .map(|value| value.to_str_lossy())
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<BString>, D::Error> {
+        Option::<Cow<str>>::deserialize(deserializer)
+            .map(|value| value.map(|value| BString::from(value.as_ref())))
+    }
+}
+
+/// Lists of byte strings as lists of strings
+pub(crate) mod bstrings {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(values: &[BString], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(|value| value.to_str_lossy()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<BString>, D::Error> {
+        Vec::<Cow<str>>::deserialize(deserializer).map(|values| {
+            values
+                .iter()
+                .map(|value| BString::from(value.as_ref()))
+                .collect()
+        })
+    }
+}
+
+/// Attribute maps as lists of attributes sorted by tag
+pub(crate) mod attributes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        attributes: &HashMap<BString, Attribute>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut attributes: Vec<&Attribute> = attributes.values().collect();
+        attributes.sort_by(|a, b| a.tag.cmp(&b.tag));
+        serializer.collect_seq(attributes)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<BString, Attribute>, D::Error> {
+        let attributes = Vec::<Attribute>::deserialize(deserializer)?;
+        let mut map = HashMap::with_capacity(attributes.len());
+        for attr in attributes {
+            match attr.attribute_type {
+                'i' | 'f' | 'Z' | 'J' | 'H' | 'B' => {}
+                other => {
+                    return Err(D::Error::custom(format!(
+                        "unknown type {} of attribute {}",
+                        other, attr.tag
+                    )));
+                }
+            }
+            map.insert(attr.tag.clone(), attr);
+        }
+        Ok(map)
+    }
+}
+
+/// Comment lines as a list of (lossily decoded) UTF-8 strings
+mod comments {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &[BString], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(value.iter().map(|comment| comment.to_str_lossy()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<BString>, D::Error> {
+        Vec::<String>::deserialize(deserializer)
+            .map(|comments| comments.into_iter().map(BString::from).collect())
+    }
+}
+
+/// An edge together with the IDs of its endpoints
+#[derive(Serialize)]
+struct EdgeRef<'a> {
+    #[serde(with = "bstring")]
+    id: &'a BString,
+    #[serde(with = "bstring")]
+    source: &'a BString,
+    #[serde(with = "bstring")]
+    target: &'a BString,
+    sv: &'a super::StructuralVariant,
+    #[serde(with = "attributes")]
+    attributes: &'a HashMap<BString, Attribute>,
+}
+
+#[derive(Deserialize)]
+struct EdgeRecord {
+    #[serde(with = "bstring")]
+    source: BString,
+    #[serde(with = "bstring")]
+    target: BString,
+    #[serde(flatten)]
+    edge: EdgeData,
+}
+
+#[derive(Serialize)]
+struct GraphSectionRef<'a> {
+    #[serde(with = "bstring")]
+    id: &'a BString,
+    #[serde(with = "attributes")]
+    attributes: &'a HashMap<BString, Attribute>,
+    #[serde(with = "comments", skip_serializing_if = "<[_]>::is_empty")]
+    comments: &'a [BString],
+    nodes: Vec<&'a NodeData>,
+    edges: Vec<EdgeRef<'a>>,
+    groups: Vec<&'a Group>,
+}
+
+#[derive(Deserialize)]
+struct GraphSectionRecord {
+    #[serde(with = "bstring")]
+    id: BString,
+    #[serde(default, with = "attributes")]
+    attributes: HashMap<BString, Attribute>,
+    #[serde(default, with = "comments")]
+    comments: Vec<BString>,
+    #[serde(default)]
+    nodes: Vec<NodeData>,
+    #[serde(default)]
+    edges: Vec<EdgeRecord>,
+    #[serde(default)]
+    groups: Vec<Group>,
+}
+
+impl GraphSection {
+    fn to_serializable(&self, ordering: OrderingPolicy) -> GraphSectionRef<'_> {
+        let graph = self.inner_graph();
+        let edges = self
+            .ordered_edge_indices(ordering)
+            .into_iter()
+            .map(|idx| {
+                let (source, target) = graph.edge_endpoints(idx).unwrap();
+                let edge = &graph[idx];
+                EdgeRef {
+                    id: &edge.id,
+                    source: &graph[source].id,
+                    target: &graph[target].id,
+                    sv: &edge.sv,
+                    attributes: &edge.attributes,
+                }
+            })
+            .collect();
+        GraphSectionRef {
+            id: &self.id,
+            attributes: &self.attributes,
+            comments: &self.comments,
+            nodes: self
+                .ordered_node_indices(ordering)
+                .into_iter()
+                .map(|idx| &graph[idx])
+                .collect(),
+            edges,
+            groups: self
+                .ordered_groups(ordering)
+                .into_iter()
+                .map(|(_, group)| group)
+                .collect(),
+        }
+    }
+}
+
+impl Serialize for GraphSection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_serializable(OrderingPolicy::Sorted)
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GraphSection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = GraphSectionRecord::deserialize(deserializer)?;
+        let mut graph = GraphSection::new(record.id);
+        graph.attributes = record.attributes;
+        graph.comments = record.comments;
+        for node in record.nodes {
+            if graph.node_indices.contains_key(&node.id) {
+                return Err(D::Error::custom(format!("duplicate node {}", node.id)));
+            }
+            graph.add_node(node).map_err(D::Error::custom)?;
+        }
+        for EdgeRecord {
+            source,
+            target,
+            edge,
+        } in record.edges
+        {
+            for endpoint in [&source, &target] {
+                if !graph.node_indices.contains_key(endpoint) {
+                    return Err(D::Error::custom(format!(
+                        "edge {} refers to unknown node {}",
+                        edge.id, endpoint
+                    )));
+                }
+            }
+            graph
+                .add_edge(source.as_bstr(), target.as_bstr(), edge)
+                .map_err(D::Error::custom)?;
+        }
+        for group in record.groups {
+            let id = match &group {
+                Group::Unordered { id, .. }
+                | Group::Ordered { id, .. }
+                | Group::Chain { id, .. } => id.clone(),
+            };
+            graph.add_group(id, group);
+        }
+        Ok(graph)
+    }
+}
+
+#[derive(Serialize)]
+struct TSGraphRef<'a> {
+    headers: &'a [Header],
+    #[serde(with = "comments", skip_serializing_if = "<[_]>::is_empty")]
+    comments: &'a [BString],
+    graphs: Vec<GraphSectionRef<'a>>,
+    links: &'a [InterGraphLink],
+}
+
+#[derive(Deserialize)]
+struct TSGraphRecord {
+    #[serde(default)]
+    headers: Vec<Header>,
+    #[serde(default, with = "comments")]
+    comments: Vec<BString>,
+    #[serde(default)]
+    graphs: Vec<GraphSection>,
+    #[serde(default)]
+    links: Vec<InterGraphLink>,
+}
+
+impl TSGraph {
+    /// Serialize the graph, listing graphs, nodes, edges and groups in the given order
+    pub fn serialize_with_ordering<S: Serializer>(
+        &self,
+        serializer: S,
+        ordering: OrderingPolicy,
+    ) -> Result<S::Ok, S::Error> {
+        TSGraphRef {
+            headers: &self.headers,
+            comments: &self.comments,
+            graphs: self
+                .ordered_graphs(ordering)
+                .into_iter()
+                .map(|graph| graph.to_serializable(ordering))
+                .collect(),
+            links: &self.links,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl Serialize for TSGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serialize_with_ordering(serializer, OrderingPolicy::Sorted)
+    }
+}
+
+impl<'de> Deserialize<'de> for TSGraph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = TSGraphRecord::deserialize(deserializer)?;
+        let mut tsgraph = TSGraph {
+            headers: record.headers,
+            comments: record.comments,
+            links: record.links,
+            ..Default::default()
+        };
+        for graph in record.graphs {
+            if tsgraph.graphs.contains_key(&graph.id) {
+                return Err(D::Error::custom(format!("duplicate graph {}", graph.id)));
+            }
+            tsgraph.add_graph(graph);
+        }
+        Ok(tsgraph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_serde_roundtrip() -> Result<()> {
+        for file in ["tests/data/test.tsg", "tests/data/test_mul.tsg"] {
+            let tsgraph = TSGraph::from_file(file)?;
+            let json = serde_json::to_string(&tsgraph)?;
+            let parsed: TSGraph = serde_json::from_str(&json)?;
+
+            let mut expected = Vec::new();
+            tsgraph.to_writer(&mut expected)?;
+            let mut actual = Vec::new();
+            parsed.to_writer(&mut actual)?;
+            assert_eq!(actual.to_str_lossy(), expected.to_str_lossy(), "{}", file);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_serde_schema() -> Result<()> {
+        let tsgraph = TSGraph::from_file("tests/data/test.tsg")?;
+        let json = serde_json::to_value(&tsgraph)?;
+        assert_eq!(json["headers"][0]["tag"], "TSG");
+
+        let graph = &json["graphs"][0];
+        assert_eq!(graph["id"], "graph");
+        let n1 = &graph["nodes"][0];
+        assert_eq!(n1["id"], "n1");
+        assert_eq!(n1["strand"], "+");
+        assert_eq!(n1["exons"][1]["start"], 1500);
+        assert_eq!(n1["reads"][0]["identity"], "SO");
+        assert_eq!(n1["attributes"][0]["tag"], "expression");
+        assert_eq!(n1["attributes"][0]["type"], "f");
+        assert_eq!(n1["attributes"][0]["value"], "10.5");
+
+        let e1 = &graph["edges"][0];
+        assert_eq!(e1["source"], "n1");
+        assert_eq!(e1["sv"]["sv_type"], "INV");
+
+        let path = graph["groups"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|group| group["id"] == "transcript1")
+            .unwrap();
+        assert_eq!(path["type"], "ordered");
+        assert_eq!(path["elements"][0]["orientation"], "+");
+
+        let invalid = r#"{"graphs": [{"id": "g", "edges": [{"id": "e", "source": "a",
+            "target": "b", "sv": {"reference_name1": "chr1", "reference_name2": "chr1",
+            "breakpoint1": 1, "breakpoint2": 2, "sv_type": "DEL"}}]}]}"#;
+        assert!(serde_json::from_str::<TSGraph>(invalid).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,325 @@
+use super::{Attribute, GraphAnalysis, GraphSection, Group, OrderingPolicy};
+use ahash::HashSet;
+use anyhow::{Result, bail};
+use bstr::BString;
+use petgraph::graph::NodeIndex;
+use serde_json::json;
+
+/// Superbubble of a graph section, with the superbubbles nested inside it
+///
+/// A site is entered through a single node and left through a single node, and every
+/// route from its entry reaches its exit without leaving the site.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Site {
+    /// `site_<entry>_<exit>`, also the ID of its `U` group
+    ///
+    /// IDs are unique within the graph section: an ID already taken by an element or by
+    /// another site, for instance because node IDs contain `_`, gets a `_<n>` suffix.
+    pub id: BString,
+    pub entry: BString,
+    pub exit: BString,
+    /// Nodes of the site, its entry first, its exit last and the others by position
+    pub nodes: Vec<BString>,
+    /// Edges between nodes of the site
+    pub edges: Vec<BString>,
+    /// IDs of the traversed paths running from the entry to the exit
+    pub paths: Vec<BString>,
+    /// Sites nested inside, by position
+    pub children: Vec<Site>,
+}
+
+impl Site {
+    /// Number of nesting levels of the site, one without nested sites
+    pub fn depth(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(Site::depth)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The site followed by its nested sites, depth first
+    pub fn flatten(&self) -> Vec<&Site> {
+        let mut sites = vec![self];
+        for child in &self.children {
+            sites.extend(child.flatten());
+        }
+        sites
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let ids = |ids: &[BString]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        json!({
+            "id": self.id.to_string(),
+            "entry": self.entry.to_string(),
+            "exit": self.exit.to_string(),
+            "nodes": ids(&self.nodes),
+            "edges": ids(&self.edges),
+            "paths": ids(&self.paths),
+            "children": self.children.iter().map(Site::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    /// `U` group of the site with its entry, exit, parent site, nesting level and number
+    /// of paths as attributes
+    fn to_group(&self, parent: Option<&BString>, level: usize) -> Group {
+        let mut attributes = vec![
+            Attribute::builder()
+                .tag("entry")
+                .value(self.entry.clone())
+                .build(),
+            Attribute::builder()
+                .tag("exit")
+                .value(self.exit.clone())
+                .build(),
+            Attribute::builder()
+                .tag("level")
+                .attribute_type('i')
+                .value(level.to_string())
+                .build(),
+            Attribute::builder()
+                .tag("paths")
+                .attribute_type('i')
+                .value(self.paths.len().to_string())
+                .build(),
+        ];
+        if let Some(parent) = parent {
+            attributes.push(
+                Attribute::builder()
+                    .tag("parent")
+                    .value(parent.clone())
+                    .build(),
+            );
+        }
+        Group::Unordered {
+            id: self.id.clone(),
+            elements: self.nodes.iter().chain(&self.edges).cloned().collect(),
+            attributes: attributes
+                .into_iter()
+                .map(|attr| (attr.tag.clone(), attr))
+                .collect(),
+        }
+    }
+}
+
+impl GraphSection {
+    /// Decompose the graph section into a forest of nested sites
+    ///
+    /// Sites are the superbubbles found by [`GraphAnalysis::detect_bubbles`]; a site lies
+    /// within the smallest other site holding all of its nodes. Top-level sites and the
+    /// children of each site are ordered by the position of their entry. Site IDs differ
+    /// from each other and from the IDs of the nodes, edges and groups of the section.
+    pub fn decompose_sites(&self) -> Result<Vec<Site>> {
+        let graph = self.inner_graph();
+        let bubbles = self.detect_bubbles();
+        let members: Vec<HashSet<NodeIndex>> = bubbles
+            .iter()
+            .map(|bubble| bubble.iter().copied().collect())
+            .collect();
+
+        // The parent of a site is the smallest larger site containing it
+        let parents: Vec<Option<usize>> = (0..bubbles.len())
+            .map(|i| {
+                (0..bubbles.len())
+                    .filter(|&j| {
+                        members[j].len() > members[i].len() && members[i].is_subset(&members[j])
+                    })
+                    .min_by_key(|&j| members[j].len())
+            })
+            .collect();
+
+        let traversals: Vec<(BString, Vec<NodeIndex>)> = self
+            .traverse_with_ordering(OrderingPolicy::Sorted)?
+            .into_iter()
+            .map(|path| Ok((path.id()?, path.nodes)))
+            .collect::<Result<_>>()?;
+        let edges = self.ordered_edge_indices(OrderingPolicy::Sorted);
+
+        let node_id = |idx: NodeIndex| graph[idx].id.clone();
+        let build_site = |i: usize| {
+            let bubble = &bubbles[i];
+            let (entry, exit) = (bubble[0], bubble[bubble.len() - 1]);
+            let paths = traversals
+                .iter()
+                .filter(|(_, nodes)| {
+                    nodes
+                        .iter()
+                        .position(|&node| node == entry)
+                        .is_some_and(|start| nodes[start..].contains(&exit))
+                })
+                .map(|(id, _)| id.clone())
+                .collect();
+            Site {
+                id: format!("site_{}_{}", graph[entry].id, graph[exit].id).into(),
+                entry: node_id(entry),
+                exit: node_id(exit),
+                nodes: bubble.iter().map(|&idx| node_id(idx)).collect(),
+                edges: edges
+                    .iter()
+                    .filter(|&&edge| {
+                        let (source, target) = graph.edge_endpoints(edge).unwrap();
+                        members[i].contains(&source) && members[i].contains(&target)
+                    })
+                    .map(|&edge| graph[edge].id.clone())
+                    .collect(),
+                paths,
+                children: Vec::new(),
+            }
+        };
+
+        fn attach(i: usize, parents: &[Option<usize>], build_site: &dyn Fn(usize) -> Site) -> Site {
+            let mut site = build_site(i);
+            site.children = (0..parents.len())
+                .filter(|&j| parents[j] == Some(i))
+                .map(|j| attach(j, parents, build_site))
+                .collect();
+            site
+        }
+
+        let mut sites: Vec<Site> = (0..bubbles.len())
+            .filter(|&i| parents[i].is_none())
+            .map(|i| attach(i, &parents, &build_site))
+            .collect();
+
+        fn make_unique(sites: &mut [Site], taken: &mut HashSet<BString>) {
+            for site in sites {
+                let base = site.id.clone();
+                let mut n = 1;
+                while !taken.insert(site.id.clone()) {
+                    n += 1;
+                    site.id = format!("{}_{}", base, n).into();
+                }
+                make_unique(&mut site.children, taken);
+            }
+        }
+        let mut taken: HashSet<BString> = self
+            .node_indices
+            .keys()
+            .chain(self.edge_indices.keys())
+            .chain(self.groups.keys())
+            .cloned()
+            .collect();
+        make_unique(&mut sites, &mut taken);
+        Ok(sites)
+    }
+
+    /// Record sites and their nested sites as `U` groups
+    ///
+    /// Each group holds the nodes and edges of its site, with `entry`, `exit`, `level`
+    /// (zero for top-level sites), `paths` and, for nested sites, `parent` attributes.
+    /// Nothing is added when a site ID is already used by a node, edge or group.
+    pub fn add_site_groups(&mut self, sites: &[Site]) -> Result<()> {
+        for site in sites.iter().flat_map(Site::flatten) {
+            if self.node_indices.contains_key(&site.id)
+                || self.edge_indices.contains_key(&site.id)
+                || self.groups.contains_key(&site.id)
+            {
+                bail!("Site ID {} is already used in graph {}", site.id, self.id);
+            }
+        }
+
+        fn add(graph: &mut GraphSection, site: &Site, parent: Option<&BString>, level: usize) {
+            graph.add_group(site.id.clone(), site.to_group(parent, level));
+            for child in &site.children {
+                add(graph, child, Some(&site.id), level + 1);
+            }
+        }
+        for site in sites {
+            add(self, site, None, 0);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::TSGraph;
+    use std::str::FromStr;
+
+    /// A skipped region from a to e holding the alternative exons c1 or c2 from b to d
+    const NESTED_TSG: &str = "N\ta\tchr1:+:100-200\tr1:SO,r2:SO,r3:SO\n\
+        N\tb\tchr1:+:300-400\tr1:IN,r2:IN\n\
+        N\tc1\tchr1:+:500-600\tr1:IN\n\
+        N\tc2\tchr1:+:650-700\tr2:IN\n\
+        N\td\tchr1:+:800-900\tr1:IN,r2:IN\n\
+        N\te\tchr1:+:1000-1100\tr1:SI,r2:SI,r3:SI\n\
+        E\te1\ta\tb\tchr1,chr1,200,300,splice\n\
+        E\te2\tb\tc1\tchr1,chr1,400,500,splice\n\
+        E\te3\tb\tc2\tchr1,chr1,400,650,splice\n\
+        E\te4\tc1\td\tchr1,chr1,600,800,splice\n\
+        E\te5\tc2\td\tchr1,chr1,700,800,splice\n\
+        E\te6\td\te\tchr1,chr1,900,1000,splice\n\
+        E\te7\ta\te\tchr1,chr1,200,1000,splice\n";
+
+    #[test]
+    fn test_decompose_sites() -> Result<()> {
+        let mut tsg = TSGraph::from_str(NESTED_TSG)?;
+        let graph = tsg.default_graph().unwrap();
+        let sites = graph.decompose_sites()?;
+
+        assert_eq!(sites.len(), 1);
+        let outer = &sites[0];
+        assert_eq!(outer.id, "site_a_e");
+        assert_eq!(outer.nodes, ["a", "b", "c1", "c2", "d", "e"]);
+        assert_eq!(outer.edges.len(), 7);
+        assert_eq!(outer.paths.len(), 3, "two through b and one skipping");
+        assert_eq!(outer.depth(), 2);
+
+        let inner = &outer.children[0];
+        assert_eq!(
+            (inner.entry.as_slice(), inner.exit.as_slice()),
+            (&b"b"[..], &b"d"[..])
+        );
+        assert_eq!(inner.edges, ["e2", "e3", "e4", "e5"]);
+        assert_eq!(inner.paths.len(), 2);
+        assert_eq!(outer.flatten().len(), 2);
+
+        let json = outer.to_json();
+        assert_eq!(json["children"][0]["id"], "site_b_d");
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 6);
+
+        let graph = tsg.default_graph_mut().unwrap();
+        graph.add_site_groups(&sites)?;
+        assert!(
+            graph.add_site_groups(&sites).is_err(),
+            "sites are added once"
+        );
+        let mut buf = Vec::new();
+        tsg.to_writer(&mut buf)?;
+        let written = String::from_utf8(buf)?;
+        assert!(written.contains("U\tsite_b_d\tb c1 c2 d e2 e3 e4 e5\n"));
+        assert!(written.contains("A\tU\tsite_b_d\tparent:Z:site_a_e\n"));
+        assert!(written.contains("A\tU\tsite_a_e\tlevel:i:0\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_site_ids_are_unique() -> Result<()> {
+        // A group and a node already hold the IDs the sites would get
+        let content = format!(
+            "{}U\tsite_a_e\ta e\nN\tsite_b_d\tchr2:+:100-200\tr4:SO\n",
+            NESTED_TSG
+        );
+        let mut tsg = TSGraph::from_str(&content)?;
+        let graph = tsg.default_graph_mut().unwrap();
+        let sites = graph.decompose_sites()?;
+        assert_eq!(sites[0].id, "site_a_e_2");
+        assert_eq!(sites[0].children[0].id, "site_b_d_2");
+
+        graph.add_site_groups(&sites)?;
+        assert!(matches!(
+            &graph.groups[&BString::from("site_a_e")],
+            Group::Unordered { elements, .. } if elements.len() == 2
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompose_sites_without_bubbles() -> Result<()> {
+        let tsg = TSGraph::from_file("tests/data/test.tsg")?;
+        assert!(tsg.default_graph().unwrap().decompose_sites()?.is_empty());
+        Ok(())
+    }
+}
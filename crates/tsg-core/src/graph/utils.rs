@@ -1,8 +1,11 @@
 use anyhow::Result;
 use anyhow::anyhow;
+use bstr::{BStr, BString};
 use regex::Regex;
 use sha2::{Digest, Sha256};
 
+use super::{Attribute, Interval, Strand};
+
 /// Convert a string to a hash-based identifier using SHA-256.
 ///
 /// # Arguments
@@ -60,6 +63,153 @@ pub fn to_hash_identifier(input_string: &str, length: Option<usize>) -> Result<S
     Ok(result)
 }
 
+/// Split a TSG record into its fields
+///
+/// Records are tab-separated, which allows IDs and attribute values to contain spaces.
+/// Empty fields are kept, so a column left empty (such as the reads of a node without reads)
+/// stays in place. Lines without any tab fall back to whitespace separation for
+/// hand-written files, where runs of spaces form a single separator.
+///
+/// # Examples
+///
+/// ```
+/// use tsg_core::graph::split_fields;
+///
+/// assert_eq!(split_fields("E\te 1\tn1\tn2"), vec!["E", "e 1", "n1", "n2"]);
+/// assert_eq!(split_fields("N\tn1\tchr1:+:1-10\t\tACGT"), vec!["N", "n1", "chr1:+:1-10", "", "ACGT"]);
+/// assert_eq!(split_fields("E e1  n1 n2"), vec!["E", "e1", "n1", "n2"]);
+/// ```
+pub fn split_fields(line: &str) -> Vec<&str> {
+    if line.contains('\t') {
+        line.trim_end_matches(['\r', '\n'])
+            .split('\t')
+            .map(str::trim)
+            .collect()
+    } else {
+        line.split_whitespace().collect()
+    }
+}
+
+/// Score of a BED record, read from a numeric attribute
+///
+/// The value is rounded and clamped to the 0-1000 range of BED; a missing tag or a
+/// non-numeric value gives 0.
+pub(crate) fn bed_score<'a>(
+    attributes: impl IntoIterator<Item = &'a Attribute>,
+    tag: Option<&str>,
+) -> u16 {
+    let Some(tag) = tag else {
+        return 0;
+    };
+    attributes
+        .into_iter()
+        .find(|attr| attr.tag == tag)
+        .and_then(|attr| attr.as_float().ok())
+        .map_or(0, |value| value.round().clamp(0.0, 1000.0) as u16)
+}
+
+/// Format a BED12 record from 1-based, inclusive exons
+///
+/// Exons are sorted by position, overlapping exons are merged and all are converted to the
+/// 0-based, half-open coordinates of BED.
+/// The thick region spans the whole record.
+pub(crate) fn bed12_record(
+    chrom: &BStr,
+    name: &BStr,
+    score: u16,
+    strand: Strand,
+    exons: &[&Interval],
+) -> Result<BString> {
+    let mut blocks: Vec<(usize, usize)> = exons
+        .iter()
+        .map(|exon| (exon.start.saturating_sub(1), exon.end))
+        .collect();
+    if blocks.is_empty() {
+        return Err(anyhow!("{} has no exons", name));
+    }
+    blocks.sort_unstable();
+    // BED blocks may not overlap
+    blocks.dedup_by(|next, previous| {
+        if next.0 <= previous.1 {
+            previous.1 = previous.1.max(next.1);
+            true
+        } else {
+            false
+        }
+    });
+
+    let start = blocks[0].0;
+    let end = blocks.iter().map(|&(_, end)| end).max().unwrap_or(start);
+    let sizes: Vec<String> = blocks
+        .iter()
+        .map(|&(block_start, block_end)| (block_end - block_start).to_string())
+        .collect();
+    let starts: Vec<String> = blocks
+        .iter()
+        .map(|&(block_start, _)| (block_start - start).to_string())
+        .collect();
+
+    Ok(format!(
+        "{chrom}\t{start}\t{end}\t{name}\t{score}\t{strand}\t{start}\t{end}\t0\t{}\t{},\t{},",
+        blocks.len(),
+        sizes.join(","),
+        starts.join(",")
+    )
+    .into())
+}
+
+/// Percent-encode the characters that are reserved in GFF3 column 9
+pub(crate) fn gff3_escape(value: &[u8]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for &byte in value {
+        match byte {
+            b';' | b'=' | b'&' | b',' | b'%' | 0x00..=0x1f | 0x7f..=0xff => {
+                escaped.push_str(&format!("%{:02X}", byte));
+            }
+            _ => escaped.push(byte as char),
+        }
+    }
+    escaped
+}
+
+/// Percent-encode the characters with a special meaning in a VCF INFO value
+pub(crate) fn vcf_escape(value: &[u8]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for &byte in value {
+        match byte {
+            b':' | b';' | b'=' | b'%' | b',' | 0x00..=0x20 | 0x7f..=0xff => {
+                escaped.push_str(&format!("%{:02X}", byte));
+            }
+            _ => escaped.push(byte as char),
+        }
+    }
+    escaped
+}
+
+/// Format a GFF3 feature from its location and already escaped attributes
+pub(crate) fn gff3_record(
+    seqid: &BStr,
+    kind: &str,
+    start: usize,
+    end: usize,
+    strand: Strand,
+    attributes: &[(String, String)],
+) -> String {
+    let attributes: Vec<String> = attributes
+        .iter()
+        .map(|(tag, value)| format!("{}={}", tag, value))
+        .collect();
+    format!(
+        "{}\ttsg\t{}\t{}\t{}\t.\t{}\t.\t{}",
+        seqid,
+        kind,
+        start,
+        end,
+        strand,
+        attributes.join(";")
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,7 +1,26 @@
+mod bed;
+mod btsg;
+mod compression;
+mod dot;
 mod fa;
+mod gfa;
+mod gff;
 mod gtf;
+mod index;
+mod json;
+mod network;
+mod stream;
+mod svg;
 mod vcf;
 
+pub use bed::*;
+pub use btsg::*;
+pub use compression::*;
 pub use fa::*;
+pub use gfa::*;
+pub use gff::*;
 pub use gtf::*;
+pub use index::*;
+pub use json::*;
+pub use stream::*;
 pub use vcf::*;
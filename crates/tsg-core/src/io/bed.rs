@@ -0,0 +1,174 @@
+use crate::graph::{GraphSection, TSGraph, WriteOptions};
+use anyhow::Result;
+use std::io::Write;
+use tracing::warn;
+
+/// Elements written as BED records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BedMode {
+    /// One BED12 record per traversed path
+    #[default]
+    Paths,
+    /// One BED12 record per node
+    Nodes,
+    /// One BED6 record per edge, spanning its junction
+    Edges,
+}
+
+pub fn to_bed<W: Write>(
+    tsg_graph: &TSGraph,
+    writer: &mut W,
+    mode: BedMode,
+    score_attribute: Option<&str>,
+) -> Result<()> {
+    to_bed_with_options(
+        tsg_graph,
+        writer,
+        mode,
+        score_attribute,
+        &WriteOptions::default(),
+    )
+}
+
+/// Write the paths, nodes or edges of all graphs as BED records, in the order given by `options`
+pub fn to_bed_with_options<W: Write>(
+    tsg_graph: &TSGraph,
+    writer: &mut W,
+    mode: BedMode,
+    score_attribute: Option<&str>,
+    options: &WriteOptions,
+) -> Result<()> {
+    for graph in tsg_graph.ordered_graphs(options.ordering) {
+        graph_to_bed_with_options(graph, writer, mode, score_attribute, options)?;
+    }
+    Ok(())
+}
+
+/// Write the paths, nodes or edges of a single graph section as BED records
+pub fn graph_to_bed<W: Write>(
+    graph: &GraphSection,
+    writer: &mut W,
+    mode: BedMode,
+    score_attribute: Option<&str>,
+) -> Result<()> {
+    graph_to_bed_with_options(
+        graph,
+        writer,
+        mode,
+        score_attribute,
+        &WriteOptions::default(),
+    )
+}
+
+/// Write the paths, nodes or edges of a single graph section as BED records, in the order given by `options`
+///
+/// Paths spanning several references and edges joining two references have no BED
+/// representation and are skipped with a warning.
+pub fn graph_to_bed_with_options<W: Write>(
+    graph: &GraphSection,
+    writer: &mut W,
+    mode: BedMode,
+    score_attribute: Option<&str>,
+    options: &WriteOptions,
+) -> Result<()> {
+    let inner = graph.inner_graph();
+    match mode {
+        BedMode::Paths => {
+            for path in graph.traverse_with_ordering(options.ordering)? {
+                match path.to_bed12(score_attribute) {
+                    Ok(record) => writeln!(writer, "{}", record)?,
+                    Err(e) => warn!("Skipping path in graph {}: {}", graph.id, e),
+                }
+            }
+        }
+        BedMode::Nodes => {
+            for idx in graph.ordered_node_indices(options.ordering) {
+                let node = &inner[idx];
+                if node.exons.is_empty() {
+                    warn!("Skipping node {} without exons", node.id);
+                    continue;
+                }
+                writeln!(writer, "{}", node.to_bed12(score_attribute)?)?;
+            }
+        }
+        BedMode::Edges => {
+            for idx in graph.ordered_edge_indices(options.ordering) {
+                let (source, _) = inner.edge_endpoints(idx).unwrap();
+                match inner[idx].to_bed6(inner[source].strand, score_attribute) {
+                    Ok(record) => writeln!(writer, "{}", record)?,
+                    Err(e) => warn!("Skipping edge in graph {}: {}", graph.id, e),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn bed(tsg_graph: &TSGraph, mode: BedMode, score_attribute: Option<&str>) -> String {
+        let mut buf = Vec::new();
+        to_bed(tsg_graph, &mut buf, mode, score_attribute).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_to_bed() {
+        let tsg_graph = TSGraph::from_file("tests/data/test.tsg").unwrap();
+
+        let nodes = bed(&tsg_graph, BedMode::Nodes, Some("expression"));
+        assert_eq!(
+            nodes.lines().next().unwrap(),
+            "chr1\t999\t1700\tn1\t11\t+\t999\t1700\t0\t2\t201,201,\t0,500,"
+        );
+        assert_eq!(nodes.lines().count(), 5);
+
+        let paths = bed(&tsg_graph, BedMode::Paths, None);
+        for record in paths.lines() {
+            let fields: Vec<&str> = record.split('\t').collect();
+            assert_eq!(fields.len(), 12);
+            let blocks: usize = fields[9].parse().unwrap();
+            assert_eq!(fields[10].split_terminator(',').count(), blocks);
+            assert_eq!(fields[11].split_terminator(',').count(), blocks);
+        }
+
+        let edges = bed(&tsg_graph, BedMode::Edges, None);
+        assert!(edges.contains("chr1\t1700\t1999\te1\t0\t+"));
+    }
+
+    #[test]
+    fn test_to_bed_reverse_strand_and_references() -> Result<()> {
+        let tsg_graph = TSGraph::from_str(
+            "N\ta\tchr1:-:500-600\tr1:SO,r2:SO\n\
+             N\tb\tchr1:-:100-200\tr1:SI\n\
+             N\tc\tchr2:+:100-200\tr2:SI\n\
+             E\te1\ta\tb\tchr1,chr1,500,200,splice\n\
+             E\te2\ta\tc\tchr1,chr2,500,100,TRA\n",
+        )?;
+
+        // Reverse-strand junctions span the bases between the lower and higher breakpoint
+        let edges = bed(&tsg_graph, BedMode::Edges, None);
+        assert_eq!(edges, "chr1\t200\t499\te1\t0\t-\n");
+
+        // Paths across references are skipped and report both references
+        let paths = bed(&tsg_graph, BedMode::Paths, None);
+        let fields: Vec<&str> = paths.trim_end().split('\t').collect();
+        assert_eq!(paths.lines().count(), 1);
+        assert_eq!(&fields[..3], ["chr1", "99", "600"]);
+        assert_eq!(
+            &fields[5..],
+            ["-", "99", "600", "0", "2", "101,101,", "0,400,"]
+        );
+        let graph = tsg_graph.default_graph().unwrap();
+        let error = graph
+            .traverse()?
+            .into_iter()
+            .find_map(|path| path.to_bed12(None).err())
+            .unwrap();
+        assert!(error.to_string().ends_with("spans chr1 and chr2"));
+        Ok(())
+    }
+}
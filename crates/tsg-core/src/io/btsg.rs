@@ -0,0 +1,1021 @@
+//! Binary TSG (BTSG) encoding
+//!
+//! Layout of a BTSG file, all integers little-endian:
+//!
+//! ```text
+//! magic "BTSG" | version u16 | flags u16
+//! block: string table   (count u32, then len u32 + bytes per string)
+//! block: globals        (headers, comments, inter-graph links, graph count)
+//! block: graph section  (one per graph)
+//! offset table          (count u32, then id u32 + offset u64 + length u64 per graph)
+//! offset of the offset table u64 | magic "BTSG"
+//! ```
+//!
+//! Every block is stored as `compressed length u64 | raw length u64 | zstd payload`.
+//! IDs, reference names, read IDs and attribute tags and values are stored once in
+//! the string table and referenced by their u32 index.
+//!
+//! The offset table lets [`BtsgReader`] decode a single graph section without reading the others.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use ahash::{HashMap, HashMapExt};
+use anyhow::{Context, Result, anyhow, bail};
+use bstr::{BString, ByteSlice};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use petgraph::graph::NodeIndex;
+use rayon::prelude::*;
+
+use crate::graph::{
+    Attribute, EdgeData, Exons, GraphSection, Group, Header, InterGraphLink, Interval, NodeData,
+    OrderingPolicy, Orientation, OrientedElement, ReadData, ReadIdentity, Strand,
+    StructuralVariant, TSGraph, TsgError,
+};
+
+pub const BTSG_MAGIC: &[u8; 4] = b"BTSG";
+pub const BTSG_VERSION: u16 = 1;
+
+/// Extension used for BTSG files
+pub const BTSG_EXTENSION: &str = "btsg";
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Size of the fixed header: magic, version and flags
+const HEADER_LEN: u64 = 8;
+/// Size of the footer: offset of the offset table and the closing magic
+const FOOTER_LEN: u64 = 12;
+/// Size of one offset table entry: graph ID, block offset and block length
+const TABLE_ENTRY_LEN: u64 = 20;
+
+/// Whether a byte stream starts with the BTSG magic
+pub fn is_btsg(bytes: &[u8]) -> bool {
+    bytes.starts_with(BTSG_MAGIC)
+}
+
+/// Deduplicated strings referenced by index from the encoded blocks
+#[derive(Default)]
+struct StringTable {
+    index: HashMap<BString, u32>,
+    strings: Vec<BString>,
+}
+
+impl StringTable {
+    fn intern(&mut self, s: &[u8]) -> u32 {
+        if let Some(&idx) = self.index.get(s.as_bstr()) {
+            return idx;
+        }
+        let idx = self.strings.len() as u32;
+        self.strings.push(s.into());
+        self.index.insert(s.into(), idx);
+        idx
+    }
+
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        buf.write_u32::<LittleEndian>(self.strings.len() as u32)?;
+        for s in &self.strings {
+            buf.write_u32::<LittleEndian>(s.len() as u32)?;
+            buf.write_all(s)?;
+        }
+        Ok(buf)
+    }
+
+    fn decode(buf: &[u8]) -> Result<Vec<BString>> {
+        let mut decoder = BlockDecoder::new(buf, &[]);
+        (0..decoder.count()?)
+            .map(|_| {
+                let len = decoder.len()?;
+                Ok(decoder.bytes(len)?.into())
+            })
+            .collect()
+    }
+}
+
+/// Writes records into an uncompressed block, interning strings on the way
+struct BlockEncoder<'a> {
+    buf: Vec<u8>,
+    strings: &'a mut StringTable,
+}
+
+impl<'a> BlockEncoder<'a> {
+    fn new(strings: &'a mut StringTable) -> Self {
+        Self {
+            buf: Vec::new(),
+            strings,
+        }
+    }
+
+    fn u8(&mut self, value: u8) -> Result<()> {
+        Ok(self.buf.write_u8(value)?)
+    }
+
+    fn u32(&mut self, value: u32) -> Result<()> {
+        Ok(self.buf.write_u32::<LittleEndian>(value)?)
+    }
+
+    fn u64(&mut self, value: u64) -> Result<()> {
+        Ok(self.buf.write_u64::<LittleEndian>(value)?)
+    }
+
+    fn len(&mut self, len: usize) -> Result<()> {
+        self.u32(u32::try_from(len).context("too many elements for a BTSG block")?)
+    }
+
+    fn str(&mut self, s: &[u8]) -> Result<()> {
+        let idx = self.strings.intern(s);
+        self.u32(idx)
+    }
+
+    fn attributes<'b>(
+        &mut self,
+        attributes: impl IntoIterator<Item = &'b Attribute>,
+    ) -> Result<()> {
+        let mut attributes: Vec<_> = attributes.into_iter().collect();
+        attributes.sort_by(|a, b| a.tag.cmp(&b.tag));
+        self.len(attributes.len())?;
+        for attr in attributes {
+            self.str(&attr.tag)?;
+            self.u32(attr.attribute_type as u32)?;
+            self.str(&attr.value)?;
+        }
+        Ok(())
+    }
+
+    fn headers(&mut self, headers: &[Header]) -> Result<()> {
+        self.len(headers.len())?;
+        for header in headers {
+            self.str(&header.tag)?;
+            self.str(&header.value)?;
+        }
+        Ok(())
+    }
+
+    fn comments(&mut self, comments: &[BString]) -> Result<()> {
+        self.len(comments.len())?;
+        for comment in comments {
+            self.str(comment)?;
+        }
+        Ok(())
+    }
+
+    fn link(&mut self, link: &InterGraphLink) -> Result<()> {
+        self.str(&link.id)?;
+        self.str(&link.source_graph)?;
+        self.str(&link.source_element)?;
+        self.str(&link.target_graph)?;
+        self.str(&link.target_element)?;
+        self.str(&link.link_type)?;
+        self.attributes(link.attributes.values())
+    }
+
+    fn node(&mut self, node: &NodeData) -> Result<()> {
+        self.str(&node.id)?;
+        self.str(&node.reference_id)?;
+        self.u8(match node.strand {
+            Strand::Forward => 0,
+            Strand::Reverse => 1,
+        })?;
+
+        self.len(node.exons.exons.len())?;
+        for exon in &node.exons.exons {
+            self.u64(exon.start as u64)?;
+            self.u64(exon.end as u64)?;
+        }
+
+        self.len(node.reads.len())?;
+        for read in &node.reads {
+            self.str(&read.id)?;
+            self.u8(match read.identity {
+                ReadIdentity::SO => 0,
+                ReadIdentity::IN => 1,
+                ReadIdentity::SI => 2,
+            })?;
+        }
+
+        // Sequences are rarely shared, so they are stored inline
+        match &node.sequence {
+            Some(sequence) => {
+                self.u8(1)?;
+                self.len(sequence.len())?;
+                self.buf.write_all(sequence)?;
+            }
+            None => self.u8(0)?,
+        }
+
+        self.attributes(node.attributes.values())
+    }
+
+    fn edge(&mut self, edge: &EdgeData, source: usize, sink: usize) -> Result<()> {
+        self.str(&edge.id)?;
+        self.len(source)?;
+        self.len(sink)?;
+        self.str(&edge.sv.reference_name1)?;
+        self.str(&edge.sv.reference_name2)?;
+        self.u64(edge.sv.breakpoint1 as u64)?;
+        self.u64(edge.sv.breakpoint2 as u64)?;
+        self.str(&edge.sv.sv_type)?;
+        self.attributes(edge.attributes.values())
+    }
+
+    fn group(&mut self, group: &Group) -> Result<()> {
+        match group {
+            Group::Unordered {
+                id,
+                elements,
+                attributes,
+            }
+            | Group::Chain {
+                id,
+                elements,
+                attributes,
+            } => {
+                self.u8(if matches!(group, Group::Unordered { .. }) {
+                    0
+                } else {
+                    2
+                })?;
+                self.str(id)?;
+                self.len(elements.len())?;
+                for element in elements {
+                    self.str(element)?;
+                }
+                self.attributes(attributes.values())
+            }
+            Group::Ordered {
+                id,
+                elements,
+                attributes,
+            } => {
+                self.u8(1)?;
+                self.str(id)?;
+                self.len(elements.len())?;
+                for element in elements {
+                    self.str(&element.id)?;
+                    self.u8(match element.orientation {
+                        None => 0,
+                        Some(Orientation::Forward) => 1,
+                        Some(Orientation::Reverse) => 2,
+                    })?;
+                }
+                self.attributes(attributes.values())
+            }
+        }
+    }
+
+    fn graph(&mut self, graph: &GraphSection) -> Result<()> {
+        let inner = graph.inner_graph();
+
+        self.str(&graph.id)?;
+        self.attributes(graph.attributes.values())?;
+        self.comments(&graph.comments)?;
+
+        // Nodes and edges are stored in insertion order, which decoding restores; edges
+        // refer to their nodes by position
+        let nodes = graph.ordered_node_indices(OrderingPolicy::Insertion);
+        let positions: HashMap<NodeIndex, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(position, &idx)| (idx, position))
+            .collect();
+        self.len(nodes.len())?;
+        for idx in nodes {
+            self.node(&inner[idx])?;
+        }
+
+        let edges = graph.ordered_edge_indices(OrderingPolicy::Insertion);
+        self.len(edges.len())?;
+        for idx in edges {
+            let (source, sink) = inner
+                .edge_endpoints(idx)
+                .ok_or_else(|| anyhow!("Edge index {} out of range", idx.index()))?;
+            self.edge(&inner[idx], positions[&source], positions[&sink])?;
+        }
+
+        let groups = graph.ordered_groups(OrderingPolicy::Insertion);
+        self.len(groups.len())?;
+        for (_, group) in groups {
+            self.group(group)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads records from an uncompressed block
+struct BlockDecoder<'a> {
+    buf: &'a [u8],
+    strings: &'a [BString],
+}
+
+impl<'a> BlockDecoder<'a> {
+    fn new(buf: &'a [u8], strings: &'a [BString]) -> Self {
+        Self { buf, strings }
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.buf.read_u8()?)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(self.buf.read_u32::<LittleEndian>()?)
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(self.buf.read_u64::<LittleEndian>()?)
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        Ok(self.u32()? as usize)
+    }
+
+    /// Read an element count, which cannot exceed the bytes left in the block
+    fn count(&mut self) -> Result<usize> {
+        let count = self.len()?;
+        if count > self.buf.len() {
+            return Err(corrupt(format!("count {} exceeds the block size", count)).into());
+        }
+        Ok(count)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        if len > self.buf.len() {
+            return Err(corrupt(format!("length {} exceeds the block size", len)).into());
+        }
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes.to_vec())
+    }
+
+    fn str(&mut self) -> Result<BString> {
+        let idx = self.len()?;
+        self.strings
+            .get(idx)
+            .cloned()
+            .ok_or_else(|| anyhow!("String index {} out of range", idx))
+    }
+
+    fn attributes(&mut self) -> Result<HashMap<BString, Attribute>> {
+        let count = self.count()?;
+        let mut attributes = HashMap::with_capacity(count);
+        for _ in 0..count {
+            let tag = self.str()?;
+            let attribute_type =
+                char::from_u32(self.u32()?).ok_or_else(|| anyhow!("Invalid attribute type"))?;
+            let value = self.str()?;
+            attributes.insert(
+                tag.clone(),
+                Attribute {
+                    tag,
+                    attribute_type,
+                    value,
+                },
+            );
+        }
+        Ok(attributes)
+    }
+
+    fn headers(&mut self) -> Result<Vec<Header>> {
+        (0..self.len()?)
+            .map(|_| {
+                Ok(Header {
+                    tag: self.str()?,
+                    value: self.str()?,
+                })
+            })
+            .collect()
+    }
+
+    fn comments(&mut self) -> Result<Vec<BString>> {
+        (0..self.count()?).map(|_| self.str()).collect()
+    }
+
+    fn link(&mut self) -> Result<InterGraphLink> {
+        Ok(InterGraphLink {
+            id: self.str()?,
+            source_graph: self.str()?,
+            source_element: self.str()?,
+            target_graph: self.str()?,
+            target_element: self.str()?,
+            link_type: self.str()?,
+            attributes: self.attributes()?,
+        })
+    }
+
+    fn node(&mut self) -> Result<NodeData> {
+        let id = self.str()?;
+        let reference_id = self.str()?;
+        let strand = match self.u8()? {
+            0 => Strand::Forward,
+            1 => Strand::Reverse,
+            value => bail!("Invalid strand code {}", value),
+        };
+
+        let exons = (0..self.len()?)
+            .map(|_| {
+                Ok(Interval {
+                    start: self.u64()? as usize,
+                    end: self.u64()? as usize,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let reads = (0..self.len()?)
+            .map(|_| {
+                let id = self.str()?;
+                let identity = match self.u8()? {
+                    0 => ReadIdentity::SO,
+                    1 => ReadIdentity::IN,
+                    2 => ReadIdentity::SI,
+                    value => bail!("Invalid read identity code {}", value),
+                };
+                Ok(ReadData { id, identity })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let sequence = match self.u8()? {
+            0 => None,
+            _ => {
+                let len = self.len()?;
+                Some(self.bytes(len)?.into())
+            }
+        };
+
+        Ok(NodeData {
+            id,
+            reference_id,
+            strand,
+            exons: Exons { exons },
+            reads,
+            sequence,
+            attributes: self.attributes()?,
+        })
+    }
+
+    fn edge(&mut self) -> Result<(EdgeData, usize, usize)> {
+        let id = self.str()?;
+        let source = self.len()?;
+        let sink = self.len()?;
+        let sv = StructuralVariant {
+            reference_name1: self.str()?,
+            reference_name2: self.str()?,
+            breakpoint1: self.u64()? as usize,
+            breakpoint2: self.u64()? as usize,
+            sv_type: self.str()?,
+        };
+        let attributes = self.attributes()?;
+        Ok((EdgeData { id, sv, attributes }, source, sink))
+    }
+
+    fn group(&mut self) -> Result<(BString, Group)> {
+        let kind = self.u8()?;
+        let id = self.str()?;
+        let key = id.clone();
+        let count = self.len()?;
+        let group = match kind {
+            0 | 2 => {
+                let elements = (0..count).map(|_| self.str()).collect::<Result<Vec<_>>>()?;
+                let attributes = self.attributes()?;
+                if kind == 0 {
+                    Group::Unordered {
+                        id,
+                        elements,
+                        attributes,
+                    }
+                } else {
+                    Group::Chain {
+                        id,
+                        elements,
+                        attributes,
+                    }
+                }
+            }
+            1 => {
+                let elements = (0..count)
+                    .map(|_| {
+                        let id = self.str()?;
+                        let orientation = match self.u8()? {
+                            0 => None,
+                            1 => Some(Orientation::Forward),
+                            2 => Some(Orientation::Reverse),
+                            value => bail!("Invalid orientation code {}", value),
+                        };
+                        Ok(OrientedElement { id, orientation })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Group::Ordered {
+                    id,
+                    elements,
+                    attributes: self.attributes()?,
+                }
+            }
+            value => bail!("Invalid group kind {}", value),
+        };
+        Ok((key, group))
+    }
+
+    fn graph(&mut self) -> Result<GraphSection> {
+        let mut graph = GraphSection::new(self.str()?);
+        graph.attributes = self.attributes()?;
+        graph.comments = self.comments()?;
+
+        let node_count = self.count()?;
+        let mut node_ids = Vec::with_capacity(node_count);
+        for _ in 0..node_count {
+            let node = self.node()?;
+            node_ids.push(node.id.clone());
+            graph.add_node(node)?;
+        }
+
+        for _ in 0..self.len()? {
+            let (edge, source, sink) = self.edge()?;
+            let (Some(source), Some(sink)) = (node_ids.get(source), node_ids.get(sink)) else {
+                bail!("Edge {} refers to a node out of range", edge.id);
+            };
+            graph.add_edge(source.as_bstr(), sink.as_bstr(), edge)?;
+        }
+
+        for _ in 0..self.len()? {
+            let (id, group) = self.group()?;
+            graph.add_group(id, group);
+        }
+        Ok(graph)
+    }
+}
+
+fn compress_block(raw: &[u8]) -> Result<Vec<u8>> {
+    let compressed = zstd::bulk::compress(raw, ZSTD_LEVEL)?;
+    let mut block = Vec::with_capacity(compressed.len() + 16);
+    block.write_u64::<LittleEndian>(compressed.len() as u64)?;
+    block.write_u64::<LittleEndian>(raw.len() as u64)?;
+    block.extend_from_slice(&compressed);
+    Ok(block)
+}
+
+/// Error for a BTSG file whose content contradicts its own lengths or offsets
+fn corrupt(reason: impl std::fmt::Display) -> TsgError {
+    TsgError::CorruptBinary {
+        reason: reason.to_string(),
+    }
+}
+
+/// Read one block, returning its compressed payload and raw length
+///
+/// Lengths come from the file, so nothing is allocated for them up front:
+/// a length larger than the data actually present is reported as a truncated block.
+fn read_block<R: Read>(reader: &mut R) -> Result<(Vec<u8>, u64)> {
+    let compressed_len = reader.read_u64::<LittleEndian>()?;
+    let raw_len = reader.read_u64::<LittleEndian>()?;
+    let mut compressed = Vec::new();
+    reader.take(compressed_len).read_to_end(&mut compressed)?;
+    if compressed.len() as u64 != compressed_len {
+        return Err(corrupt(format!(
+            "block of {} bytes is truncated to {}",
+            compressed_len,
+            compressed.len()
+        ))
+        .into());
+    }
+    Ok((compressed, raw_len))
+}
+
+fn decompress_block(compressed: &[u8], raw_len: u64) -> Result<Vec<u8>> {
+    // Read one byte past the expected length to detect blocks that are too long
+    let mut raw = Vec::new();
+    zstd::Decoder::new(compressed)?
+        .take(raw_len.saturating_add(1))
+        .read_to_end(&mut raw)?;
+    if raw.len() as u64 != raw_len {
+        return Err(corrupt(format!(
+            "block should hold {} bytes, found {}",
+            raw_len,
+            raw.len()
+        ))
+        .into());
+    }
+    Ok(raw)
+}
+
+/// Strings and global records stored before the graph sections
+struct Preamble {
+    strings: Vec<BString>,
+    headers: Vec<Header>,
+    comments: Vec<BString>,
+    links: Vec<InterGraphLink>,
+    graph_count: usize,
+}
+
+impl Preamble {
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != BTSG_MAGIC {
+            bail!("Not a BTSG file");
+        }
+        let version = reader.read_u16::<LittleEndian>()?;
+        if version != BTSG_VERSION {
+            bail!("Unsupported BTSG version: {}", version);
+        }
+        let _flags = reader.read_u16::<LittleEndian>()?;
+
+        let (compressed, raw_len) = read_block(reader)?;
+        let strings = StringTable::decode(&decompress_block(&compressed, raw_len)?)?;
+
+        let (compressed, raw_len) = read_block(reader)?;
+        let globals = decompress_block(&compressed, raw_len)?;
+        let mut decoder = BlockDecoder::new(&globals, &strings);
+        let headers = decoder.headers()?;
+        let comments = decoder.comments()?;
+        let links = (0..decoder.count()?)
+            .map(|_| decoder.link())
+            .collect::<Result<Vec<_>>>()?;
+        let graph_count = decoder.len()?;
+
+        Ok(Self {
+            strings,
+            headers,
+            comments,
+            links,
+            graph_count,
+        })
+    }
+}
+
+/// Read the offset table: the graph ID and block range of every section
+///
+/// `table_len` is the number of bytes available to the table, bounding its entry count.
+fn read_offset_table<R: Read>(
+    reader: &mut R,
+    strings: &[BString],
+    table_len: u64,
+) -> Result<Vec<(BString, u64, u64)>> {
+    let count = reader.read_u32::<LittleEndian>()? as u64;
+    if count * TABLE_ENTRY_LEN > table_len.saturating_sub(4) {
+        return Err(corrupt(format!("offset table of {} entries is truncated", count)).into());
+    }
+    (0..count)
+        .map(|_| {
+            let id = reader.read_u32::<LittleEndian>()? as usize;
+            let id = strings
+                .get(id)
+                .cloned()
+                .ok_or_else(|| anyhow!("String index {} out of range", id))?;
+            let offset = reader.read_u64::<LittleEndian>()?;
+            let length = reader.read_u64::<LittleEndian>()?;
+            Ok((id, offset, length))
+        })
+        .collect()
+}
+
+impl TSGraph {
+    /// Write the graph in the binary BTSG format
+    ///
+    /// Graph sections and groups keep their insertion order, and sections are compressed in parallel.
+    pub fn to_binary<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut strings = StringTable::default();
+
+        let graphs = self.ordered_graphs(OrderingPolicy::Insertion);
+
+        let mut globals = BlockEncoder::new(&mut strings);
+        globals.headers(&self.headers)?;
+        globals.comments(&self.comments)?;
+        globals.len(self.links.len())?;
+        for link in &self.links {
+            globals.link(link)?;
+        }
+        globals.len(graphs.len())?;
+        let globals = globals.buf;
+
+        let raw_graphs = graphs
+            .iter()
+            .map(|graph| {
+                let mut encoder = BlockEncoder::new(&mut strings);
+                encoder.graph(graph)?;
+                Ok(encoder.buf)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let graph_blocks = raw_graphs
+            .par_iter()
+            .map(|raw| compress_block(raw))
+            .collect::<Result<Vec<_>>>()?;
+
+        let graph_ids: Vec<u32> = graphs
+            .iter()
+            .map(|graph| strings.intern(&graph.id))
+            .collect();
+
+        let mut offset = 0u64;
+        let mut write = |writer: &mut W, bytes: &[u8]| -> Result<u64> {
+            writer.write_all(bytes)?;
+            let start = offset;
+            offset += bytes.len() as u64;
+            Ok(start)
+        };
+
+        let mut header = Vec::with_capacity(8);
+        header.extend_from_slice(BTSG_MAGIC);
+        header.write_u16::<LittleEndian>(BTSG_VERSION)?;
+        header.write_u16::<LittleEndian>(0)?;
+        write(writer, &header)?;
+        write(writer, &compress_block(&strings.encode()?)?)?;
+        write(writer, &compress_block(&globals)?)?;
+
+        let mut offsets = Vec::with_capacity(graph_blocks.len());
+        for block in &graph_blocks {
+            offsets.push((write(writer, block)?, block.len() as u64));
+        }
+
+        let mut table = Vec::new();
+        table.write_u32::<LittleEndian>(offsets.len() as u32)?;
+        for (id, (block_offset, length)) in graph_ids.iter().zip(&offsets) {
+            table.write_u32::<LittleEndian>(*id)?;
+            table.write_u64::<LittleEndian>(*block_offset)?;
+            table.write_u64::<LittleEndian>(*length)?;
+        }
+        let table_offset = write(writer, &table)?;
+
+        let mut footer = Vec::with_capacity(12);
+        footer.write_u64::<LittleEndian>(table_offset)?;
+        footer.extend_from_slice(BTSG_MAGIC);
+        write(writer, &footer)?;
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read a graph written with [`TSGraph::to_binary`]
+    ///
+    /// Graph sections are decompressed and decoded in parallel.
+    ///
+    /// The file is read sequentially, so any reader works; the offset table at the end
+    /// is checked against the sections read. Use [`BtsgReader`] to decode single sections.
+    pub fn from_binary<R: Read>(mut reader: R) -> Result<Self> {
+        let preamble = Preamble::read(&mut reader)?;
+        let strings = &preamble.strings;
+
+        let blocks = (0..preamble.graph_count)
+            .map(|_| read_block(&mut reader))
+            .collect::<Result<Vec<_>>>()?;
+
+        let graphs = blocks
+            .par_iter()
+            .map(|(compressed, raw_len)| {
+                let raw = decompress_block(compressed, *raw_len)?;
+                BlockDecoder::new(&raw, strings).graph()
+            })
+            .collect::<Result<Vec<_>>>()
+            .context("failed to decode BTSG graph section")?;
+
+        let table = read_offset_table(&mut reader, strings, u64::MAX)?;
+        let table_ids = table.iter().map(|(id, _, _)| id);
+        if !table_ids.eq(graphs.iter().map(|graph| &graph.id)) {
+            return Err(corrupt("offset table does not match the graph sections").into());
+        }
+
+        let mut tsgraph = TSGraph::default();
+        tsgraph.headers = preamble.headers;
+        tsgraph.comments = preamble.comments;
+        for graph in graphs {
+            tsgraph.add_graph(graph);
+        }
+        tsgraph.links = preamble.links;
+        Ok(tsgraph)
+    }
+}
+
+/// Reader that uses the offset table of a BTSG file to decode single graph sections
+///
+/// Headers and links are read when the reader is created; graph sections are only
+/// decompressed when requested with [`BtsgReader::read_graph`].
+pub struct BtsgReader<R> {
+    inner: R,
+    strings: Vec<BString>,
+    headers: Vec<Header>,
+    comments: Vec<BString>,
+    links: Vec<InterGraphLink>,
+    offsets: Vec<(BString, u64, u64)>,
+}
+
+impl BtsgReader<BufReader<File>> {
+    /// Open a BTSG file for random access
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> BtsgReader<R> {
+    pub fn new(mut inner: R) -> Result<Self> {
+        let preamble = Preamble::read(&mut inner)?;
+        let data_start = inner.stream_position()?;
+
+        let file_len = inner.seek(SeekFrom::End(0))?;
+        if file_len < HEADER_LEN + FOOTER_LEN {
+            return Err(corrupt("file is too short for a footer").into());
+        }
+        inner.seek(SeekFrom::Start(file_len - FOOTER_LEN))?;
+        let table_offset = inner.read_u64::<LittleEndian>()?;
+        let mut magic = [0; 4];
+        inner.read_exact(&mut magic)?;
+        if &magic != BTSG_MAGIC {
+            return Err(corrupt("missing closing magic").into());
+        }
+        let table_end = file_len - FOOTER_LEN;
+        if table_offset < data_start || table_offset > table_end {
+            return Err(
+                corrupt(format!("offset table at {} is out of range", table_offset)).into(),
+            );
+        }
+
+        inner.seek(SeekFrom::Start(table_offset))?;
+        let offsets = read_offset_table(&mut inner, &preamble.strings, table_end - table_offset)?;
+        if offsets.len() != preamble.graph_count {
+            return Err(corrupt(format!(
+                "offset table lists {} graphs, expected {}",
+                offsets.len(),
+                preamble.graph_count
+            ))
+            .into());
+        }
+        for (id, offset, length) in &offsets {
+            if *offset < data_start || offset.saturating_add(*length) > table_offset {
+                return Err(corrupt(format!("section of graph {} is out of range", id)).into());
+            }
+        }
+
+        Ok(Self {
+            inner,
+            strings: preamble.strings,
+            headers: preamble.headers,
+            comments: preamble.comments,
+            links: preamble.links,
+            offsets,
+        })
+    }
+
+    pub fn headers(&self) -> &[Header] {
+        &self.headers
+    }
+
+    /// Comment lines written before the first graph section
+    pub fn comments(&self) -> &[BString] {
+        &self.comments
+    }
+
+    pub fn links(&self) -> &[InterGraphLink] {
+        &self.links
+    }
+
+    /// IDs of all graph sections in file order
+    pub fn graph_ids(&self) -> impl Iterator<Item = &BString> {
+        self.offsets.iter().map(|(id, _, _)| id)
+    }
+
+    /// Seek to a graph section and decode only that section
+    pub fn read_graph(&mut self, id: &str) -> Result<GraphSection> {
+        let (_, offset, length) = self
+            .offsets
+            .iter()
+            .find(|(graph_id, _, _)| graph_id == id)
+            .ok_or_else(|| anyhow!("Graph with ID {} not found", id))?;
+        self.inner.seek(SeekFrom::Start(*offset))?;
+        let (compressed, raw_len) = read_block(&mut (&mut self.inner).take(*length))?;
+        let raw = decompress_block(&compressed, raw_len)?;
+        BlockDecoder::new(&raw, &self.strings)
+            .graph()
+            .with_context(|| format!("failed to decode BTSG graph section {}", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::WriteOptions;
+    use std::io;
+    use std::str::FromStr;
+
+    fn text(tsg: &TSGraph) -> Result<String> {
+        let mut buf = Vec::new();
+        tsg.to_writer(&mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+
+    #[test]
+    fn test_binary_roundtrip() -> Result<()> {
+        for file in [
+            "tests/data/test.tsg",
+            "tests/data/test_mul.tsg",
+            "tests/data/AR_in_40_2.tsg",
+            "tests/data/sampling_46.tsg",
+        ] {
+            let tsg = TSGraph::from_file(file)?;
+
+            let mut buf = Vec::new();
+            tsg.to_binary(&mut buf)?;
+            assert!(is_btsg(&buf));
+
+            let decoded = TSGraph::from_binary(buf.as_slice())?;
+            assert_eq!(decoded.graphs.len(), tsg.graphs.len());
+            assert_eq!(text(&decoded)?, text(&tsg)?, "{}", file);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_roundtrip_keeps_insertion_order() -> Result<()> {
+        let mut tsg = TSGraph::from_str(
+            "N\ta\tchr1:+:100-200\tr1:SO\n\
+             N\tb\tchr1:+:300-400\tr1:IN\n\
+             N\tc\tchr1:+:500-600\tr1:IN\n\
+             N\td\tchr1:+:700-800\tr1:SI\n\
+             E\te1\ta\tb\tchr1,chr1,200,300,splice\n\
+             E\te2\tb\tc\tchr1,chr1,400,500,splice\n\
+             E\te3\tc\td\tchr1,chr1,600,700,splice\n",
+        )?;
+        // The removal moves d and e3 to lower petgraph indices
+        tsg.default_graph_mut().unwrap().remove_node("a");
+
+        let mut buf = Vec::new();
+        tsg.to_binary(&mut buf)?;
+        let decoded = TSGraph::from_binary(buf.as_slice())?;
+
+        let insertion_text = |tsg: &TSGraph| -> Result<String> {
+            let mut buf = Vec::new();
+            tsg.to_writer_with_options(&mut buf, &WriteOptions::insertion())?;
+            Ok(String::from_utf8(buf)?)
+        };
+        assert_eq!(insertion_text(&decoded)?, insertion_text(&tsg)?);
+        let edge_ids: Vec<_> = decoded
+            .default_graph()
+            .unwrap()
+            .edge_indices()
+            .keys()
+            .collect();
+        assert_eq!(edge_ids, ["e2", "e3"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_btsg_reader() -> Result<()> {
+        let tsg = TSGraph::from_file("tests/data/test_mul.tsg")?;
+        let mut buf = Vec::new();
+        tsg.to_binary(&mut buf)?;
+
+        let mut reader = BtsgReader::new(io::Cursor::new(buf))?;
+        assert_eq!(reader.headers(), tsg.headers.as_slice());
+        assert_eq!(reader.links().len(), 1);
+        let ids: Vec<_> = reader.graph_ids().map(|id| id.to_string()).collect();
+        assert_eq!(ids, vec!["gene_a", "gene_b"]);
+
+        let gene_b = reader.read_graph("gene_b")?;
+        let expected = tsg.graph("gene_b").unwrap();
+        assert_eq!(gene_b.nodes().len(), expected.nodes().len());
+        assert_eq!(gene_b.edges().len(), expected.edges().len());
+        assert!(reader.read_graph("gene_c").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_rejects_corrupt_lengths() -> Result<()> {
+        let tsg = TSGraph::from_file("tests/data/test_mul.tsg")?;
+        let mut buf = Vec::new();
+        tsg.to_binary(&mut buf)?;
+
+        // Compressed length of the string table block
+        let mut corrupted = buf.clone();
+        corrupted[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        let err = TSGraph::from_binary(corrupted.as_slice()).unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<TsgError>(),
+                Some(TsgError::CorruptBinary { .. })
+            ),
+            "{}",
+            err
+        );
+
+        // Raw length of the string table block
+        let mut corrupted = buf.clone();
+        corrupted[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        let err = TSGraph::from_binary(corrupted.as_slice()).unwrap_err();
+        assert!(err.downcast_ref::<TsgError>().is_some(), "{}", err);
+
+        // Offset of the offset table
+        let mut corrupted = buf.clone();
+        let footer = corrupted.len() - FOOTER_LEN as usize;
+        corrupted[footer..footer + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(BtsgReader::new(io::Cursor::new(corrupted)).is_err());
+
+        assert!(TSGraph::from_binary(&buf[..buf.len() - 20]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_rejects_text() {
+        let err = TSGraph::from_binary("H\tVN\t1.0\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("Not a BTSG file"));
+    }
+}
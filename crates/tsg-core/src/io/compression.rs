@@ -0,0 +1,390 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use flate2::Crc;
+use flate2::bufread::MultiGzDecoder;
+use flate2::write::{DeflateEncoder, GzEncoder};
+
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Maximum number of uncompressed bytes stored in a single BGZF block
+const BGZF_BLOCK_SIZE: usize = 0xff00;
+
+/// The empty block that terminates every BGZF file
+const BGZF_EOF: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// Compression formats understood by the TSG readers and writers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Bgzf,
+    Zstd,
+}
+
+impl Compression {
+    /// Detect the compression format from the leading bytes of a stream.
+    ///
+    /// BGZF is a gzip stream whose first member carries a `BC` extra subfield,
+    /// so it is checked before plain gzip.
+    pub fn from_magic(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&ZSTD_MAGIC) {
+            return Compression::Zstd;
+        }
+        if bytes.starts_with(&GZIP_MAGIC) {
+            // FLG.FEXTRA set and the first subfield identifier is "BC"
+            let has_extra = bytes.get(3).is_some_and(|flag| flag & 0x04 != 0);
+            if has_extra && bytes.get(12..14) == Some(b"BC".as_slice()) {
+                return Compression::Bgzf;
+            }
+            return Compression::Gzip;
+        }
+        Compression::None
+    }
+
+    /// Pick the compression format from the file extension.
+    ///
+    /// `.gz` is written as plain gzip, `.bgz`/`.bgzf` as BGZF and `.zst`/`.zstd` as zstd.
+    /// Any other extension is written uncompressed.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("gz") => Compression::Gzip,
+            Some("bgz") | Some("bgzf") => Compression::Bgzf,
+            Some("zst") | Some("zstd") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// The file extension conventionally used for this format, without the leading dot
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Bgzf => Some("bgz"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
+}
+
+/// Wrap a buffered reader so that gzip, BGZF and zstd input is decompressed transparently.
+///
+/// The compression format is sniffed from the magic bytes at the start of the stream;
+/// uncompressed input is passed through untouched.
+pub fn decompressed_reader<R: BufRead + 'static>(mut reader: R) -> Result<Box<dyn BufRead>> {
+    let compression = Compression::from_magic(reader.fill_buf()?);
+    let reader: Box<dyn BufRead> = match compression {
+        Compression::None => Box::new(reader),
+        // BGZF is a series of gzip members, so a multi-member decoder reads both
+        Compression::Gzip | Compression::Bgzf => {
+            Box::new(BufReader::new(MultiGzDecoder::new(reader)))
+        }
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+    };
+    Ok(reader)
+}
+
+/// Open a file for reading, decompressing it if it is gzip, BGZF or zstd compressed
+pub fn open_reader<P: AsRef<Path>>(path: P) -> Result<Box<dyn BufRead>> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    decompressed_reader(BufReader::new(file))
+}
+
+/// Writer compressing its output as gzip, BGZF or zstd, or writing it as is
+///
+/// Call [`CompressedWriter::finish`] once everything is written: it ends the compressed
+/// stream and reports the errors that dropping the writer can only ignore.
+pub struct CompressedWriter {
+    encoder: Encoder,
+    finished: bool,
+}
+
+enum Encoder {
+    Plain(BufWriter<Box<dyn Write>>),
+    Gzip(GzEncoder<BufWriter<Box<dyn Write>>>),
+    Bgzf(BgzfWriter<BufWriter<Box<dyn Write>>>),
+    Zstd(zstd::Encoder<'static, BufWriter<Box<dyn Write>>>),
+}
+
+impl CompressedWriter {
+    /// Uncompressed, buffered writer to the standard output
+    pub fn stdout() -> Self {
+        Self {
+            encoder: Encoder::Plain(BufWriter::new(Box::new(io::stdout().lock()))),
+            finished: false,
+        }
+    }
+
+    /// End the compressed stream and flush everything to the underlying writer
+    pub fn finish(mut self) -> io::Result<()> {
+        self.try_finish()
+    }
+
+    fn try_finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        match &mut self.encoder {
+            Encoder::Plain(writer) => writer.flush(),
+            Encoder::Gzip(encoder) => {
+                encoder.try_finish()?;
+                encoder.get_mut().flush()
+            }
+            Encoder::Bgzf(writer) => writer.try_finish(),
+            Encoder::Zstd(encoder) => {
+                encoder.do_finish()?;
+                encoder.get_mut().flush()
+            }
+        }
+    }
+}
+
+impl Write for CompressedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.encoder {
+            Encoder::Plain(writer) => writer.write(buf),
+            Encoder::Gzip(encoder) => encoder.write(buf),
+            Encoder::Bgzf(writer) => writer.write(buf),
+            Encoder::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.encoder {
+            Encoder::Plain(writer) => writer.flush(),
+            Encoder::Gzip(encoder) => encoder.flush(),
+            Encoder::Bgzf(writer) => writer.flush(),
+            Encoder::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+impl Drop for CompressedWriter {
+    fn drop(&mut self) {
+        let _ = self.try_finish();
+    }
+}
+
+/// Wrap a writer so that everything written to it is compressed with `compression`.
+///
+/// The compressed stream ends with [`CompressedWriter::finish`].
+pub fn compressed_writer<W: Write + 'static>(
+    writer: W,
+    compression: Compression,
+) -> Result<CompressedWriter> {
+    let writer = BufWriter::new(Box::new(writer) as Box<dyn Write>);
+    let encoder = match compression {
+        Compression::None => Encoder::Plain(writer),
+        Compression::Gzip => Encoder::Gzip(GzEncoder::new(writer, flate2::Compression::default())),
+        Compression::Bgzf => Encoder::Bgzf(BgzfWriter::new(writer)),
+        Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(writer, 0)?),
+    };
+    Ok(CompressedWriter {
+        encoder,
+        finished: false,
+    })
+}
+
+/// Create a file for writing, compressing it according to its extension
+pub fn create_writer<P: AsRef<Path>>(path: P) -> Result<CompressedWriter> {
+    let path = path.as_ref();
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    compressed_writer(file, Compression::from_path(path))
+}
+
+/// Writer producing blocked gzip (BGZF) output as used by `bgzip` and htslib
+///
+/// Data is split into independent gzip members of at most [`BGZF_BLOCK_SIZE`] bytes,
+/// and the standard empty EOF block is appended by [`BgzfWriter::try_finish`], or when
+/// the writer is dropped.
+pub struct BgzfWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+    finished: bool,
+}
+
+impl<W: Write> BgzfWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buffer: Vec::with_capacity(BGZF_BLOCK_SIZE),
+            finished: false,
+        }
+    }
+
+    /// Write the pending block and the EOF block, then flush the underlying writer
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.write_block()?;
+        self.inner.write_all(&BGZF_EOF)?;
+        self.finished = true;
+        self.inner.flush()
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&self.buffer)?;
+        let compressed = encoder.finish()?;
+
+        let mut crc = Crc::new();
+        crc.update(&self.buffer);
+
+        // 18 bytes of header, the deflate payload and 8 bytes of trailer
+        let block_size = u16::try_from(compressed.len() + 25)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "BGZF block exceeds 64 KiB"))?;
+
+        let mut header = [
+            0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, b'B', b'C',
+            0x02, 0x00, 0x00, 0x00,
+        ];
+        header[16..18].copy_from_slice(&block_size.to_le_bytes());
+
+        self.inner.write_all(&header)?;
+        self.inner.write_all(&compressed)?;
+        self.inner.write_all(&crc.sum().to_le_bytes())?;
+        self.inner
+            .write_all(&(self.buffer.len() as u32).to_le_bytes())?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for BgzfWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let available = BGZF_BLOCK_SIZE - self.buffer.len();
+        let len = buf.len().min(available);
+        self.buffer.extend_from_slice(&buf[..len]);
+        if self.buffer.len() == BGZF_BLOCK_SIZE {
+            self.write_block()?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for BgzfWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.try_finish();
+    }
+}
+
+/// Read a whole (possibly compressed) file into a string
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
+    let mut content = String::new();
+    open_reader(path)?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "H\tTSG\t1.0\nN\tn1\tchr1:+:100-200\tread1:SO\n";
+
+    fn roundtrip(name: &str, expected: Compression) -> Result<()> {
+        let path = std::env::temp_dir().join(name);
+        let mut writer = create_writer(&path)?;
+        writer.write_all(CONTENT.as_bytes())?;
+        writer.finish()?;
+
+        let raw = std::fs::read(&path)?;
+        assert_eq!(Compression::from_magic(&raw), expected);
+        assert_eq!(read_to_string(&path)?, CONTENT);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compression_from_path() {
+        assert_eq!(Compression::from_path("a.tsg"), Compression::None);
+        assert_eq!(Compression::from_path("a.tsg.gz"), Compression::Gzip);
+        assert_eq!(Compression::from_path("a.tsg.bgz"), Compression::Bgzf);
+        assert_eq!(Compression::from_path("a.tsg.zst"), Compression::Zstd);
+    }
+
+    #[test]
+    fn test_roundtrip_plain() -> Result<()> {
+        roundtrip("tsg_compression_test.tsg", Compression::None)
+    }
+
+    #[test]
+    fn test_roundtrip_gzip() -> Result<()> {
+        roundtrip("tsg_compression_test.tsg.gz", Compression::Gzip)
+    }
+
+    #[test]
+    fn test_roundtrip_bgzf() -> Result<()> {
+        roundtrip("tsg_compression_test.tsg.bgz", Compression::Bgzf)
+    }
+
+    #[test]
+    fn test_roundtrip_zstd() -> Result<()> {
+        roundtrip("tsg_compression_test.tsg.zst", Compression::Zstd)
+    }
+
+    #[test]
+    fn test_bgzf_multiple_blocks() -> Result<()> {
+        let path = std::env::temp_dir().join("tsg_compression_blocks.tsg.bgz");
+        let content = CONTENT.repeat(5000);
+        let mut writer = create_writer(&path)?;
+        writer.write_all(content.as_bytes())?;
+        writer.finish()?;
+        assert_eq!(read_to_string(&path)?, content);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Writer whose underlying device is full
+    struct FullWriter;
+
+    impl Write for FullWriter {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("device full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_finish_reports_errors() -> Result<()> {
+        for compression in [
+            Compression::None,
+            Compression::Gzip,
+            Compression::Bgzf,
+            Compression::Zstd,
+        ] {
+            let mut writer = compressed_writer(FullWriter, compression)?;
+            writer.write_all(CONTENT.as_bytes())?;
+            assert!(writer.finish().is_err(), "{:?}", compression);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,400 @@
+use crate::graph::{
+    DotOptions, FORWARD_COLOR, GraphSection, Group, NodeData, OrderingPolicy, REVERSE_COLOR,
+    ReadIdentity, Strand, scale_style,
+};
+use ahash::HashMap;
+use anyhow::{Result, bail};
+use bstr::{BString, ByteSlice};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use std::fmt::Write;
+
+/// Fill colours of the read identities of a node
+const SOURCE_COLOR: &str = "#a6dba0";
+const INTERMEDIATE_COLOR: &str = "#fdd49e";
+const SINK_COLOR: &str = "#c2a5cf";
+/// Colour of the elements outside the highlighted paths
+const MUTED_COLOR: &str = "#cccccc";
+/// Colours of the highlighted paths, reused when there are more paths
+pub(super) const PATH_PALETTE: &[&str] = &[
+    "#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#a65628", "#f781bf",
+];
+/// Range of edge pen widths, in points
+const PENWIDTH_RANGE: (f64, f64) = (1.0, 5.0);
+
+/// Quote a DOT identifier or label, keeping `\n` line breaks of labels
+fn dot_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Striped fill colours of a node by the share of its source, intermediate and sink reads
+fn identity_fill(node: &NodeData) -> Option<String> {
+    let total = node.reads.len();
+    if total == 0 {
+        return None;
+    }
+    let count = |identity: ReadIdentity| {
+        node.reads
+            .iter()
+            .filter(|read| read.identity == identity)
+            .count()
+    };
+    let stripes: Vec<_> = [
+        (SOURCE_COLOR, count(ReadIdentity::SO)),
+        (INTERMEDIATE_COLOR, count(ReadIdentity::IN)),
+        (SINK_COLOR, count(ReadIdentity::SI)),
+    ]
+    .into_iter()
+    .filter(|(_, count)| *count > 0)
+    .collect();
+
+    // The last stripe takes the remainder, so rounding never exceeds the whole
+    let last = stripes.len() - 1;
+    let fill = stripes
+        .iter()
+        .enumerate()
+        .map(|(i, (color, count))| {
+            if i == last {
+                color.to_string()
+            } else {
+                format!("{};{:.2}", color, *count as f64 / total as f64)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(":");
+    Some(fill)
+}
+
+/// Node label: its ID, locus and read count, or its ID alone when it has no exons
+fn node_label(node: &NodeData) -> String {
+    let exons = &node.exons.exons;
+    let (Some(first), Some(last)) = (exons.first(), exons.last()) else {
+        return node.id.to_string();
+    };
+    format!(
+        "{}\n{}:{}-{} ({})\n{} reads",
+        node.id,
+        node.reference_id,
+        first.start,
+        last.end,
+        node.strand,
+        node.reads.len()
+    )
+}
+
+/// Reference and start of a node, if it has exons
+fn node_start(node: &NodeData) -> Option<(&BString, usize)> {
+    node.exons
+        .exons
+        .first()
+        .map(|exon| (&node.reference_id, exon.start))
+}
+
+/// Colours of the highlighted paths running through each node and edge
+#[derive(Default)]
+struct Highlights {
+    nodes: HashMap<NodeIndex, Vec<&'static str>>,
+    edges: HashMap<EdgeIndex, Vec<&'static str>>,
+}
+
+impl Highlights {
+    fn new(graph: &GraphSection, path_ids: &[BString]) -> Result<Self> {
+        let mut highlights = Self::default();
+        for (i, path_id) in path_ids.iter().enumerate() {
+            let Some(Group::Ordered { elements, .. }) = graph.groups().get(path_id) else {
+                bail!("Path {} not found in graph {}", path_id, graph.id);
+            };
+            let color = PATH_PALETTE[i % PATH_PALETTE.len()];
+            let mut previous: Option<NodeIndex> = None;
+            for element in elements {
+                if let Some(&node_idx) = graph.node_indices().get(&element.id) {
+                    // Paths may list only their nodes, so the edges between them are implied
+                    // and the earliest added of parallel ones is taken; listed edges choose
+                    // among parallel ones
+                    if let Some(source) = previous
+                        && let Some(edge_idx) = graph.first_edge_between(source, node_idx)
+                    {
+                        highlights.add_edge(edge_idx, color);
+                    }
+                    highlights.add_node(node_idx, color);
+                    previous = Some(node_idx);
+                } else if let Some(&edge_idx) = graph.edge_indices().get(&element.id) {
+                    highlights.add_edge(edge_idx, color);
+                    previous = None;
+                } else {
+                    bail!("Element {} of path {} not found", element.id, path_id);
+                }
+            }
+        }
+        Ok(highlights)
+    }
+
+    fn add_node(&mut self, idx: NodeIndex, color: &'static str) {
+        let colors = self.nodes.entry(idx).or_default();
+        if !colors.contains(&color) {
+            colors.push(color);
+        }
+    }
+
+    fn add_edge(&mut self, idx: EdgeIndex, color: &'static str) {
+        let colors = self.edges.entry(idx).or_default();
+        if !colors.contains(&color) {
+            colors.push(color);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.edges.is_empty()
+    }
+}
+
+impl GraphSection {
+    /// Convert the graph section to DOT, optionally labelling nodes and edges
+    pub fn to_dot(&self, node_label: bool, edge_label: bool) -> Result<String> {
+        self.to_dot_with_options(
+            &DotOptions::builder()
+                .node_label(node_label)
+                .edge_label(edge_label)
+                .build(),
+        )
+    }
+
+    /// Convert the graph section to DOT for Graphviz
+    ///
+    /// Nodes are laid out from left to right by genomic coordinate, with nodes starting at
+    /// the same position in one rank. Their border is coloured by strand and their fill is
+    /// striped by the share of source, intermediate and sink reads. Edge pen widths grow
+    /// with the number of reads shared by both ends. Nodes and edges of the paths in
+    /// `options.highlight_paths` are drawn in one colour per path and the rest in grey;
+    /// an unknown path is an error.
+    pub fn to_dot_with_options(&self, options: &DotOptions) -> Result<String> {
+        let highlights = Highlights::new(self, &options.highlight_paths)?;
+        let highlighting = !highlights.is_empty();
+        let nodes = self.ordered_node_indices(OrderingPolicy::Sorted);
+        let edges = self.ordered_edge_indices(OrderingPolicy::Sorted);
+        let node_id = |idx: NodeIndex| dot_quote(&self.inner_graph()[idx].id.to_str_lossy());
+
+        let mut dot = String::new();
+        writeln!(dot, "digraph {} {{", dot_quote(&self.id.to_str_lossy()))?;
+        writeln!(dot, "  rankdir=LR;")?;
+        writeln!(
+            dot,
+            "  node [shape=box, style=filled, fillcolor=white, fontname=Helvetica];"
+        )?;
+        writeln!(dot, "  edge [fontname=Helvetica];")?;
+
+        for &idx in &nodes {
+            let node = &self.inner_graph()[idx];
+            let label = if options.node_label {
+                node_label(node)
+            } else {
+                node.id.to_string()
+            };
+            let mut attrs = vec![format!("label={}", dot_quote(&label))];
+            if let Some(fill) = identity_fill(node) {
+                attrs.push("style=striped".to_string());
+                attrs.push(format!("fillcolor={}", dot_quote(&fill)));
+            }
+            let color = match (highlighting, highlights.nodes.get(&idx)) {
+                (true, Some(colors)) => {
+                    attrs.push("penwidth=3".to_string());
+                    colors[0]
+                }
+                (true, None) => {
+                    attrs.push(format!("fontcolor={}", dot_quote(MUTED_COLOR)));
+                    MUTED_COLOR
+                }
+                (false, _) => match node.strand {
+                    Strand::Forward => FORWARD_COLOR,
+                    Strand::Reverse => REVERSE_COLOR,
+                },
+            };
+            attrs.push(format!("color={}", dot_quote(color)));
+            writeln!(dot, "  {} [{}];", node_id(idx), attrs.join(", "))?;
+        }
+
+        // Rank nodes by genomic coordinate: nodes starting together share a rank and
+        // invisible edges keep consecutive ranks in order; nodes without exons rank alone
+        let mut ranks: Vec<Vec<NodeIndex>> = Vec::new();
+        let mut last_position = None;
+        for &idx in &nodes {
+            let position = node_start(&self.inner_graph()[idx]);
+            if position.is_some() && position == last_position {
+                ranks.last_mut().unwrap().push(idx);
+            } else {
+                ranks.push(vec![idx]);
+                last_position = position;
+            }
+        }
+        for rank in ranks.iter().filter(|rank| rank.len() > 1) {
+            let members: Vec<_> = rank.iter().map(|&idx| node_id(idx)).collect();
+            writeln!(dot, "  {{ rank=same; {}; }}", members.join("; "))?;
+        }
+        for pair in ranks.windows(2) {
+            writeln!(
+                dot,
+                "  {} -> {} [style=invis];",
+                node_id(pair[0][0]),
+                node_id(pair[1][0])
+            )?;
+        }
+
+        let max_weight = edges
+            .iter()
+            .map(|&idx| self.edge_weight(idx))
+            .max()
+            .unwrap_or_default();
+        for &idx in &edges {
+            let edge = &self.inner_graph()[idx];
+            let (source, target) = self.inner_graph().edge_endpoints(idx).unwrap();
+            let weight = self.edge_weight(idx);
+            let mut attrs = vec![format!("id={}", dot_quote(&edge.id.to_str_lossy()))];
+            if options.edge_label {
+                attrs.push(format!(
+                    "label={}",
+                    dot_quote(&edge.sv.sv_type.to_str_lossy())
+                ));
+            }
+            let mut penwidth = scale_style(weight, max_weight, PENWIDTH_RANGE);
+            match (highlighting, highlights.edges.get(&idx)) {
+                (true, Some(colors)) => {
+                    penwidth += 1.0;
+                    attrs.push(format!("color={}", dot_quote(&colors.join(":"))));
+                }
+                (true, None) => {
+                    attrs.push(format!("color={}", dot_quote(MUTED_COLOR)));
+                    attrs.push(format!("fontcolor={}", dot_quote(MUTED_COLOR)));
+                }
+                (false, _) => {}
+            }
+            attrs.push(format!("penwidth={}", penwidth));
+            attrs.push(format!("weight={}", weight.max(1)));
+            // Edges running back along the genome must not undo the coordinate ranks
+            if let (Some(source_start), Some(target_start)) = (
+                node_start(&self.inner_graph()[source]),
+                node_start(&self.inner_graph()[target]),
+            ) && source_start.0 == target_start.0
+                && target_start.1 < source_start.1
+            {
+                attrs.push("constraint=false".to_string());
+            }
+            writeln!(
+                dot,
+                "  {} -> {} [{}];",
+                node_id(source),
+                node_id(target),
+                attrs.join(", ")
+            )?;
+        }
+
+        writeln!(dot, "}}")?;
+        Ok(dot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::TSGraph;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_dot_quote() {
+        assert_eq!(dot_quote("n1"), "\"n1\"");
+        assert_eq!(dot_quote("a\"b\\c\nd"), "\"a\\\"b\\\\c\\nd\"");
+    }
+
+    #[test]
+    fn test_identity_fill() -> Result<()> {
+        let node: NodeData = "N\tn1\tchr1:+:1-2\tr1:SO,r2:IN,r3:IN,r4:SI\t".parse()?;
+        assert_eq!(
+            identity_fill(&node).unwrap(),
+            "#a6dba0;0.25:#fdd49e;0.50:#c2a5cf"
+        );
+        let node: NodeData = "N\tn1\tchr1:+:1-2\t\t".parse()?;
+        assert_eq!(identity_fill(&node), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_dot_with_options() -> Result<()> {
+        let tsg_graph = TSGraph::from_file("tests/data/test.tsg")?;
+        let graph = tsg_graph.default_graph().unwrap();
+
+        let dot = graph.to_dot_with_options(&DotOptions::default())?;
+        assert!(dot.starts_with("digraph \"graph\" {\n  rankdir=LR;"));
+        assert!(dot.contains(
+            "\"n1\" [label=\"n1\\nchr1:1000-1700 (+)\\n2 reads\", style=striped, fillcolor=\"#a6dba0\", color=\"#1f77b4\"];"
+        ));
+        assert!(
+            dot.contains("fillcolor=\"#fdd49e\"") && dot.contains("color=\"#d62728\""),
+            "n3 holds intermediate reads and n4 is on the reverse strand"
+        );
+        assert!(dot.contains("{ rank=same; \"n3\"; \"n4\"; \"n5\"; }"));
+        assert!(dot.contains("\"n1\" -> \"n2\" [style=invis];"));
+        assert!(dot.contains("\"n3\" -> \"n5\" [id=\"e4\", label=\"DUP\", penwidth=5, weight=2];"));
+        assert!(dot.contains("\"n1\" -> \"n3\" [id=\"e1\", label=\"INV\", penwidth=5"));
+
+        let options = DotOptions::builder()
+            .node_label(false)
+            .edge_label(false)
+            .highlight_paths(vec!["transcript1".into()])
+            .build();
+        let dot = graph.to_dot_with_options(&options)?;
+        assert!(dot.contains("\"n1\" [label=\"n1\""));
+        assert!(!dot.contains("label=\"INV\""));
+        assert!(dot.contains("\"n1\" -> \"n3\" [id=\"e1\", color=\"#e41a1c\""));
+        assert!(dot.contains("\"n2\" -> \"n3\" [id=\"e3\", color=\"#cccccc\""));
+
+        let options = DotOptions::builder()
+            .highlight_paths(vec!["missing".into()])
+            .build();
+        assert!(graph.to_dot_with_options(&options).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_dot_without_exons() -> Result<()> {
+        // Segments of a plain GFA have no locus
+        let gfa = "S\ts1\tACGT\nS\ts2\tTTGA\nS\ts3\tCC\nL\ts1\t+\ts2\t+\t0M\nL\ts3\t+\ts2\t+\t0M\n";
+        let tsg_graph = TSGraph::from_gfa(gfa.as_bytes())?;
+        let dot = tsg_graph
+            .default_graph()
+            .unwrap()
+            .to_dot_with_options(&DotOptions::default())?;
+        assert!(dot.contains("\"s1\" [label=\"s1\","));
+        assert!(!dot.contains("rank=same"), "nodes without exons rank alone");
+        assert!(!dot.contains("constraint=false"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_dot_highlights_first_parallel_edge() -> Result<()> {
+        let tsg_graph = TSGraph::from_str(
+            "N\tn1\tchr1:+:100-200\tr1:SO\nN\tn2\tchr1:+:300-400\tr1:SI\n\
+             E\te1\tn1\tn2\tchr1,chr1,200,300,splice\n\
+             E\te2\tn1\tn2\tchr1,chr1,200,300,DUP\n\
+             P\tp1\tn1+ n2+\n",
+        )?;
+        let options = DotOptions::builder()
+            .highlight_paths(vec!["p1".into()])
+            .build();
+        let dot = tsg_graph
+            .default_graph()
+            .unwrap()
+            .to_dot_with_options(&options)?;
+        assert!(dot.contains("[id=\"e1\", label=\"splice\", color=\"#e41a1c\""));
+        assert!(dot.contains("[id=\"e2\", label=\"DUP\", color=\"#cccccc\""));
+        Ok(())
+    }
+}
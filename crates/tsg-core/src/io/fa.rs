@@ -1,9 +1,35 @@
-use crate::graph::TSGraph;
+use crate::graph::{GraphSection, TSGraph, WriteOptions};
 use anyhow::Result;
 use std::io::Write;
 
 pub fn to_fa<W: Write>(tsg_graph: &mut TSGraph, writer: &mut W) -> Result<()> {
-    let paths = tsg_graph.traverse_all_graphs()?;
+    to_fa_with_options(tsg_graph, writer, &WriteOptions::default())
+}
+
+/// Write the sequences of the paths of all graphs, in the order given by `options`
+pub fn to_fa_with_options<W: Write>(
+    tsg_graph: &TSGraph,
+    writer: &mut W,
+    options: &WriteOptions,
+) -> Result<()> {
+    for graph in tsg_graph.ordered_graphs(options.ordering) {
+        graph_to_fa_with_options(graph, writer, options)?;
+    }
+    Ok(())
+}
+
+/// Write the sequences of the paths of a single graph section
+pub fn graph_to_fa<W: Write>(graph: &GraphSection, writer: &mut W) -> Result<()> {
+    graph_to_fa_with_options(graph, writer, &WriteOptions::default())
+}
+
+/// Write the sequences of the paths of a single graph section, in the order given by `options`
+pub fn graph_to_fa_with_options<W: Write>(
+    graph: &GraphSection,
+    writer: &mut W,
+    options: &WriteOptions,
+) -> Result<()> {
+    let paths = graph.traverse_with_ordering(options.ordering)?;
 
     for path in paths {
         let seq = path.to_fa()?;
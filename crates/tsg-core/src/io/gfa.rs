@@ -0,0 +1,1034 @@
+//! GFA 1.0 and GFA 2.0 import and export
+//!
+//! TSG elements map onto GFA records as follows:
+//!
+//! | TSG            | GFA 1.0        | GFA 2.0        |
+//! |----------------|----------------|----------------|
+//! | node (`N`)     | `S` segment    | `S` segment    |
+//! | edge (`E`)     | `L` link       | `E` edge       |
+//! | path (`P`)     | `P` path       | `O` group      |
+//! | chain (`C`)    | `P` path       | `O` group      |
+//! | set (`U`)      | not supported  | `U` group      |
+//!
+//! Fields without a GFA counterpart are kept in optional tags, so that nothing is lost:
+//! reference (`rf`), strand (`sd`), exons (`ex`), reads (`rd`), structural variant (`sv`),
+//! owning graph (`gr`), chain marker (`ty`), full path elements in GFA 1.0 (`pe`),
+//! TSG headers (`th`), inter-graph links (`lk`) and comments of a graph section (`cm`).
+//! TSG attributes are written as they are, and comments before the first graph section
+//! become GFA `#` comment lines.
+//! When a file has several graphs, element names are prefixed with `<graph_id>:` to keep
+//! them unique.
+
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use ahash::{HashMap, HashSet, HashSetExt};
+use anyhow::{Context, Result, anyhow, bail};
+use bstr::{BStr, BString, ByteSlice};
+use tracing::warn;
+
+use super::open_reader;
+use crate::graph::{
+    Attribute, DEFAULT_GRAPH_ID, EdgeData, GraphSection, Group, Header, InterGraphLink, NodeData,
+    OrderingPolicy, Orientation, OrientedElement, ReadData, TSGraph,
+};
+
+/// Tag holding the ID of the graph an element belongs to
+pub const GFA_TAG_GRAPH: &str = "gr";
+/// Tag holding the reference sequence name of a node
+pub const GFA_TAG_REFERENCE: &str = "rf";
+/// Tag holding the strand of a node
+pub const GFA_TAG_STRAND: &str = "sd";
+/// Tag holding the exon coordinates of a node
+pub const GFA_TAG_EXONS: &str = "ex";
+/// Tag holding the reads supporting a node
+pub const GFA_TAG_READS: &str = "rd";
+/// Tag holding the structural variant of an edge
+pub const GFA_TAG_SV: &str = "sv";
+/// Tag marking a GFA path or ordered group that came from a TSG chain
+pub const GFA_TAG_GROUP_TYPE: &str = "ty";
+/// Tag holding all elements of a path, including edges, in GFA 1.0
+pub const GFA_TAG_PATH_ELEMENTS: &str = "pe";
+/// Tag holding a TSG header as `<tag>=<value>`
+pub const GFA_TAG_HEADER: &str = "th";
+/// Tag holding an inter-graph link as `<id>,<source>,<target>,<type>`
+pub const GFA_TAG_LINK: &str = "lk";
+/// Tag holding one comment line of a graph section, on a header line with [`GFA_TAG_GRAPH`]
+pub const GFA_TAG_COMMENT: &str = "cm";
+
+/// Value of [`GFA_TAG_GROUP_TYPE`] for chains
+pub const GFA_CHAIN_TYPE: &str = "chain";
+
+/// GFA specification version to write
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GfaVersion {
+    V1,
+    #[default]
+    V2,
+}
+
+impl fmt::Display for GfaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GfaVersion::V1 => write!(f, "1.0"),
+            GfaVersion::V2 => write!(f, "2.0"),
+        }
+    }
+}
+
+/// Builds the optional tags of a GFA record
+#[derive(Default)]
+struct Tags(Vec<String>);
+
+impl Tags {
+    fn push(&mut self, tag: &str, kind: char, value: impl fmt::Display) {
+        self.0.push(format!("{}:{}:{}", tag, kind, value));
+    }
+
+    fn graph(&mut self, graph: &GraphSection) {
+        if graph.id != DEFAULT_GRAPH_ID {
+            self.push(GFA_TAG_GRAPH, 'Z', &graph.id);
+        }
+    }
+
+    fn attributes<'a>(&mut self, attributes: impl IntoIterator<Item = &'a Attribute>) {
+        let mut attributes: Vec<_> = attributes.into_iter().collect();
+        attributes.sort_by(|a, b| a.tag.cmp(&b.tag));
+        self.0
+            .extend(attributes.into_iter().map(|attr| attr.to_string()));
+    }
+}
+
+impl fmt::Display for Tags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for tag in &self.0 {
+            write!(f, "\t{}", tag)?;
+        }
+        Ok(())
+    }
+}
+
+/// Length of a segment: its sequence if known, otherwise the total length of its exons
+fn segment_length(node: &NodeData) -> usize {
+    node.sequence
+        .as_ref()
+        .map_or_else(|| node.exons.span(), |sequence| sequence.len())
+}
+
+/// Writes the records of one graph section
+struct GfaSectionWriter<'a> {
+    graph: &'a GraphSection,
+    version: GfaVersion,
+    qualify: bool,
+}
+
+impl GfaSectionWriter<'_> {
+    /// GFA name of an element, prefixed with its graph when several graphs share the file
+    fn name(&self, id: &BStr) -> BString {
+        if self.qualify {
+            let mut name = self.graph.id.clone();
+            name.push(b':');
+            name.extend_from_slice(id);
+            name
+        } else {
+            id.to_owned()
+        }
+    }
+
+    fn oriented(&self, id: &BStr, orientation: Option<Orientation>) -> String {
+        let sign = match orientation {
+            Some(Orientation::Reverse) => '-',
+            _ => '+',
+        };
+        format!("{}{}", self.name(id), sign)
+    }
+
+    /// All elements of a group, oriented where the orientation is known
+    fn elements(&self, elements: &[(&BStr, Option<Orientation>)]) -> String {
+        elements
+            .iter()
+            .map(|&(element, orientation)| match orientation {
+                Some(_) => self.oriented(element, orientation),
+                None => self.name(element).to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let graph = self.graph;
+        let inner = graph.inner_graph();
+
+        for idx in graph.ordered_node_indices(OrderingPolicy::Sorted) {
+            let node = &inner[idx];
+            let length = segment_length(node);
+            let sequence = node.sequence.as_ref().map_or("*".into(), |s| s.clone());
+
+            let mut tags = Tags::default();
+            tags.graph(graph);
+            if !node.reference_id.is_empty() {
+                tags.push(GFA_TAG_REFERENCE, 'Z', &node.reference_id);
+            }
+            tags.push(GFA_TAG_STRAND, 'A', node.strand);
+            if !node.exons.is_empty() {
+                tags.push(GFA_TAG_EXONS, 'Z', &node.exons);
+            }
+            if !node.reads.is_empty() {
+                let reads: Vec<String> = node.reads.iter().map(|r| r.to_string()).collect();
+                tags.push(GFA_TAG_READS, 'Z', reads.join(","));
+            }
+            tags.attributes(node.attributes.values());
+
+            let name = self.name(node.id.as_bstr());
+            match self.version {
+                GfaVersion::V1 => {
+                    if node.sequence.is_none() {
+                        tags.0.insert(0, format!("LN:i:{}", length));
+                    }
+                    writeln!(writer, "S\t{}\t{}{}", name, sequence, tags)?;
+                }
+                GfaVersion::V2 => {
+                    writeln!(writer, "S\t{}\t{}\t{}{}", name, length, sequence, tags)?;
+                }
+            }
+        }
+
+        for idx in graph.ordered_edge_indices(OrderingPolicy::Sorted) {
+            let edge = &inner[idx];
+            let (source, target) = inner.edge_endpoints(idx).unwrap();
+            let (source, target) = (&inner[source], &inner[target]);
+
+            let mut tags = Tags::default();
+            tags.graph(graph);
+            tags.push(GFA_TAG_SV, 'Z', &edge.sv);
+            tags.attributes(edge.attributes.values());
+
+            match self.version {
+                GfaVersion::V1 => {
+                    writeln!(
+                        writer,
+                        "L\t{}\t+\t{}\t+\t0M\tID:Z:{}{}",
+                        self.name(source.id.as_bstr()),
+                        self.name(target.id.as_bstr()),
+                        self.name(edge.id.as_bstr()),
+                        tags
+                    )?;
+                }
+                GfaVersion::V2 => {
+                    // A dovetail from the end of the source to the start of the target
+                    let source_length = segment_length(source);
+                    writeln!(
+                        writer,
+                        "E\t{}\t{}+\t{}+\t{}$\t{}$\t0\t0\t*{}",
+                        self.name(edge.id.as_bstr()),
+                        self.name(source.id.as_bstr()),
+                        self.name(target.id.as_bstr()),
+                        source_length,
+                        source_length,
+                        tags
+                    )?;
+                }
+            }
+        }
+
+        for (id, group) in graph.ordered_groups(OrderingPolicy::Sorted) {
+            self.write_group(writer, id.as_bstr(), group)?;
+        }
+        Ok(())
+    }
+
+    fn write_group<W: Write>(&self, writer: &mut W, id: &BStr, group: &Group) -> Result<()> {
+        let mut tags = Tags::default();
+        tags.graph(self.graph);
+
+        let (elements, attributes): (Vec<_>, _) = match group {
+            Group::Unordered {
+                elements,
+                attributes,
+                ..
+            } if self.version == GfaVersion::V2 => {
+                tags.attributes(attributes.values());
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|e| self.name(e.as_bstr()).to_string())
+                    .collect();
+                writeln!(
+                    writer,
+                    "U\t{}\t{}{}",
+                    self.name(id),
+                    elements.join(" "),
+                    tags
+                )?;
+                return Ok(());
+            }
+            Group::Unordered { .. } => {
+                warn!("GFA 1.0 has no unordered groups, skipping set {}", id);
+                return Ok(());
+            }
+            Group::Ordered {
+                elements,
+                attributes,
+                ..
+            } => (
+                elements
+                    .iter()
+                    .map(|e| (e.id.as_bstr(), e.orientation))
+                    .collect(),
+                attributes,
+            ),
+            Group::Chain {
+                elements,
+                attributes,
+                ..
+            } => {
+                tags.push(GFA_TAG_GROUP_TYPE, 'Z', GFA_CHAIN_TYPE);
+                (
+                    elements.iter().map(|e| (e.as_bstr(), None)).collect(),
+                    attributes,
+                )
+            }
+        };
+
+        let has_unoriented = matches!(group, Group::Ordered { .. })
+            && elements
+                .iter()
+                .any(|(_, orientation)| orientation.is_none());
+
+        match self.version {
+            GfaVersion::V1 => {
+                // GFA 1.0 paths only list segments; edges are kept in a tag
+                let segments: Vec<String> = elements
+                    .iter()
+                    .filter(|(element, _)| self.graph.node_indices().contains_key(*element))
+                    .map(|&(element, orientation)| self.oriented(element, orientation))
+                    .collect();
+                if segments.is_empty() {
+                    warn!("Path {} has no segments, skipping it", id);
+                    return Ok(());
+                }
+                // Keep the complete element list when the segments alone would lose
+                // edges, nested groups or unoriented elements
+                if segments.len() != elements.len() || has_unoriented {
+                    tags.push(GFA_TAG_PATH_ELEMENTS, 'Z', self.elements(&elements));
+                }
+                tags.attributes(attributes.values());
+                writeln!(
+                    writer,
+                    "P\t{}\t{}\t*{}",
+                    self.name(id),
+                    segments.join(","),
+                    tags
+                )?;
+            }
+            GfaVersion::V2 => {
+                let refs: Vec<String> = elements
+                    .iter()
+                    .map(|&(element, orientation)| self.oriented(element, orientation))
+                    .collect();
+                // GFA 2.0 references are always oriented
+                if has_unoriented {
+                    tags.push(GFA_TAG_PATH_ELEMENTS, 'Z', self.elements(&elements));
+                }
+                tags.attributes(attributes.values());
+                writeln!(writer, "O\t{}\t{}{}", self.name(id), refs.join(" "), tags)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write a TSGraph as GFA
+///
+/// Graphs are written in ID order and their elements in the sorted order of
+/// [`OrderingPolicy::Sorted`].
+pub fn write_gfa<W: Write>(tsg_graph: &TSGraph, writer: &mut W, version: GfaVersion) -> Result<()> {
+    writeln!(writer, "H\tVN:Z:{}", version)?;
+    for comment in &tsg_graph.comments {
+        writeln!(writer, "#{}", comment)?;
+    }
+    for header in &tsg_graph.headers {
+        writeln!(
+            writer,
+            "H\t{}:Z:{}={}",
+            GFA_TAG_HEADER, header.tag, header.value
+        )?;
+    }
+
+    let graphs = tsg_graph.ordered_graphs(OrderingPolicy::Sorted);
+
+    // Graph sections, their attributes and comments are kept as header lines
+    for graph in &graphs {
+        if graph.id == DEFAULT_GRAPH_ID && graph.attributes.is_empty() && graph.comments.is_empty()
+        {
+            continue;
+        }
+        let mut tags = Tags::default();
+        tags.attributes(graph.attributes.values());
+        writeln!(writer, "H\t{}:Z:{}{}", GFA_TAG_GRAPH, graph.id, tags)?;
+        for comment in &graph.comments {
+            writeln!(
+                writer,
+                "H\t{}:Z:{}\t{}:Z:{}",
+                GFA_TAG_GRAPH, graph.id, GFA_TAG_COMMENT, comment
+            )?;
+        }
+    }
+
+    for link in &tsg_graph.links {
+        let mut tags = Tags::default();
+        tags.attributes(link.attributes.values());
+        writeln!(
+            writer,
+            "H\t{}:Z:{},{}:{},{}:{},{}{}",
+            GFA_TAG_LINK,
+            link.id,
+            link.source_graph,
+            link.source_element,
+            link.target_graph,
+            link.target_element,
+            link.link_type,
+            tags
+        )?;
+    }
+
+    let qualify = graphs.len() > 1;
+    for graph in graphs {
+        GfaSectionWriter {
+            graph,
+            version,
+            qualify,
+        }
+        .write(writer)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Parse the optional tags of a GFA record
+///
+/// GFA character tags (`A`) have no TSG counterpart and become strings.
+fn parse_tags(fields: &[&str]) -> Result<Vec<Attribute>> {
+    fields
+        .iter()
+        .map(|field| {
+            let attr = match field.splitn(3, ':').collect::<Vec<_>>()[..] {
+                [tag, "A", value] => Attribute::builder()
+                    .tag(tag)
+                    .attribute_type('Z')
+                    .value(value)
+                    .build(),
+                _ => field.parse::<Attribute>()?,
+            };
+            Ok(attr)
+        })
+        .collect()
+}
+
+/// Remove a tag from the list and return its value
+fn take_tag(tags: &mut Vec<Attribute>, tag: &str) -> Option<BString> {
+    let position = tags.iter().position(|attr| attr.tag == tag)?;
+    Some(tags.remove(position).value)
+}
+
+fn to_attribute_map(tags: Vec<Attribute>) -> HashMap<BString, Attribute> {
+    tags.into_iter()
+        .map(|attr| (attr.tag.clone(), attr))
+        .collect()
+}
+
+/// Split a GFA reference such as `n1+` into its name and orientation
+fn split_reference(reference: &str) -> Result<(&str, Orientation)> {
+    if let Some(name) = reference.strip_suffix('+') {
+        Ok((name, Orientation::Forward))
+    } else if let Some(name) = reference.strip_suffix('-') {
+        Ok((name, Orientation::Reverse))
+    } else {
+        bail!("Reference {} has no orientation", reference)
+    }
+}
+
+fn parse_orientation(orientation: &str) -> Result<Orientation> {
+    match orientation {
+        "+" => Ok(Orientation::Forward),
+        "-" => Ok(Orientation::Reverse),
+        _ => bail!("Invalid orientation: {}", orientation),
+    }
+}
+
+/// Builds a TSGraph from GFA records
+struct GfaReader {
+    tsgraph: TSGraph,
+    version: Option<GfaVersion>,
+    segments: HashSet<(BString, BString)>,
+}
+
+impl GfaReader {
+    fn new() -> Self {
+        Self {
+            tsgraph: TSGraph::new(),
+            version: None,
+            segments: HashSet::new(),
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields[0] {
+            "H" => self.parse_header(&fields),
+            "S" => self.parse_segment(&fields),
+            "L" => self.parse_link(&fields),
+            "E" => self.parse_edge(&fields),
+            "P" => self.parse_path(&fields),
+            "O" => self.parse_ordered_group(&fields),
+            "U" => self.parse_unordered_group(&fields),
+            record => {
+                warn!("Ignoring unsupported GFA record type: {}", record);
+                Ok(())
+            }
+        }
+    }
+
+    fn parse_header(&mut self, fields: &[&str]) -> Result<()> {
+        let mut tags = parse_tags(&fields[1..])?;
+
+        if let Some(graph_id) = take_tag(&mut tags, GFA_TAG_GRAPH) {
+            let graph = self
+                .tsgraph
+                .graphs
+                .entry(graph_id.clone())
+                .or_insert_with(|| GraphSection::new(graph_id));
+            if let Some(comment) = take_tag(&mut tags, GFA_TAG_COMMENT) {
+                graph.comments.push(comment);
+            }
+            graph.attributes.extend(to_attribute_map(tags));
+            return Ok(());
+        }
+
+        if let Some(link) = take_tag(&mut tags, GFA_TAG_LINK) {
+            let link = link.to_str()?;
+            let parts: Vec<&str> = link.splitn(4, ',').collect();
+            let [id, source, target, link_type] = parts[..] else {
+                bail!("Invalid inter-graph link: {}", link);
+            };
+            let (source_graph, source_element) = source
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid link source: {}", source))?;
+            let (target_graph, target_element) = target
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid link target: {}", target))?;
+            self.tsgraph.links.push(InterGraphLink {
+                id: id.into(),
+                source_graph: source_graph.into(),
+                source_element: source_element.into(),
+                target_graph: target_graph.into(),
+                target_element: target_element.into(),
+                link_type: link_type.into(),
+                attributes: to_attribute_map(tags),
+            });
+            return Ok(());
+        }
+
+        for attr in tags {
+            if attr.tag == "VN" {
+                self.version = Some(if attr.value.starts_with(b"2") {
+                    GfaVersion::V2
+                } else {
+                    GfaVersion::V1
+                });
+            } else if attr.tag == GFA_TAG_HEADER {
+                let (tag, value) = attr
+                    .value
+                    .split_once_str("=")
+                    .ok_or_else(|| anyhow!("Invalid TSG header: {}", attr.value))?;
+                self.tsgraph.headers.push(Header {
+                    tag: tag.into(),
+                    value: value.into(),
+                });
+            } else {
+                self.tsgraph.headers.push(Header {
+                    tag: attr.tag,
+                    value: attr.value,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_segment(&mut self, fields: &[&str]) -> Result<()> {
+        // GFA 2.0 segments carry their length before the sequence
+        let version = self.version.unwrap_or_else(|| {
+            let is_v2 = fields.len() > 3
+                && fields[2].parse::<usize>().is_ok()
+                && fields[3].parse::<Attribute>().is_err();
+            if is_v2 {
+                GfaVersion::V2
+            } else {
+                GfaVersion::V1
+            }
+        });
+        let (name, sequence, tags) = match version {
+            GfaVersion::V1 if fields.len() >= 3 => (fields[1], fields[2], &fields[3..]),
+            GfaVersion::V2 if fields.len() >= 4 => (fields[1], fields[3], &fields[4..]),
+            _ => bail!("Segment has {} fields", fields.len()),
+        };
+
+        let mut tags = parse_tags(tags)?;
+        // The length is derived from the sequence or exons
+        take_tag(&mut tags, "LN");
+        let reference_id = take_tag(&mut tags, GFA_TAG_REFERENCE).unwrap_or_default();
+        let strand = match take_tag(&mut tags, GFA_TAG_STRAND) {
+            Some(strand) => strand.to_str()?.parse()?,
+            None => Default::default(),
+        };
+        let exons = match take_tag(&mut tags, GFA_TAG_EXONS) {
+            Some(exons) => exons.to_str()?.parse()?,
+            None => Default::default(),
+        };
+        let reads = match take_tag(&mut tags, GFA_TAG_READS) {
+            Some(reads) => reads
+                .to_str()?
+                .split(',')
+                .map(|read| read.parse())
+                .collect::<Result<Vec<ReadData>, _>>()?,
+            None => Vec::new(),
+        };
+
+        let graph = graph_mut(&mut self.tsgraph, &mut tags);
+        let id = local_name(graph, name);
+        if !self.segments.insert((graph.id.clone(), id.clone())) {
+            bail!("Duplicate segment {}", name);
+        }
+        graph.add_node(NodeData {
+            id,
+            reference_id,
+            strand,
+            exons,
+            reads,
+            sequence: (sequence != "*").then(|| sequence.into()),
+            attributes: to_attribute_map(tags),
+        })?;
+        Ok(())
+    }
+
+    /// Add a directed edge between two oriented segments
+    ///
+    /// A reverse-to-reverse edge is the same adjacency as the forward edge from
+    /// target to source; other reverse orientations cannot be kept.
+    fn add_edge(
+        &mut self,
+        id: Option<&str>,
+        source: (&str, Orientation),
+        target: (&str, Orientation),
+        mut tags: Vec<Attribute>,
+    ) -> Result<()> {
+        let (source, target) = match (source.1, target.1) {
+            (Orientation::Forward, Orientation::Forward) => (source.0, target.0),
+            (Orientation::Reverse, Orientation::Reverse) => (target.0, source.0),
+            _ => {
+                warn!(
+                    "Dropping the orientation of the edge from {} to {}",
+                    source.0, target.0
+                );
+                (source.0, target.0)
+            }
+        };
+        let sv = match take_tag(&mut tags, GFA_TAG_SV) {
+            Some(sv) => sv.to_str()?.parse()?,
+            None => Default::default(),
+        };
+
+        let graph = graph_mut(&mut self.tsgraph, &mut tags);
+        let id = match id {
+            Some(id) => local_name(graph, id),
+            None => {
+                let mut count = graph.edge_indices().len() + 1;
+                while graph
+                    .edge_indices()
+                    .contains_key(format!("e{}", count).as_bytes())
+                {
+                    count += 1;
+                }
+                format!("e{}", count).into()
+            }
+        };
+        if graph.edge_indices().contains_key(&id) {
+            bail!("Duplicate edge {}", id);
+        }
+        let (source, target) = (local_name(graph, source), local_name(graph, target));
+        graph.add_edge(
+            source.as_bstr(),
+            target.as_bstr(),
+            EdgeData {
+                id,
+                sv,
+                attributes: to_attribute_map(tags),
+            },
+        )?;
+        Ok(())
+    }
+
+    fn parse_link(&mut self, fields: &[&str]) -> Result<()> {
+        // L  <from>  <orient>  <to>  <orient>  <overlap>  [tags]
+        if fields.len() < 6 {
+            bail!("Link has {} fields", fields.len());
+        }
+        let source = (fields[1], parse_orientation(fields[2])?);
+        let target = (fields[3], parse_orientation(fields[4])?);
+        let mut tags = parse_tags(&fields[6..])?;
+        let id = take_tag(&mut tags, "ID").map(|id| id.to_string());
+        self.add_edge(id.as_deref(), source, target, tags)
+    }
+
+    fn parse_edge(&mut self, fields: &[&str]) -> Result<()> {
+        // E  <id>  <sid1>  <sid2>  <beg1>  <end1>  <beg2>  <end2>  <alignment>  [tags]
+        if fields.len() < 9 {
+            bail!("Edge has {} fields", fields.len());
+        }
+        let id = (fields[1] != "*").then_some(fields[1]);
+        let source = split_reference(fields[2])?;
+        let target = split_reference(fields[3])?;
+        let tags = parse_tags(&fields[9..])?;
+        self.add_edge(id, source, target, tags)
+    }
+
+    /// Add a path or chain from its references
+    ///
+    /// The `pe` tag, when present, replaces the references as it also lists edges.
+    fn add_ordered_group(
+        &mut self,
+        name: &str,
+        references: Vec<&str>,
+        mut tags: Vec<Attribute>,
+    ) -> Result<()> {
+        let is_chain = take_tag(&mut tags, GFA_TAG_GROUP_TYPE)
+            .is_some_and(|group_type| group_type == GFA_CHAIN_TYPE);
+        let path_elements = take_tag(&mut tags, GFA_TAG_PATH_ELEMENTS);
+        let references = match &path_elements {
+            Some(elements) => elements.to_str()?.split_whitespace().collect(),
+            None => references,
+        };
+
+        let graph = graph_mut(&mut self.tsgraph, &mut tags);
+        let id = local_name(graph, name);
+        let attributes = to_attribute_map(tags);
+        let group = if is_chain {
+            let elements = references
+                .iter()
+                .map(|reference| {
+                    let (name, _) =
+                        split_reference(reference).unwrap_or((reference, Orientation::Forward));
+                    local_name(graph, name)
+                })
+                .collect();
+            Group::Chain {
+                id: id.clone(),
+                elements,
+                attributes,
+            }
+        } else {
+            let elements = references
+                .iter()
+                .map(|reference| {
+                    let mut element = reference.parse::<OrientedElement>()?;
+                    element.id = local_name(graph, element.id.to_str()?);
+                    Ok(element)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Group::Ordered {
+                id: id.clone(),
+                elements,
+                attributes,
+            }
+        };
+        graph.add_group(id, group);
+        Ok(())
+    }
+
+    fn parse_path(&mut self, fields: &[&str]) -> Result<()> {
+        // P  <name>  <segments>  <overlaps>  [tags]
+        if fields.len() < 3 {
+            bail!("Path has {} fields", fields.len());
+        }
+        let references = fields[2].split(',').collect();
+        let tags = parse_tags(fields.get(4..).unwrap_or_default())?;
+        self.add_ordered_group(fields[1], references, tags)
+    }
+
+    fn parse_ordered_group(&mut self, fields: &[&str]) -> Result<()> {
+        // O  <name>  <references>  [tags]
+        if fields.len() < 3 {
+            bail!("Ordered group has {} fields", fields.len());
+        }
+        let references = fields[2].split_whitespace().collect();
+        let tags = parse_tags(&fields[3..])?;
+        self.add_ordered_group(fields[1], references, tags)
+    }
+
+    fn parse_unordered_group(&mut self, fields: &[&str]) -> Result<()> {
+        // U  <name>  <ids>  [tags]
+        if fields.len() < 3 {
+            bail!("Unordered group has {} fields", fields.len());
+        }
+        let mut tags = parse_tags(&fields[3..])?;
+        let graph = graph_mut(&mut self.tsgraph, &mut tags);
+        let id = local_name(graph, fields[1]);
+        let elements = fields[2]
+            .split_whitespace()
+            .map(|element| local_name(graph, element))
+            .collect();
+        graph.add_group(
+            id.clone(),
+            Group::Unordered {
+                id,
+                elements,
+                attributes: to_attribute_map(tags),
+            },
+        );
+        Ok(())
+    }
+
+    /// Check references and drop an unused default graph
+    fn finish(mut self) -> Result<TSGraph> {
+        for graph in self.tsgraph.graphs.values() {
+            for group in graph.groups().values() {
+                if let Group::Ordered { id, elements, .. } = group
+                    && let Some(element) = elements
+                        .iter()
+                        .find(|element| !graph.contains_element(&element.id))
+                {
+                    bail!("Path {} references unknown element {}", id, element.id);
+                }
+            }
+        }
+
+        if let Some(default_graph) = self.tsgraph.graph(DEFAULT_GRAPH_ID)
+            && default_graph.node_indices().is_empty()
+        {
+            self.tsgraph
+                .graphs
+                .shift_remove(&BString::from(DEFAULT_GRAPH_ID));
+        }
+        Ok(self.tsgraph)
+    }
+}
+
+/// The graph named by the `gr` tag, or the default graph, created on first use
+fn graph_mut<'a>(tsgraph: &'a mut TSGraph, tags: &mut Vec<Attribute>) -> &'a mut GraphSection {
+    let id = take_tag(tags, GFA_TAG_GRAPH).unwrap_or_else(|| DEFAULT_GRAPH_ID.into());
+    if !tsgraph.graphs.contains_key(&id) {
+        tsgraph.add_graph(GraphSection::new(id.clone()));
+    }
+    tsgraph.graphs.get_mut(&id).unwrap()
+}
+
+/// Name of an element within its graph, without the `<graph_id>:` prefix added on export
+fn local_name(graph: &GraphSection, name: &str) -> BString {
+    name.strip_prefix(graph.id.to_str().unwrap_or_default())
+        .and_then(|rest| rest.strip_prefix(':'))
+        .unwrap_or(name)
+        .into()
+}
+
+/// Read a TSGraph from GFA 1.0 or GFA 2.0
+///
+/// Segments become nodes, links and edges become edges, paths and ordered groups become
+/// paths (or chains when tagged as such) and unordered groups become sets. The tags written
+/// by [`write_gfa`] are mapped back onto the TSG fields, so a graph exported with
+/// [`TSGraph::to_gfa`] is read back unchanged. Other tags are kept as attributes.
+pub fn read_gfa<R: BufRead>(reader: R) -> Result<TSGraph> {
+    let mut gfa = GfaReader::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            gfa.tsgraph.comments.push(comment.into());
+            continue;
+        }
+        gfa.parse_line(&line)
+            .with_context(|| format!("Invalid GFA record at line {}", idx + 1))?;
+    }
+    gfa.finish()
+}
+
+impl TSGraph {
+    /// Convert the graph to GFA 1.0 or GFA 2.0 text
+    pub fn to_gfa(&self, version: GfaVersion) -> Result<String> {
+        let mut buf = Vec::new();
+        write_gfa(self, &mut buf, version)?;
+        Ok(String::from_utf8(buf)?)
+    }
+
+    /// Parse a GFA 1.0 or GFA 2.0 stream, see [`read_gfa`]
+    pub fn from_gfa<R: BufRead>(reader: R) -> Result<Self> {
+        read_gfa(reader)
+    }
+
+    /// Parse a GFA file, which may be compressed, see [`read_gfa`]
+    pub fn from_gfa_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        read_gfa(open_reader(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_gfa1() -> Result<()> {
+        let tsg = TSGraph::from_file("tests/data/test.tsg")?;
+        let gfa = tsg.to_gfa(GfaVersion::V1)?;
+        let lines: Vec<&str> = gfa.lines().collect();
+
+        assert_eq!(lines[0], "H\tVN:Z:1.0");
+        assert_eq!(lines.iter().filter(|l| l.starts_with("S\t")).count(), 5);
+        assert_eq!(lines.iter().filter(|l| l.starts_with("L\t")).count(), 4);
+        assert!(!lines.iter().any(|l| l.starts_with("U\t")));
+
+        let segment = lines.iter().find(|l| l.starts_with("S\tn1\t")).unwrap();
+        assert!(segment.contains("\trf:Z:chr1\tsd:A:+\tex:Z:1000-1200,1500-1700"));
+        assert!(segment.contains("\trd:Z:read1:SO,read2:SO"));
+
+        let path = lines.iter().find(|l| l.starts_with("P\t")).unwrap();
+        let fields: Vec<&str> = path.split('\t').collect();
+        assert!(!fields[2].contains('e'), "GFA 1.0 paths only list segments");
+        assert!(path.contains("\tpe:Z:"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_gfa2() -> Result<()> {
+        let tsg = TSGraph::from_file("tests/data/test_mul.tsg")?;
+        let gfa = tsg.to_gfa(GfaVersion::V2)?;
+        let lines: Vec<&str> = gfa.lines().collect();
+
+        assert_eq!(lines[0], "H\tVN:Z:2.0");
+        assert!(lines.contains(&"H\tgr:Z:gene_a\tlocus:Z:chr17q21.31\tname:Z:BRCA1"));
+        assert!(lines.iter().any(|l| l.starts_with("H\tlk:Z:")));
+
+        // element names are qualified by their graph when there are several graphs
+        let edge = lines
+            .iter()
+            .find(|l| l.starts_with("E\tgene_a:e1\t"))
+            .unwrap();
+        assert!(edge.starts_with("E\tgene_a:e1\tgene_a:n1+\tgene_a:n2+\t8$\t8$\t0\t0\t*"));
+        assert!(edge.contains("\tgr:Z:gene_a\tsv:Z:chr17,chr17,41196402,41199660,splice"));
+
+        assert_eq!(lines.iter().filter(|l| l.starts_with("O\t")).count(), 4);
+        assert_eq!(lines.iter().filter(|l| l.starts_with("U\t")).count(), 2);
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.starts_with("O\tgene_b:chain1\t") && l.contains("\tty:Z:chain"))
+        );
+        Ok(())
+    }
+
+    fn text(tsg: &TSGraph) -> Result<String> {
+        let mut buf = Vec::new();
+        tsg.to_writer(&mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+
+    #[test]
+    fn test_gfa_roundtrip() -> Result<()> {
+        for file in [
+            "tests/data/test.tsg",
+            "tests/data/test_mul.tsg",
+            "tests/data/AR_in_40_2.tsg",
+            "tests/data/sampling_46.tsg",
+            "tests/data/scannls.tsg",
+        ] {
+            let tsg = TSGraph::from_file(file)?;
+            let gfa = tsg.to_gfa(GfaVersion::V2)?;
+            let imported = TSGraph::from_gfa(gfa.as_bytes())?;
+            assert_eq!(text(&imported)?, text(&tsg)?, "{}", file);
+        }
+
+        // GFA 1.0 has no unordered groups
+        let mut tsg = TSGraph::from_file("tests/data/test.tsg")?;
+        let graph = tsg.default_graph_mut().unwrap();
+        let unordered: Vec<String> = graph
+            .groups()
+            .iter()
+            .filter(|(_, group)| matches!(group, Group::Unordered { .. }))
+            .map(|(id, _)| id.to_string())
+            .collect();
+        for id in unordered {
+            graph.remove_group(&id);
+        }
+        let gfa = tsg.to_gfa(GfaVersion::V1)?;
+        let imported = TSGraph::from_gfa(gfa.as_bytes())?;
+        assert_eq!(text(&imported)?, text(&tsg)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_gfa1() -> Result<()> {
+        let gfa = "H\tVN:Z:1.0\n\
+                   S\ts1\tACGT\tRC:i:5\n\
+                   S\ts2\t*\tLN:i:100\n\
+                   S\ts3\tGG\n\
+                   L\ts1\t+\ts2\t+\t0M\n\
+                   L\ts3\t-\ts2\t-\t0M\tID:Z:l2\n\
+                   P\tp1\ts1+,s2+\t*\n";
+        let tsg = TSGraph::from_gfa(gfa.as_bytes())?;
+        let graph = tsg.default_graph().unwrap();
+
+        assert_eq!(graph.node_indices().len(), 3);
+        let s1 = graph.node_by_id("s1").unwrap();
+        assert_eq!(s1.sequence.as_ref().unwrap(), "ACGT");
+        assert_eq!(s1.attributes[&BString::from("RC")].value, "5");
+        assert!(graph.node_by_id("s2").unwrap().sequence.is_none());
+        assert!(graph.node_by_id("s2").unwrap().attributes.is_empty());
+
+        // unnamed links are numbered, reverse-reverse links are flipped
+        assert!(graph.edge_by_id("e1").is_some());
+        let (source, target) = graph
+            .inner_graph()
+            .edge_endpoints(graph.edge_indices()[&BString::from("l2")])
+            .unwrap();
+        assert_eq!(graph.find_node_id_by_idx(source).unwrap(), "s2");
+        assert_eq!(graph.find_node_id_by_idx(target).unwrap(), "s3");
+
+        assert!(matches!(
+            &graph.groups()[&BString::from("p1")],
+            Group::Ordered { elements, .. } if elements.len() == 2
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_gfa_roundtrip() -> Result<()> {
+        // Segments of a third-party GFA have no reference, strand or exons
+        let gfa = "H\tVN:Z:1.0\n\
+                   S\ts1\tACGT\n\
+                   S\ts2\tTTGA\n\
+                   L\ts1\t+\ts2\t+\t0M\n\
+                   P\tp1\ts1+,s2+\t*\n";
+        let tsg = TSGraph::from_gfa(gfa.as_bytes())?;
+        let written = text(&tsg)?;
+        assert!(written.contains("N\ts1\t:+:\t\tACGT\n"));
+
+        let reparsed = TSGraph::from_reader(written.as_bytes())?;
+        assert_eq!(text(&reparsed)?, written);
+        let s2 = reparsed.default_graph().unwrap().node_by_id("s2").unwrap();
+        assert!(s2.reference_id.is_empty() && s2.exons.is_empty());
+        assert_eq!(s2.sequence.as_ref().unwrap(), "TTGA");
+
+        let exported = reparsed.to_gfa(GfaVersion::V1)?;
+        let imported = TSGraph::from_gfa(exported.as_bytes())?;
+        assert_eq!(text(&imported)?, written);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_gfa_errors() {
+        assert!(TSGraph::from_gfa("S\ts1\tACGT\nP\tp1\ts1+,s9+\t*\n".as_bytes()).is_err());
+        assert!(TSGraph::from_gfa("S\ts1\tACGT\nS\ts1\tACGT\n".as_bytes()).is_err());
+        assert!(TSGraph::from_gfa("L\ts1\tx\ts2\t+\t0M\n".as_bytes()).is_err());
+    }
+}
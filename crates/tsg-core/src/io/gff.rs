@@ -0,0 +1,235 @@
+use crate::graph::{GraphSection, Strand, TSGraph, WriteOptions, gff3_escape, gff3_record};
+use anyhow::Result;
+use bstr::{BString, ByteSlice};
+use std::io::Write;
+use tracing::warn;
+
+/// Directive that starts every GFF3 file
+pub const GFF3_HEADER: &str = "##gff-version 3";
+
+pub fn to_gff3<W: Write>(tsg_graph: &TSGraph, writer: &mut W) -> Result<()> {
+    to_gff3_with_options(tsg_graph, writer, &WriteOptions::default())
+}
+
+/// Write the paths of all graphs as GFF3, in the order given by `options`
+pub fn to_gff3_with_options<W: Write>(
+    tsg_graph: &TSGraph,
+    writer: &mut W,
+    options: &WriteOptions,
+) -> Result<()> {
+    writeln!(writer, "{}", GFF3_HEADER)?;
+    for graph in tsg_graph.ordered_graphs(options.ordering) {
+        graph_to_gff3_with_options(graph, writer, options)?;
+    }
+    Ok(())
+}
+
+/// Write the paths of a single graph section as GFF3 records, without the version directive
+pub fn graph_to_gff3<W: Write>(graph: &GraphSection, writer: &mut W) -> Result<()> {
+    graph_to_gff3_with_options(graph, writer, &WriteOptions::default())
+}
+
+/// Write the paths of a single graph section as GFF3 records, in the order given by `options`
+///
+/// The graph section becomes a `gene` identified by the graph ID and carrying the graph
+/// attributes; it takes the reference and strand of its first path and spans all paths.
+/// Each path becomes an `mRNA` with its `exon`s, see
+/// [`TSGPath::to_gff3`](crate::graph::TSGPath::to_gff3). Paths on another reference or
+/// strand than the gene, or whose nodes change reference or strand, cannot be written
+/// under it and are skipped with a warning.
+pub fn graph_to_gff3_with_options<W: Write>(
+    graph: &GraphSection,
+    writer: &mut W,
+    options: &WriteOptions,
+) -> Result<()> {
+    let paths = graph.traverse_with_ordering(options.ordering)?;
+    let mut located: Vec<(BString, (BString, usize, usize, Strand))> =
+        Vec::with_capacity(paths.len());
+    for path in &paths {
+        let (records, location) = match path
+            .location()
+            .and_then(|location| Ok((path.to_gff3(graph.id.as_bstr())?, location)))
+        {
+            Ok(located) => located,
+            Err(e) => {
+                warn!("Skipping path in graph {}: {}", graph.id, e);
+                continue;
+            }
+        };
+        if let Some((_, (reference_id, _, _, strand))) = located.first()
+            && (&location.0 != reference_id || &location.3 != strand)
+        {
+            warn!(
+                "Skipping path {} in graph {}: it is on {}:{}, but gene {} is on {}:{}",
+                path.id()?,
+                graph.id,
+                location.0,
+                location.3,
+                graph.id,
+                reference_id,
+                strand
+            );
+            continue;
+        }
+        located.push((records, location));
+    }
+    let Some((_, (reference_id, _, _, strand))) = located.first() else {
+        return Ok(());
+    };
+
+    let (start, end) = located
+        .iter()
+        .fold((usize::MAX, 0), |(start, end), (_, location)| {
+            (start.min(location.1), end.max(location.2))
+        });
+
+    let mut attributes = vec![("ID".to_string(), gff3_escape(&graph.id))];
+    let mut graph_attributes: Vec<_> = graph.attributes.values().collect();
+    graph_attributes.sort_by(|a, b| a.tag.cmp(&b.tag));
+    attributes.extend(
+        graph_attributes
+            .into_iter()
+            .map(|attr| (attr.tag.to_string(), gff3_escape(&attr.value))),
+    );
+    writeln!(
+        writer,
+        "{}",
+        gff3_record(
+            reference_id.as_bstr(),
+            "gene",
+            start,
+            end,
+            *strand,
+            &attributes
+        )
+    )?;
+
+    for (records, _) in located {
+        writeln!(writer, "{}", records)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_to_gff3() -> Result<()> {
+        let tsg_graph = TSGraph::from_file("tests/data/test_mul.tsg")?;
+        let mut buf = Vec::new();
+        to_gff3(&tsg_graph, &mut buf)?;
+        let gff3 = String::from_utf8(buf)?;
+        let lines: Vec<&str> = gff3.lines().collect();
+
+        assert_eq!(lines[0], GFF3_HEADER);
+        assert_eq!(
+            lines[1],
+            "chr17\ttsg\tgene\t41196312\t41203134\t.\t+\t.\tID=gene_a;locus=chr17q21.31;name=BRCA1"
+        );
+
+        let mrna: Vec<&str> = lines[2].split('\t').collect();
+        assert_eq!(mrna[2], "mRNA");
+        assert_eq!((mrna[3], mrna[4], mrna[6]), ("41196312", "41203134", "+"));
+        assert!(mrna[8].contains(";Parent=gene_a;"));
+        assert!(mrna[8].contains(";edge_ids=e1,e2;sv_types=splice,splice;"));
+        let mrna_id = mrna[8]
+            .split(';')
+            .next()
+            .unwrap()
+            .strip_prefix("ID=")
+            .unwrap();
+
+        let exons: Vec<&&str> = lines
+            .iter()
+            .filter(|line| line.contains(&format!("Parent={}", mrna_id)))
+            .collect();
+        assert_eq!(exons.len(), 3);
+        assert!(exons[0].starts_with("chr17\ttsg\texon\t41196312\t41196402\t.\t+\t.\t"));
+
+        // each ID is defined once
+        let mut ids: Vec<&str> = lines[1..]
+            .iter()
+            .filter_map(|line| line.split('\t').nth(8)?.split(';').next())
+            .collect();
+        let count = ids.len();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), count);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_gff3_skips_paths_off_the_gene() -> Result<()> {
+        let tsg_graph = TSGraph::from_str(
+            "N\ta\tchr1:+:100-200\tr1:SO\n\
+             N\tb\tchr1:+:300-400\tr1:SI\n\
+             N\tc\tchr2:-:5000-6000\tr2:SO\n\
+             N\td\tchr2:-:700-800\tr2:SI\n\
+             E\te1\ta\tb\tchr1,chr1,200,300,splice\n\
+             E\te2\tc\td\tchr2,chr2,5000,800,splice\n",
+        )?;
+        let mut buf = Vec::new();
+        to_gff3(&tsg_graph, &mut buf)?;
+        let gff3 = String::from_utf8(buf)?;
+        let lines: Vec<&str> = gff3.lines().collect();
+
+        let gene: Vec<&str> = lines[1].split('\t').collect();
+        assert_eq!(gene[2], "gene");
+        let mrnas: Vec<Vec<&str>> = lines
+            .iter()
+            .map(|line| line.split('\t').collect::<Vec<_>>())
+            .filter(|fields| fields.get(2) == Some(&"mRNA"))
+            .collect();
+        assert_eq!(mrnas.len(), 1);
+        for mrna in &mrnas {
+            assert_eq!((mrna[0], mrna[6]), (gene[0], gene[6]));
+            assert!(mrna[3].parse::<usize>()? >= gene[3].parse()?);
+            assert!(mrna[4].parse::<usize>()? <= gene[4].parse()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_gff3_exons_follow_their_mrna() -> Result<()> {
+        let tsg_graph = TSGraph::from_file("tests/data/test.tsg")?;
+        let mut buf = Vec::new();
+        to_gff3(&tsg_graph, &mut buf)?;
+        let gff3 = String::from_utf8(buf)?;
+        let records: Vec<Vec<&str>> = gff3
+            .lines()
+            .skip(1)
+            .map(|line| line.split('\t').collect())
+            .collect();
+
+        let mrnas: Vec<_> = records
+            .iter()
+            .filter(|fields| fields[2] == "mRNA")
+            .collect();
+        assert!(!mrnas.is_empty());
+        for mrna in mrnas {
+            let id = mrna[8]
+                .split(';')
+                .next()
+                .unwrap()
+                .strip_prefix("ID=")
+                .unwrap();
+            let parent = format!(";Parent={};", id);
+            let exons: Vec<_> = records
+                .iter()
+                .filter(|fields| fields[2] == "exon" && fields[8].contains(&parent))
+                .collect();
+            assert!(!exons.is_empty());
+            let mut blocks = Vec::new();
+            for exon in exons {
+                assert_eq!((exon[0], exon[6]), (mrna[0], mrna[6]));
+                blocks.push((exon[3].parse::<usize>()?, exon[4].parse::<usize>()?));
+            }
+            // The n3 and n5 segments on 2500-2700 make a single exon
+            blocks.sort_unstable();
+            assert!(blocks.windows(2).all(|pair| pair[0].1 + 1 < pair[1].0));
+        }
+        Ok(())
+    }
+}
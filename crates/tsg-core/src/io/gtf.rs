@@ -1,10 +1,44 @@
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow, bail};
+use bstr::{BString, ByteSlice};
 
-use crate::graph::TSGraph;
-use std::io::Write;
+use super::open_reader;
+use crate::graph::{
+    Attribute, EdgeData, Exons, GraphSection, Group, Interval, NodeData, Orientation,
+    OrientedElement, ReadData, ReadIdentity, Strand, StructuralVariant, TSGraph, WriteOptions,
+};
+use ahash::{HashMap, HashMapExt};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
 
 pub fn to_gtf<W: Write>(tsg_graph: &TSGraph, writer: &mut W) -> Result<()> {
-    let paths = tsg_graph.traverse_all_graphs()?;
+    to_gtf_with_options(tsg_graph, writer, &WriteOptions::default())
+}
+
+/// Write the paths of all graphs as GTF records, in the order given by `options`
+pub fn to_gtf_with_options<W: Write>(
+    tsg_graph: &TSGraph,
+    writer: &mut W,
+    options: &WriteOptions,
+) -> Result<()> {
+    for graph in tsg_graph.ordered_graphs(options.ordering) {
+        graph_to_gtf_with_options(graph, writer, options)?;
+    }
+    Ok(())
+}
+
+/// Write the paths of a single graph section as GTF records
+pub fn graph_to_gtf<W: Write>(graph: &GraphSection, writer: &mut W) -> Result<()> {
+    graph_to_gtf_with_options(graph, writer, &WriteOptions::default())
+}
+
+/// Write the paths of a single graph section as GTF records, in the order given by `options`
+pub fn graph_to_gtf_with_options<W: Write>(
+    graph: &GraphSection,
+    writer: &mut W,
+    options: &WriteOptions,
+) -> Result<()> {
+    let paths = graph.traverse_with_ordering(options.ordering)?;
     for path in paths {
         let seq = path.to_gtf()?;
         writeln!(writer, "{}", seq)?;
@@ -12,6 +46,384 @@ pub fn to_gtf<W: Write>(tsg_graph: &TSGraph, writer: &mut W) -> Result<()> {
     Ok(())
 }
 
+/// A GTF or GFF3 feature line
+struct Feature {
+    seqid: BString,
+    kind: String,
+    start: usize,
+    end: usize,
+    strand: Strand,
+    attributes: HashMap<String, String>,
+}
+
+/// Decode the `%XX` escapes of a GFF3 attribute or VCF INFO value
+pub(super) fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%'
+            && let Some(byte) = value
+                .get(idx + 1..idx + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            idx += 3;
+            continue;
+        }
+        decoded.push(bytes[idx]);
+        idx += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parse the attribute column of a GTF (`key "value";`) or GFF3 (`key=value;`) line
+fn parse_feature_attributes(column: &str) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    for item in column
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let gff3 = item.split_once('=');
+        let gtf = item.split_once(char::is_whitespace);
+        match (gff3, gtf) {
+            // GTF values may contain `=`, GFF3 keys never contain spaces
+            (Some((key, value)), Some((gtf_key, _))) if key.len() < gtf_key.len() => {
+                attributes.insert(key.to_string(), percent_decode(value));
+            }
+            (Some((key, value)), None) => {
+                attributes.insert(key.to_string(), percent_decode(value));
+            }
+            (_, Some((key, value))) => {
+                attributes.insert(key.to_string(), value.trim().trim_matches('"').to_string());
+            }
+            (None, None) => {}
+        }
+    }
+    attributes
+}
+
+impl Feature {
+    fn parse(line: &str) -> Result<Self> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 9 {
+            bail!("expected 9 fields, found {}", fields.len());
+        }
+        let strand = match fields[6] {
+            "-" => Strand::Reverse,
+            _ => Strand::Forward,
+        };
+        Ok(Self {
+            seqid: fields[0].into(),
+            kind: fields[2].to_string(),
+            start: fields[3].parse().context("invalid start")?,
+            end: fields[4].parse().context("invalid end")?,
+            strand,
+            attributes: parse_feature_attributes(fields[8]),
+        })
+    }
+
+    fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes.get(key).map(String::as_str)
+    }
+}
+
+/// An annotated transcript and its exons
+struct Transcript {
+    id: BString,
+    gene_id: BString,
+    gene_name: Option<BString>,
+    seqid: BString,
+    strand: Strand,
+    exons: Vec<(usize, usize)>,
+}
+
+/// Group exon features into transcripts
+///
+/// GTF exons name their transcript and gene directly. GFF3 exons point to their
+/// transcript through `Parent`, which in turn points to its gene.
+fn collect_transcripts(features: &[Feature]) -> Vec<Transcript> {
+    let mut by_id: HashMap<&str, &Feature> = HashMap::new();
+    let mut gene_names: HashMap<&str, &str> = HashMap::new();
+    let mut exons: Vec<(&str, &Feature)> = Vec::new();
+
+    for feature in features {
+        if feature.kind == "exon" {
+            if let Some(transcript_id) = feature.attribute("transcript_id") {
+                exons.push((transcript_id, feature));
+            } else if let Some(parents) = feature.attribute("Parent") {
+                exons.extend(parents.split(',').map(|parent| (parent, feature)));
+            }
+            continue;
+        }
+        if let (Some(gene_id), Some(gene_name)) =
+            (feature.attribute("gene_id"), feature.attribute("gene_name"))
+        {
+            gene_names.insert(gene_id, gene_name);
+        }
+        if let Some(id) = feature.attribute("ID") {
+            by_id.insert(id, feature);
+        }
+    }
+
+    let mut transcripts: Vec<Transcript> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for (parent, exon) in exons {
+        let position = *index.entry(parent).or_insert_with(|| {
+            let transcript = by_id.get(parent).copied();
+            let gene_feature = transcript
+                .and_then(|t| t.attribute("Parent"))
+                .and_then(|gene| by_id.get(gene).copied());
+            let lookup = |key: &str| {
+                exon.attribute(key)
+                    .or_else(|| transcript.and_then(|t| t.attribute(key)))
+                    .or_else(|| gene_feature.and_then(|g| g.attribute(key)))
+            };
+
+            let id = lookup("transcript_id").unwrap_or(parent);
+            let gene_id = lookup("gene_id")
+                .or_else(|| transcript.and_then(|t| t.attribute("Parent")))
+                .unwrap_or(id);
+            let gene_name = lookup("gene_name")
+                .or_else(|| gene_feature.and_then(|g| g.attribute("Name")))
+                .or_else(|| gene_names.get(gene_id).copied());
+
+            transcripts.push(Transcript {
+                id: id.into(),
+                gene_id: gene_id.into(),
+                gene_name: gene_name.map(Into::into),
+                seqid: exon.seqid.clone(),
+                strand: exon.strand,
+                exons: Vec::new(),
+            });
+            transcripts.len() - 1
+        });
+        transcripts[position].exons.push((exon.start, exon.end));
+    }
+    transcripts
+}
+
+/// Build the graph section of one gene from its transcripts
+///
+/// Exons shared by several transcripts become a single node, supported by
+/// those transcripts as reads. Edges connect consecutive exons in transcription order.
+fn gene_to_graph(gene_id: &BString, transcripts: &[&Transcript]) -> Result<GraphSection> {
+    let mut graph = GraphSection::new(gene_id.clone());
+    graph.attributes.insert(
+        "gene_id".into(),
+        Attribute::builder()
+            .tag("gene_id")
+            .value(gene_id.clone())
+            .build(),
+    );
+    if let Some(gene_name) = transcripts.iter().find_map(|t| t.gene_name.as_ref()) {
+        graph.attributes.insert(
+            "gene_name".into(),
+            Attribute::builder()
+                .tag("gene_name")
+                .value(gene_name.clone())
+                .build(),
+        );
+    }
+
+    // Transcript exons in transcription order
+    let ordered_exons: Vec<Vec<(usize, usize)>> = transcripts
+        .iter()
+        .map(|transcript| {
+            let mut exons = transcript.exons.clone();
+            exons.sort_unstable();
+            exons.dedup();
+            if transcript.strand == Strand::Reverse {
+                exons.reverse();
+            }
+            exons
+        })
+        .collect();
+
+    // Unique exons, numbered by genomic position
+    let mut exon_reads: BTreeMap<(&BString, Strand, usize, usize), Vec<ReadData>> = BTreeMap::new();
+    for (transcript, exons) in transcripts.iter().zip(&ordered_exons) {
+        for (idx, &(start, end)) in exons.iter().enumerate() {
+            let identity = if idx == 0 {
+                ReadIdentity::SO
+            } else if idx + 1 == exons.len() {
+                ReadIdentity::SI
+            } else {
+                ReadIdentity::IN
+            };
+            exon_reads
+                .entry((&transcript.seqid, transcript.strand, start, end))
+                .or_default()
+                .push(ReadData {
+                    id: transcript.id.clone(),
+                    identity,
+                });
+        }
+    }
+
+    let mut node_ids = HashMap::new();
+    for (idx, ((seqid, strand, start, end), reads)) in exon_reads.into_iter().enumerate() {
+        let id: BString = format!("n{}", idx + 1).into();
+        node_ids.insert((seqid, strand, start, end), id.clone());
+        graph.add_node(NodeData {
+            id,
+            reference_id: seqid.clone(),
+            strand,
+            exons: Exons {
+                exons: vec![Interval { start, end }],
+            },
+            reads,
+            ..Default::default()
+        })?;
+    }
+
+    // Introns between consecutive exons, one edge per pair of nodes
+    let mut introns: BTreeMap<(&BString, &BString), StructuralVariant> = BTreeMap::new();
+    let mut transcript_nodes = Vec::with_capacity(transcripts.len());
+    for (transcript, exons) in transcripts.iter().zip(&ordered_exons) {
+        let nodes: Vec<&BString> = exons
+            .iter()
+            .map(|&(start, end)| &node_ids[&(&transcript.seqid, transcript.strand, start, end)])
+            .collect();
+        for (pair, nodes) in exons.windows(2).zip(nodes.windows(2)) {
+            let (donor, acceptor) = match transcript.strand {
+                Strand::Forward => (pair[0].1, pair[1].0),
+                Strand::Reverse => (pair[0].0, pair[1].1),
+            };
+            introns
+                .entry((nodes[0], nodes[1]))
+                .or_insert_with(|| StructuralVariant {
+                    reference_name1: transcript.seqid.clone(),
+                    reference_name2: transcript.seqid.clone(),
+                    breakpoint1: donor,
+                    breakpoint2: acceptor,
+                    sv_type: "splice".into(),
+                });
+        }
+        transcript_nodes.push(nodes);
+    }
+
+    let mut edge_ids = HashMap::new();
+    for (idx, ((source, target), sv)) in introns.into_iter().enumerate() {
+        let id: BString = format!("e{}", idx + 1).into();
+        edge_ids.insert((source.clone(), target.clone()), id.clone());
+        graph.add_edge(
+            source.as_bstr(),
+            target.as_bstr(),
+            EdgeData {
+                id,
+                sv,
+                ..Default::default()
+            },
+        )?;
+    }
+
+    for (transcript, nodes) in transcripts.iter().zip(transcript_nodes) {
+        let mut elements: Vec<BString> = Vec::with_capacity(nodes.len() * 2);
+        for (idx, node) in nodes.iter().enumerate() {
+            if idx > 0 {
+                elements.push(edge_ids[&(nodes[idx - 1].clone(), (*node).clone())].clone());
+            }
+            elements.push((*node).clone());
+        }
+
+        let mut attributes = HashMap::new();
+        for (tag, value) in [
+            ("transcript_id", Some(&transcript.id)),
+            ("gene_id", Some(&transcript.gene_id)),
+            ("gene_name", transcript.gene_name.as_ref()),
+        ] {
+            if let Some(value) = value {
+                attributes.insert(
+                    tag.into(),
+                    Attribute::builder().tag(tag).value(value.clone()).build(),
+                );
+            }
+        }
+
+        let chain_id: BString = format!("{}_chain", transcript.id).into();
+        graph.add_group(
+            chain_id.clone(),
+            Group::Chain {
+                id: chain_id,
+                elements: elements.clone(),
+                attributes: attributes.clone(),
+            },
+        );
+        graph.add_group(
+            transcript.id.clone(),
+            Group::Ordered {
+                id: transcript.id.clone(),
+                elements: elements
+                    .into_iter()
+                    .map(|id| OrientedElement {
+                        id,
+                        orientation: Some(Orientation::Forward),
+                    })
+                    .collect(),
+                attributes,
+            },
+        );
+    }
+    Ok(graph)
+}
+
+/// Build a reference TSGraph from a GTF or GFF3 annotation
+///
+/// Each gene becomes a graph section named after its `gene_id`. Its unique exons become
+/// nodes, the introns observed between consecutive exons become edges with `splice`
+/// structural variants, and each transcript becomes a chain (`<transcript_id>_chain`) and
+/// a path (`<transcript_id>`) carrying `transcript_id`, `gene_id` and `gene_name` attributes.
+/// Coordinates are kept as written in the annotation. The format is detected per line.
+pub fn read_gtf<R: BufRead>(reader: R) -> Result<TSGraph> {
+    let mut features = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        // GFF3 may end with an embedded FASTA section
+        if line.starts_with("##FASTA") {
+            break;
+        }
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let feature = Feature::parse(&line)
+            .with_context(|| format!("Invalid annotation record at line {}", idx + 1))?;
+        features.push(feature);
+    }
+
+    let transcripts = collect_transcripts(&features);
+    let mut genes: Vec<&BString> = Vec::new();
+    let mut gene_transcripts: HashMap<&BString, Vec<&Transcript>> = HashMap::new();
+    for transcript in &transcripts {
+        let entry = gene_transcripts.entry(&transcript.gene_id).or_default();
+        if entry.is_empty() {
+            genes.push(&transcript.gene_id);
+        }
+        entry.push(transcript);
+    }
+
+    let mut tsgraph = TSGraph::default();
+    for gene_id in genes {
+        let graph = gene_to_graph(gene_id, &gene_transcripts[gene_id])
+            .map_err(|e| anyhow!("Failed to build graph for gene {}: {}", gene_id, e))?;
+        tsgraph.add_graph(graph);
+    }
+    Ok(tsgraph)
+}
+
+impl TSGraph {
+    /// Build a reference graph from a GTF or GFF3 stream, see [`read_gtf`]
+    pub fn from_gtf<R: BufRead>(reader: R) -> Result<Self> {
+        read_gtf(reader)
+    }
+
+    /// Build a reference graph from a GTF or GFF3 file, which may be compressed, see [`read_gtf`]
+    pub fn from_gtf_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        read_gtf(open_reader(path)?)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -19,10 +431,85 @@ mod tests {
     #[test]
     fn test_to_gtf() {
         let tsg_graph = TSGraph::from_file("tests/data/test.tsg").unwrap();
-        let output = "tests/data/test.gtf";
+        let output = std::env::temp_dir().join("tsg_gtf_test.gtf");
 
         let file = std::fs::File::create(output).unwrap();
         let mut writer = std::io::BufWriter::new(file);
         to_gtf(&tsg_graph, &mut writer).unwrap();
     }
+
+    #[test]
+    fn test_to_gtf_transcript_location() -> Result<()> {
+        let tsg_graph = TSGraph::from_file("tests/data/test_mul.tsg")?;
+        let mut buf = Vec::new();
+        to_gtf(&tsg_graph, &mut buf)?;
+        let gtf = String::from_utf8(buf)?;
+
+        let transcripts: Vec<&str> = gtf
+            .lines()
+            .filter(|line| line.split('\t').nth(2) == Some("transcript"))
+            .collect();
+        assert_eq!(transcripts.len(), 2);
+        assert!(
+            transcripts[0].starts_with("chr17\ttsg\ttranscript\t41196312\t41203134\t.\t+\t.\t")
+        );
+        assert!(
+            transcripts[1].starts_with("chr13\ttsg\ttranscript\t32315480\t32319325\t.\t+\t.\t")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_gtf() -> Result<()> {
+        let tsg = TSGraph::from_gtf_file("tests/data/annotation.gtf")?;
+        assert_eq!(tsg.graphs.len(), 2);
+
+        let gene = tsg.graph("G1").unwrap();
+        assert_eq!(gene.attributes[&BString::from("gene_name")].value, "GENE1");
+        assert_eq!(gene.node_indices().len(), 3);
+        assert_eq!(gene.edge_indices().len(), 3);
+        assert_eq!(gene.chains().len(), 2);
+
+        let first = gene.node_by_id("n1").unwrap();
+        assert_eq!(first.exons.to_string(), "100-200");
+        assert_eq!(first.reads.len(), 2);
+        let skip = gene
+            .edges()
+            .into_iter()
+            .find(|edge| edge.sv.breakpoint1 == 200 && edge.sv.breakpoint2 == 500)
+            .unwrap();
+        assert_eq!(skip.sv.sv_type, "splice");
+
+        match &gene.groups()[&BString::from("T2")] {
+            Group::Ordered {
+                elements,
+                attributes,
+                ..
+            } => {
+                assert_eq!(elements.len(), 3);
+                assert_eq!(attributes[&BString::from("transcript_id")].value, "T2");
+            }
+            _ => panic!("T2 is not a path"),
+        }
+
+        // minus strand transcripts run from the last exon to the first
+        let gene = tsg.graph("G2").unwrap();
+        let edge = gene.edges()[0];
+        assert_eq!((edge.sv.breakpoint1, edge.sv.breakpoint2), (1200, 1100));
+        assert_eq!(gene.traverse()?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_gff3() -> Result<()> {
+        let gtf = TSGraph::from_gtf_file("tests/data/annotation.gtf")?;
+        let gff3 = TSGraph::from_gtf_file("tests/data/annotation.gff3")?;
+
+        let mut expected = Vec::new();
+        gtf.to_writer(&mut expected)?;
+        let mut actual = Vec::new();
+        gff3.to_writer(&mut actual)?;
+        assert_eq!(actual.as_bstr(), expected.as_bstr());
+        Ok(())
+    }
 }
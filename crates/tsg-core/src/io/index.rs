@@ -0,0 +1,545 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow, bail};
+use bstr::{BString, ByteSlice};
+use noodles::bgzf;
+
+use super::{Compression, create_writer, open_reader};
+use crate::graph::{DEFAULT_GRAPH_ID, GraphSection, Header, InterGraphLink, ParseOptions, TSGraph};
+
+/// First line of every index file, followed by the format version
+const INDEX_MAGIC: &str = "#tsi";
+const INDEX_VERSION: u32 = 2;
+
+/// Extension appended to a TSG file name to locate its index
+pub const INDEX_EXTENSION: &str = "tsi";
+
+/// Byte range of a block of records in a TSG file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Byte offset of the first record, or its BGZF virtual position in a BGZF file
+    pub offset: u64,
+    /// Number of uncompressed bytes spanned by the block
+    pub length: u64,
+    /// 1-based line number of the first record
+    pub first_line: u64,
+    /// Number of lines in the block
+    pub lines: u64,
+}
+
+/// Random-access index of the graph sections in an uncompressed or BGZF-compressed TSG file
+///
+/// The index is stored as a small tab-separated sidecar file (`<file>.tsg.tsi`)
+/// listing the byte range of every `G` section and `L` record.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TsgIndex {
+    /// Compression of the indexed file, either [`Compression::None`] or [`Compression::Bgzf`]
+    pub compression: Compression,
+    /// Size in bytes of the indexed file, used to detect a file changed since indexing
+    pub source_size: u64,
+    /// Records before the first `G` line: headers and records of the default graph
+    pub preamble: IndexEntry,
+    /// One entry per `G` section in file order, starting at its `G` line
+    pub graphs: Vec<(BString, IndexEntry)>,
+    /// Every `L` record in the file
+    pub links: Vec<IndexEntry>,
+}
+
+/// Path of the index file belonging to a TSG file
+pub fn index_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut index = path.as_ref().as_os_str().to_owned();
+    index.push(".");
+    index.push(INDEX_EXTENSION);
+    PathBuf::from(index)
+}
+
+impl TsgIndex {
+    /// Scan an uncompressed or BGZF-compressed TSG stream and record the byte range of each section
+    ///
+    /// Sections of a BGZF stream are located by their virtual positions.
+    /// Plain gzip and zstd streams cannot be seeked into and are rejected.
+    pub fn build<R: BufRead>(mut reader: R) -> Result<Self> {
+        match Compression::from_magic(reader.fill_buf()?) {
+            Compression::None => Self::scan(reader, Compression::None, |_, offset| offset),
+            Compression::Bgzf => {
+                Self::scan(bgzf::Reader::new(reader), Compression::Bgzf, |r, _| {
+                    u64::from(r.virtual_position())
+                })
+            }
+            compression => bail!(
+                "Indexing requires an uncompressed or BGZF-compressed TSG file, found {:?}",
+                compression
+            ),
+        }
+    }
+
+    /// Record the sections of a stream, locating each line with `position`
+    ///
+    /// `position` receives the reader and the number of uncompressed bytes read so far.
+    fn scan<R: BufRead>(
+        mut reader: R,
+        compression: Compression,
+        position: impl Fn(&R, u64) -> u64,
+    ) -> Result<Self> {
+        let mut index = TsgIndex {
+            compression,
+            ..Default::default()
+        };
+        let mut current_id: Option<BString> = None;
+        let mut current = IndexEntry {
+            first_line: 1,
+            ..Default::default()
+        };
+
+        let mut consumed = 0u64;
+        let mut line_number = 0u64;
+        let mut line = Vec::new();
+        loop {
+            let offset = position(&reader, consumed);
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)? as u64;
+            if read == 0 {
+                break;
+            }
+            line_number += 1;
+
+            let mut fields = line.fields();
+            match fields.next() {
+                Some(b"G") => {
+                    let id = fields.next().ok_or_else(|| {
+                        anyhow!("line {}: G record without a graph ID", line_number)
+                    })?;
+                    index.push_section(current_id.take(), current);
+                    current_id = Some(id.into());
+                    current = IndexEntry {
+                        offset,
+                        first_line: line_number,
+                        ..Default::default()
+                    };
+                }
+                Some(b"L") => index.links.push(IndexEntry {
+                    offset,
+                    length: read,
+                    first_line: line_number,
+                    lines: 1,
+                }),
+                _ => {}
+            }
+
+            current.length += read;
+            current.lines += 1;
+            consumed += read;
+        }
+        index.push_section(current_id, current);
+
+        Ok(index)
+    }
+
+    fn push_section(&mut self, id: Option<BString>, entry: IndexEntry) {
+        match id {
+            Some(id) => self.graphs.push((id, entry)),
+            None => self.preamble = entry,
+        }
+    }
+
+    /// Build the index of a TSG file
+    pub fn from_tsg_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        let source_size = file.metadata()?.len();
+        let mut index = Self::build(BufReader::new(file))?;
+        index.source_size = source_size;
+        Ok(index)
+    }
+
+    /// Whether the file at `path` still has the size recorded when it was indexed
+    ///
+    /// This catches most edits that keep the modification time older than the index,
+    /// such as files restored or copied with their original timestamps.
+    pub fn matches_source<P: AsRef<Path>>(&self, path: P) -> Result<bool> {
+        let path = path.as_ref();
+        let metadata = path
+            .metadata()
+            .with_context(|| format!("failed to read metadata of {}", path.display()))?;
+        Ok(metadata.len() == self.source_size)
+    }
+
+    /// Look up the section of a graph by its ID
+    pub fn graph(&self, id: &str) -> Option<&IndexEntry> {
+        self.graphs
+            .iter()
+            .find(|(graph_id, _)| graph_id == id)
+            .map(|(_, entry)| entry)
+    }
+
+    /// Write the index in its tab-separated text form
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writeln!(writer, "{}\t{}", INDEX_MAGIC, INDEX_VERSION)?;
+        let compression = match self.compression {
+            Compression::Bgzf => "bgzf",
+            _ => "none",
+        };
+        writeln!(writer, "S\t{}\t{}", self.source_size, compression)?;
+        writeln!(writer, "H\t{}", format_entry(&self.preamble))?;
+        for (id, entry) in &self.graphs {
+            writeln!(writer, "G\t{}\t{}", id, format_entry(entry))?;
+        }
+        for entry in &self.links {
+            writeln!(writer, "L\t{}", format_entry(entry))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read an index previously written with [`TsgIndex::write`]
+    pub fn read<R: BufRead>(reader: R) -> Result<Self> {
+        let mut lines = reader.lines();
+
+        let magic = lines.next().transpose()?.unwrap_or_default();
+        let version = magic
+            .strip_prefix(INDEX_MAGIC)
+            .map(str::trim)
+            .ok_or_else(|| anyhow!("Not a TSG index file"))?;
+        if version != INDEX_VERSION.to_string() {
+            bail!("Unsupported TSG index version: {}", version);
+        }
+
+        let mut index = TsgIndex::default();
+        for (idx, line) in lines.enumerate() {
+            let line = line?;
+            let fields: Vec<&str> = line.split('\t').collect();
+            let context = || format!("invalid index record on line {}", idx + 2);
+            match fields.as_slice() {
+                ["S", size, compression] => {
+                    index.source_size = size.parse().with_context(context)?;
+                    index.compression = match *compression {
+                        "none" => Compression::None,
+                        "bgzf" => Compression::Bgzf,
+                        _ => bail!("{}: unknown compression {}", context(), compression),
+                    };
+                }
+                ["H", entry @ ..] => index.preamble = parse_entry(entry).with_context(context)?,
+                ["G", id, entry @ ..] => index
+                    .graphs
+                    .push(((*id).into(), parse_entry(entry).with_context(context)?)),
+                ["L", entry @ ..] => index.links.push(parse_entry(entry).with_context(context)?),
+                [""] => {}
+                _ => bail!("{}", context()),
+            }
+        }
+        Ok(index)
+    }
+
+    /// Load an index file, decompressing it if it was saved compressed
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::read(open_reader(path)?)
+    }
+
+    /// Save the index to a file, compressed according to its extension
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut writer = create_writer(path)?;
+        self.write(&mut writer)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+fn format_entry(entry: &IndexEntry) -> String {
+    format!(
+        "{}\t{}\t{}\t{}",
+        entry.offset, entry.length, entry.first_line, entry.lines
+    )
+}
+
+fn parse_entry(fields: &[&str]) -> Result<IndexEntry> {
+    let [offset, length, first_line, lines] = fields else {
+        bail!("expected 4 numeric fields, found {}", fields.len());
+    };
+    Ok(IndexEntry {
+        offset: offset.parse()?,
+        length: length.parse()?,
+        first_line: first_line.parse()?,
+        lines: lines.parse()?,
+    })
+}
+
+/// Reader that uses a [`TsgIndex`] to parse single graph sections without reading the whole file
+///
+/// `L` records inside a section are skipped when the section is parsed,
+/// since they may refer to graphs that are not loaded; read them with [`IndexedReader::read_links`].
+pub struct IndexedReader<R> {
+    inner: Source<R>,
+    index: TsgIndex,
+}
+
+/// The indexed file, decompressed block by block when it is BGZF-compressed
+enum Source<R> {
+    Plain(R),
+    Bgzf(bgzf::Reader<R>),
+}
+
+impl<R: Read + Seek> Source<R> {
+    /// Move to an index offset: a byte offset, or a virtual position in a BGZF file
+    fn seek_to(&mut self, offset: u64) -> Result<()> {
+        match self {
+            Source::Plain(reader) => {
+                reader.seek(SeekFrom::Start(offset))?;
+            }
+            Source::Bgzf(reader) => {
+                reader.seek(bgzf::VirtualPosition::from(offset))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read exactly `length` uncompressed bytes from the current position
+    fn read_bytes(&mut self, length: u64) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        let read = match self {
+            Source::Plain(reader) => reader.take(length).read_to_end(&mut buffer)?,
+            Source::Bgzf(reader) => reader.take(length).read_to_end(&mut buffer)?,
+        };
+        if read as u64 != length {
+            bail!("TSG file is shorter than its index, rebuild the index");
+        }
+        Ok(buffer)
+    }
+}
+
+impl IndexedReader<BufReader<File>> {
+    /// Open a TSG file together with its `.tsi` index
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let index = TsgIndex::load(index_path(path))?;
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        Ok(Self::new(BufReader::new(file), index))
+    }
+}
+
+impl<R: Read + Seek> IndexedReader<R> {
+    /// Wrap a reader of the indexed file, which must be compressed as recorded in the index
+    pub fn new(inner: R, index: TsgIndex) -> Self {
+        let inner = match index.compression {
+            Compression::Bgzf => Source::Bgzf(bgzf::Reader::new(inner)),
+            _ => Source::Plain(inner),
+        };
+        Self { inner, index }
+    }
+
+    pub fn index(&self) -> &TsgIndex {
+        &self.index
+    }
+
+    /// IDs of all graph sections in file order
+    pub fn graph_ids(&self) -> impl Iterator<Item = &BString> {
+        self.index.graphs.iter().map(|(id, _)| id)
+    }
+
+    /// Read the raw bytes of a block, blanking out `L` records so line numbers stay intact
+    fn read_block(&mut self, entry: &IndexEntry) -> Result<String> {
+        self.inner.seek_to(entry.offset)?;
+        let buffer = self.inner.read_bytes(entry.length)?;
+
+        let block = buffer
+            .lines_with_terminator()
+            .map(|line| match line.fields().next() {
+                Some(b"L") => b"\n".as_slice(),
+                _ => line,
+            })
+            .collect::<Vec<_>>()
+            .concat();
+        String::from_utf8(block).context("TSG file is not valid UTF-8")
+    }
+
+    fn parse_block(&mut self, entry: &IndexEntry) -> Result<TSGraph> {
+        let block = self.read_block(entry)?;
+        let (graph, _) = TSGraph::from_reader_at_line(
+            block.as_bytes(),
+            &ParseOptions::strict(),
+            entry.first_line as usize,
+        )?;
+        Ok(graph)
+    }
+
+    /// Read the global headers
+    pub fn read_headers(&mut self) -> Result<Vec<Header>> {
+        let preamble = self.index.preamble;
+        Ok(self.parse_block(&preamble)?.headers)
+    }
+
+    /// Read the comments written before the first graph section
+    pub fn read_comments(&mut self) -> Result<Vec<BString>> {
+        let preamble = self.index.preamble;
+        Ok(self.parse_block(&preamble)?.comments)
+    }
+
+    /// Seek to a graph section and parse only that section
+    ///
+    /// Records of the default graph, written before the first `G` line,
+    /// are available under [`DEFAULT_GRAPH_ID`].
+    pub fn read_graph(&mut self, id: &str) -> Result<GraphSection> {
+        let entry = match self.index.graph(id) {
+            Some(entry) => *entry,
+            None if id == DEFAULT_GRAPH_ID => self.index.preamble,
+            None => bail!("Graph with ID {} not found in index", id),
+        };
+
+        self.parse_block(&entry)?
+            .graphs
+            .swap_remove(id.as_bytes().as_bstr())
+            .ok_or_else(|| anyhow!("Graph with ID {} not found", id))
+    }
+
+    /// Read every inter-graph link record
+    ///
+    /// Links are not validated against the graphs they connect.
+    pub fn read_links(&mut self) -> Result<Vec<InterGraphLink>> {
+        let entries = self.index.links.clone();
+        entries
+            .iter()
+            .map(|entry| {
+                self.inner.seek_to(entry.offset)?;
+                let line = String::from_utf8(self.inner.read_bytes(entry.length)?)
+                    .context("TSG file is not valid UTF-8")?;
+                InterGraphLink::from_str(&line)
+                    .map_err(|e| e.at_line(entry.first_line as usize).into())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_index() -> Result<()> {
+        let index = TsgIndex::from_tsg_file("tests/data/test_mul.tsg")?;
+
+        let ids: Vec<_> = index.graphs.iter().map(|(id, _)| id.to_string()).collect();
+        assert_eq!(ids, vec!["gene_a", "gene_b"]);
+        assert_eq!(index.links.len(), 1);
+        assert_eq!(index.preamble.first_line, 1);
+
+        let content = std::fs::read("tests/data/test_mul.tsg")?;
+        let gene_b = index.graph("gene_b").unwrap();
+        let start = gene_b.offset as usize;
+        assert!(content[start..].starts_with(b"G  gene_b"));
+
+        let link = &index.links[0];
+        let start = link.offset as usize;
+        assert!(content[start..start + link.length as usize].starts_with(b"L"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_roundtrip() -> Result<()> {
+        let index = TsgIndex::from_tsg_file("tests/data/test_mul.tsg")?;
+        let path = std::env::temp_dir().join("tsg_index_test.tsg.tsi");
+        index.save(&path)?;
+        assert_eq!(TsgIndex::load(&path)?, index);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_indexed_reader() -> Result<()> {
+        let index = TsgIndex::from_tsg_file("tests/data/test_mul.tsg")?;
+        let file = File::open("tests/data/test_mul.tsg")?;
+        let mut reader = IndexedReader::new(BufReader::new(file), index);
+
+        let tsg = TSGraph::from_file("tests/data/test_mul.tsg")?;
+
+        let headers = reader.read_headers()?;
+        assert_eq!(headers, tsg.headers);
+        assert_eq!(reader.read_comments()?, tsg.comments);
+
+        let gene_b = reader.read_graph("gene_b")?;
+        let expected = tsg.graph("gene_b").unwrap();
+        assert_eq!(gene_b.nodes().len(), expected.nodes().len());
+        assert_eq!(gene_b.edges().len(), expected.edges().len());
+        assert_eq!(gene_b.groups().len(), expected.groups().len());
+
+        assert!(reader.read_graph("gene_c").is_err());
+
+        let links = reader.read_links()?;
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].id, tsg.links[0].id);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_rejects_gzip() -> Result<()> {
+        let path = std::env::temp_dir().join("tsg_index_test.tsg.gz");
+        let mut writer = create_writer(&path)?;
+        writer.write_all(b"H\tTSG\t1.0\n")?;
+        writer.finish()?;
+        assert!(TsgIndex::from_tsg_file(&path).is_err());
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_indexed_bgzf_reader() -> Result<()> {
+        // Enough sections to span several BGZF blocks
+        let mut content = String::from("H\tTSG\t1.0\n");
+        for i in 0..2000 {
+            content.push_str(&format!(
+                "G\tg{i}\nN\tn1\tchr1:+:{}-{}\tread{i}:SO\tACGTACGT\n",
+                i * 100 + 1,
+                i * 100 + 50
+            ));
+        }
+        content.push_str("L\tl1\tg0:n1\tg1999:n1\tfusion\n");
+
+        let path = std::env::temp_dir().join("tsg_index_test.tsg.bgz");
+        let mut writer = create_writer(&path)?;
+        writer.write_all(content.as_bytes())?;
+        writer.finish()?;
+
+        let index = TsgIndex::from_tsg_file(&path)?;
+        assert_eq!(index.compression, Compression::Bgzf);
+        assert_eq!(index.graphs.len(), 2000);
+        assert!(index.matches_source(&path)?);
+
+        let file = File::open(&path)?;
+        let mut reader = IndexedReader::new(BufReader::new(file), index);
+        for id in ["g0", "g1234", "g1999"] {
+            let graph = reader.read_graph(id)?;
+            assert_eq!(graph.id, id);
+            assert_eq!(graph.nodes().len(), 1);
+        }
+        let start = reader.read_graph("g1500")?.nodes()[0].reference_start();
+        assert_eq!(start, 150001);
+        assert_eq!(reader.read_links()?.len(), 1);
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_index_roundtrip() -> Result<()> {
+        let index = TsgIndex::from_tsg_file("tests/data/test_mul.tsg")?;
+        let path = std::env::temp_dir().join("tsg_index_test.tsg.tsi.gz");
+        index.save(&path)?;
+        assert_eq!(TsgIndex::load(&path)?, index);
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_detects_resized_source() -> Result<()> {
+        let mut index = TsgIndex::from_tsg_file("tests/data/test_mul.tsg")?;
+        assert!(index.matches_source("tests/data/test_mul.tsg")?);
+        index.source_size += 1;
+        assert!(!index.matches_source("tests/data/test_mul.tsg")?);
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+#[cfg(feature = "serde")]
+use crate::graph::WriteOptions;
+use crate::graph::{DEFAULT_GRAPH_ID, GraphSection, TSGraph};
+#[cfg(not(feature = "serde"))]
+use anyhow::bail;
+use anyhow::{Context, Result};
+use std::io::Read;
+#[cfg(feature = "serde")]
+use std::io::Write;
+use std::path::Path;
+
+use super::open_reader;
+
+/// Read a TSGraph from JSON
+///
+/// Both the complete schema of the `serde` feature and the Cytoscape JSON of a single graph
+/// section, as written by [`GraphSection::to_json`], are understood. The section read from
+/// Cytoscape JSON is named `graph_id`.
+pub fn read_json<R: Read>(reader: R, graph_id: &str) -> Result<TSGraph> {
+    let json: serde_json::Value = serde_json::from_reader(reader).context("Invalid JSON")?;
+
+    if json.get("elements").is_some() {
+        let graph = GraphSection::from_cytoscape_json(graph_id.into(), &json)?;
+        let mut tsgraph = TSGraph::new();
+        if graph_id != DEFAULT_GRAPH_ID {
+            tsgraph.graphs.clear();
+        }
+        tsgraph.add_graph(graph);
+        return Ok(tsgraph);
+    }
+
+    #[cfg(feature = "serde")]
+    return serde_json::from_value(json).context("Invalid TSG JSON");
+    #[cfg(not(feature = "serde"))]
+    bail!("Reading TSG JSON requires the serde feature of tsg-core")
+}
+
+/// Write a TSGraph as JSON in the schema of the `serde` feature
+#[cfg(feature = "serde")]
+pub fn write_json<W: Write>(tsg_graph: &TSGraph, writer: &mut W, pretty: bool) -> Result<()> {
+    write_json_with_options(tsg_graph, writer, pretty, &WriteOptions::default())
+}
+
+/// Write a TSGraph as JSON in the schema of the `serde` feature, in the order given by `options`
+#[cfg(feature = "serde")]
+pub fn write_json_with_options<W: Write>(
+    tsg_graph: &TSGraph,
+    writer: &mut W,
+    pretty: bool,
+    options: &WriteOptions,
+) -> Result<()> {
+    if pretty {
+        let mut serializer = serde_json::Serializer::pretty(&mut *writer);
+        tsg_graph.serialize_with_ordering(&mut serializer, options.ordering)?;
+    } else {
+        let mut serializer = serde_json::Serializer::new(&mut *writer);
+        tsg_graph.serialize_with_ordering(&mut serializer, options.ordering)?;
+    }
+    writeln!(writer)?;
+    Ok(())
+}
+
+impl TSGraph {
+    /// Load a TSGraph from a JSON file, optionally compressed, see [`read_json`]
+    ///
+    /// A Cytoscape graph section is named after the file, as `tsg-cli json` names its
+    /// files after the graph IDs.
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let graph_id = match name.find(".json") {
+            Some(end) if end > 0 => &name[..end],
+            _ => DEFAULT_GRAPH_ID,
+        };
+        read_json(open_reader(path)?, graph_id)
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bstr::{BString, ByteSlice};
+    #[cfg(feature = "serde")]
+    use std::str::FromStr;
+
+    #[test]
+    fn test_read_cytoscape_json() -> Result<()> {
+        let tsg_graph = TSGraph::from_file("tests/data/test.tsg")?;
+        let json = tsg_graph.default_graph().unwrap().to_json()?;
+        let parsed = read_json(json.to_string().as_bytes(), "gene")?;
+        assert_eq!(parsed.graphs.len(), 1);
+
+        let graph = &parsed.graphs[&BString::from("gene")];
+        assert_eq!(graph.node_indices().len(), 5);
+        assert_eq!(graph.edge_indices().len(), 4);
+        let n1 = parsed.node("gene", "n1").unwrap();
+        assert_eq!(
+            n1.to_string(),
+            "N\tn1\tchr1:+:1000-1200,1500-1700\tread1:SO,read2:SO\t",
+            "sequences are not part of Cytoscape JSON"
+        );
+        let attr = |tag: &str| n1.attributes[&BString::from(tag)].to_string();
+        assert_eq!(attr("expression"), "expression:f:10.5");
+        assert_eq!(attr("ptc"), "ptc:i:10");
+        // styling hints and derived fields do not come back as attributes
+        let original = tsg_graph.default_graph().unwrap();
+        for node in graph.nodes() {
+            let mut tags: Vec<_> = node.attributes.keys().collect();
+            let mut expected: Vec<_> = original
+                .node_by_id(node.id.to_str()?)
+                .unwrap()
+                .attributes
+                .keys()
+                .collect();
+            tags.sort();
+            expected.sort();
+            assert_eq!(tags, expected, "node {}", node.id);
+        }
+        let e1 = &parsed.edges("gene")[0];
+        assert_eq!(e1.sv.to_string(), "chr1,chr1,1700,2000,INV");
+        assert!(e1.attributes.is_empty(), "the weight is derived");
+
+        let from_file = TSGraph::from_json_file("tests/data/test.graph.json")?;
+        assert!(from_file.graphs.contains_key(&BString::from("test.graph")));
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_write_json() -> Result<()> {
+        let tsg_graph = TSGraph::from_file("tests/data/test_mul.tsg")?;
+        let mut buf = Vec::new();
+        write_json(&tsg_graph, &mut buf, true)?;
+        let parsed = read_json(buf.as_slice(), DEFAULT_GRAPH_ID)?;
+        assert_eq!(parsed.graphs.len(), tsg_graph.graphs.len());
+        assert_eq!(parsed.links.len(), tsg_graph.links.len());
+
+        // graphs keep the order of the input with insertion ordering
+        let tsg_graph = TSGraph::from_str("G\tg2\nG\tg1\n")?;
+        let graph_ids = |options: &WriteOptions| -> Result<Vec<String>> {
+            let mut buf = Vec::new();
+            write_json_with_options(&tsg_graph, &mut buf, false, options)?;
+            let json: serde_json::Value = serde_json::from_slice(&buf)?;
+            Ok(json["graphs"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|graph| graph["id"].as_str().unwrap().to_string())
+                .collect())
+        };
+        assert_eq!(graph_ids(&WriteOptions::sorted())?, ["g1", "g2"]);
+        assert_eq!(graph_ids(&WriteOptions::insertion())?, ["g2", "g1"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,447 @@
+use crate::graph::{Attribute, GraphSection, NodeData, WriteOptions};
+use anyhow::Result;
+use bstr::{BString, ByteSlice};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Built-in node fields and their GraphML types
+const NODE_FIELDS: &[(&str, &str)] = &[
+    ("chrom", "string"),
+    ("start", "long"),
+    ("end", "long"),
+    ("strand", "string"),
+    ("read_count", "int"),
+    ("exon_count", "int"),
+];
+
+/// Built-in edge fields and their GraphML types
+const EDGE_FIELDS: &[(&str, &str)] = &[
+    ("sv_type", "string"),
+    ("breakpoints", "string"),
+    ("weight", "int"),
+];
+
+/// Escape text for XML content and attribute values
+pub(super) fn xml_escape(value: &[u8]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.to_str_lossy().chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Types of the user attributes by tag: `i`, `f`, or `Z` for text and when types disagree
+fn attribute_types<'a>(
+    attributes: impl Iterator<Item = &'a Attribute>,
+) -> BTreeMap<&'a BString, char> {
+    let mut types = BTreeMap::new();
+    for attr in attributes {
+        let kind = match attr.attribute_type {
+            'i' | 'f' => attr.attribute_type,
+            _ => 'Z',
+        };
+        types
+            .entry(&attr.tag)
+            .and_modify(|existing| {
+                if *existing != kind {
+                    *existing = 'Z';
+                }
+            })
+            .or_insert(kind);
+    }
+    types
+}
+
+/// Values of the built-in node fields, in the order of [`NODE_FIELDS`]
+fn node_fields(node: &NodeData) -> [String; 6] {
+    let exons = &node.exons.exons;
+    [
+        node.reference_id.to_string(),
+        exons.first().map_or(0, |exon| exon.start).to_string(),
+        exons.last().map_or(0, |exon| exon.end).to_string(),
+        node.strand.to_string(),
+        node.reads.len().to_string(),
+        exons.len().to_string(),
+    ]
+}
+
+/// Values of the built-in edge fields, in the order of [`EDGE_FIELDS`]
+fn edge_fields(graph: &GraphSection, edge_idx: EdgeIndex) -> [String; 3] {
+    let edge = graph.edge_by_idx(edge_idx).unwrap();
+    [
+        edge.sv.sv_type.to_string(),
+        edge.sv.to_string(),
+        graph.edge_weight(edge_idx).to_string(),
+    ]
+}
+
+/// Elements of a graph section in the order of `options`, with their user attribute types
+struct NetworkElements<'a> {
+    nodes: Vec<NodeIndex>,
+    edges: Vec<(EdgeIndex, &'a BString, &'a BString)>,
+    graph_types: BTreeMap<&'a BString, char>,
+    node_types: BTreeMap<&'a BString, char>,
+    edge_types: BTreeMap<&'a BString, char>,
+}
+
+impl<'a> NetworkElements<'a> {
+    fn new(graph: &'a GraphSection, options: &WriteOptions) -> Self {
+        let nodes = graph.ordered_node_indices(options.ordering);
+        let edges = graph
+            .ordered_edge_indices(options.ordering)
+            .into_iter()
+            .map(|idx| {
+                let (source, target) = graph.inner_graph().edge_endpoints(idx).unwrap();
+                (
+                    idx,
+                    &graph.node_by_idx(source).unwrap().id,
+                    &graph.node_by_idx(target).unwrap().id,
+                )
+            })
+            .collect();
+        Self {
+            nodes,
+            edges,
+            graph_types: attribute_types(graph.attributes.values()),
+            node_types: attribute_types(
+                graph
+                    .inner_graph()
+                    .node_weights()
+                    .flat_map(|node| node.attributes.values()),
+            ),
+            edge_types: attribute_types(
+                graph
+                    .inner_graph()
+                    .edge_weights()
+                    .flat_map(|edge| edge.attributes.values()),
+            ),
+        }
+    }
+}
+
+/// GraphML and GEXF type of a user attribute type
+fn attribute_xml_type(kind: char) -> &'static str {
+    match kind {
+        'i' => "long",
+        'f' => "double",
+        _ => "string",
+    }
+}
+
+/// GEXF name of a GraphML type
+fn gexf_type(kind: &'static str) -> &'static str {
+    match kind {
+        "int" => "integer",
+        other => other,
+    }
+}
+
+/// Sorted user attributes of an element
+fn sorted_attributes<'a>(attributes: impl Iterator<Item = &'a Attribute>) -> Vec<&'a Attribute> {
+    let mut attributes: Vec<_> = attributes.collect();
+    attributes.sort_by(|a, b| a.tag.cmp(&b.tag));
+    attributes
+}
+
+impl GraphSection {
+    /// Write the graph section as GraphML, see [`GraphSection::to_graphml_with_options`]
+    pub fn to_graphml<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.to_graphml_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Write the graph section as GraphML, listing elements in the order of `options`
+    ///
+    /// Nodes carry typed `chrom`, `start`, `end`, `strand`, `read_count` and `exon_count`
+    /// keys, and edges `sv_type`, `breakpoints` and `weight`, the number of reads shared by
+    /// both ends. Every user attribute of the graph, its nodes and its edges is declared as a
+    /// key `graph_attr_<tag>`, `node_attr_<tag>` or `edge_attr_<tag>` named after its tag,
+    /// typed `long` for `i`, `double` for `f` and `string` otherwise.
+    pub fn to_graphml_with_options<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<()> {
+        let elements = NetworkElements::new(self, options);
+
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd">"#
+        )?;
+        for (domain, fields) in [("node", NODE_FIELDS), ("edge", EDGE_FIELDS)] {
+            for (name, kind) in fields {
+                writeln!(
+                    writer,
+                    r#"  <key id="{name}" for="{domain}" attr.name="{name}" attr.type="{kind}"/>"#
+                )?;
+            }
+        }
+        for (domain, types) in [
+            ("graph", &elements.graph_types),
+            ("node", &elements.node_types),
+            ("edge", &elements.edge_types),
+        ] {
+            for (tag, kind) in types {
+                let tag = xml_escape(tag);
+                writeln!(
+                    writer,
+                    r#"  <key id="{domain}_attr_{tag}" for="{domain}" attr.name="{tag}" attr.type="{}"/>"#,
+                    attribute_xml_type(*kind)
+                )?;
+            }
+        }
+
+        let data = |writer: &mut W, domain: &str, attributes: Vec<&Attribute>| -> Result<()> {
+            for attr in attributes {
+                writeln!(
+                    writer,
+                    r#"      <data key="{}_attr_{}">{}</data>"#,
+                    domain,
+                    xml_escape(&attr.tag),
+                    xml_escape(&attr.value)
+                )?;
+            }
+            Ok(())
+        };
+
+        writeln!(
+            writer,
+            r#"  <graph id="{}" edgedefault="directed">"#,
+            xml_escape(&self.id)
+        )?;
+        for attr in sorted_attributes(self.attributes.values()) {
+            writeln!(
+                writer,
+                r#"    <data key="graph_attr_{}">{}</data>"#,
+                xml_escape(&attr.tag),
+                xml_escape(&attr.value)
+            )?;
+        }
+        for &node_idx in &elements.nodes {
+            let node = self.node_by_idx(node_idx).unwrap();
+            writeln!(writer, r#"    <node id="{}">"#, xml_escape(&node.id))?;
+            for ((name, _), value) in NODE_FIELDS.iter().zip(node_fields(node)) {
+                writeln!(
+                    writer,
+                    r#"      <data key="{}">{}</data>"#,
+                    name,
+                    xml_escape(value.as_bytes())
+                )?;
+            }
+            data(writer, "node", sorted_attributes(node.attributes.values()))?;
+            writeln!(writer, "    </node>")?;
+        }
+        for &(edge_idx, source, target) in &elements.edges {
+            let edge = self.edge_by_idx(edge_idx).unwrap();
+            writeln!(
+                writer,
+                r#"    <edge id="{}" source="{}" target="{}">"#,
+                xml_escape(&edge.id),
+                xml_escape(source),
+                xml_escape(target)
+            )?;
+            for ((name, _), value) in EDGE_FIELDS.iter().zip(edge_fields(self, edge_idx)) {
+                writeln!(
+                    writer,
+                    r#"      <data key="{}">{}</data>"#,
+                    name,
+                    xml_escape(value.as_bytes())
+                )?;
+            }
+            data(writer, "edge", sorted_attributes(edge.attributes.values()))?;
+            writeln!(writer, "    </edge>")?;
+        }
+        writeln!(writer, "  </graph>")?;
+        writeln!(writer, "</graphml>")?;
+        Ok(())
+    }
+
+    /// Write the graph section as GEXF 1.3, see [`GraphSection::to_gexf_with_options`]
+    pub fn to_gexf<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.to_gexf_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Write the graph section as GEXF 1.3, listing elements in the order of `options`
+    ///
+    /// Declares the same typed node and edge fields as [`GraphSection::to_graphml`], except
+    /// that the shared-read weight is the native edge `weight`. User attributes are declared
+    /// as `attr_<tag>` with type `long`, `double` or `string`; graph attributes are written
+    /// to the description.
+    pub fn to_gexf_with_options<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<()> {
+        let elements = NetworkElements::new(self, options);
+
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<gexf xmlns="http://gexf.net/1.3" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://gexf.net/1.3 http://gexf.net/1.3/gexf.xsd" version="1.3">"#
+        )?;
+        writeln!(writer, "  <meta>")?;
+        writeln!(writer, "    <creator>tsg</creator>")?;
+        let mut description = xml_escape(&self.id);
+        for attr in sorted_attributes(self.attributes.values()) {
+            description.push_str(&format!(
+                " {}={}",
+                xml_escape(&attr.tag),
+                xml_escape(&attr.value)
+            ));
+        }
+        writeln!(writer, "    <description>{}</description>", description)?;
+        writeln!(writer, "  </meta>")?;
+        writeln!(
+            writer,
+            r#"  <graph defaultedgetype="directed" mode="static">"#
+        )?;
+
+        for (class, fields, types) in [
+            ("node", NODE_FIELDS, &elements.node_types),
+            ("edge", EDGE_FIELDS, &elements.edge_types),
+        ] {
+            writeln!(writer, r#"    <attributes class="{}">"#, class)?;
+            // the edge weight is a native GEXF attribute
+            for (name, kind) in fields.iter().filter(|(name, _)| *name != "weight") {
+                writeln!(
+                    writer,
+                    r#"      <attribute id="{name}" title="{name}" type="{}"/>"#,
+                    gexf_type(kind)
+                )?;
+            }
+            for (tag, kind) in types {
+                let tag = xml_escape(tag);
+                writeln!(
+                    writer,
+                    r#"      <attribute id="attr_{tag}" title="{tag}" type="{}"/>"#,
+                    attribute_xml_type(*kind)
+                )?;
+            }
+            writeln!(writer, "    </attributes>")?;
+        }
+
+        let attvalues = |writer: &mut W,
+                         fields: Vec<(&str, String)>,
+                         attributes: Vec<&Attribute>|
+         -> Result<()> {
+            writeln!(writer, "        <attvalues>")?;
+            for (name, value) in fields {
+                writeln!(
+                    writer,
+                    r#"          <attvalue for="{}" value="{}"/>"#,
+                    name,
+                    xml_escape(value.as_bytes())
+                )?;
+            }
+            for attr in attributes {
+                writeln!(
+                    writer,
+                    r#"          <attvalue for="attr_{}" value="{}"/>"#,
+                    xml_escape(&attr.tag),
+                    xml_escape(&attr.value)
+                )?;
+            }
+            writeln!(writer, "        </attvalues>")?;
+            Ok(())
+        };
+
+        writeln!(writer, "    <nodes>")?;
+        for &node_idx in &elements.nodes {
+            let node = self.node_by_idx(node_idx).unwrap();
+            let id = xml_escape(&node.id);
+            writeln!(writer, r#"      <node id="{id}" label="{id}">"#)?;
+            let fields = NODE_FIELDS
+                .iter()
+                .map(|(name, _)| *name)
+                .zip(node_fields(node))
+                .collect();
+            attvalues(writer, fields, sorted_attributes(node.attributes.values()))?;
+            writeln!(writer, "      </node>")?;
+        }
+        writeln!(writer, "    </nodes>")?;
+
+        writeln!(writer, "    <edges>")?;
+        for &(edge_idx, source, target) in &elements.edges {
+            let edge = self.edge_by_idx(edge_idx).unwrap();
+            let [sv_type, breakpoints, weight] = edge_fields(self, edge_idx);
+            let id = xml_escape(&edge.id);
+            writeln!(
+                writer,
+                r#"      <edge id="{id}" source="{}" target="{}" label="{id}" weight="{}">"#,
+                xml_escape(source),
+                xml_escape(target),
+                weight
+            )?;
+            let fields = vec![("sv_type", sv_type), ("breakpoints", breakpoints)];
+            attvalues(writer, fields, sorted_attributes(edge.attributes.values()))?;
+            writeln!(writer, "      </edge>")?;
+        }
+        writeln!(writer, "    </edges>")?;
+        writeln!(writer, "  </graph>")?;
+        writeln!(writer, "</gexf>")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::TSGraph;
+
+    #[test]
+    fn test_to_graphml() -> Result<()> {
+        let tsg_graph = TSGraph::from_file("tests/data/test.tsg")?;
+        let mut buf = Vec::new();
+        tsg_graph.default_graph().unwrap().to_graphml(&mut buf)?;
+        let graphml = String::from_utf8(buf)?;
+
+        assert!(
+            graphml.contains(r#"<key id="start" for="node" attr.name="start" attr.type="long"/>"#)
+        );
+        assert!(graphml.contains(
+            r#"<key id="node_attr_expression" for="node" attr.name="expression" attr.type="double"/>"#
+        ));
+        assert!(
+            graphml.contains(
+                r#"<key id="node_attr_ptc" for="node" attr.name="ptc" attr.type="long"/>"#
+            )
+        );
+        assert!(graphml.contains(r#"<node id="n1">"#));
+        assert!(graphml.contains(r#"<data key="exon_count">2</data>"#));
+        assert!(graphml.contains(r#"<edge id="e1" source="n1" target="n3">"#));
+        assert!(graphml.contains(r#"<data key="weight">2</data>"#));
+        assert_eq!(graphml.matches("<node ").count(), 5);
+        assert_eq!(graphml.matches("<edge ").count(), 4);
+        assert!(graphml.trim_end().ends_with("</graphml>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_gexf() -> Result<()> {
+        let tsg_graph = TSGraph::from_file("tests/data/test_mul.tsg")?;
+        let mut buf = Vec::new();
+        tsg_graph.graph("gene_a").unwrap().to_gexf(&mut buf)?;
+        let gexf = String::from_utf8(buf)?;
+
+        assert!(gexf.contains(r#"<attribute id="read_count" title="read_count" type="integer"/>"#));
+        assert!(gexf.contains("<description>gene_a locus=chr17q21.31 name=BRCA1</description>"));
+        assert!(gexf.contains(r#"<attvalue for="chrom" value="chr17"/>"#));
+        assert!(gexf.contains(r#"source="n1" target="n2" label="e1" weight="#));
+        assert!(!gexf.contains(r#"<attribute id="weight""#));
+        assert!(gexf.trim_end().ends_with("</gexf>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        assert_eq!(xml_escape(b"a<b & \"c\""), "a&lt;b &amp; &quot;c&quot;");
+    }
+}
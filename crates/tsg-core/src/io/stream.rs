@@ -0,0 +1,283 @@
+use std::io::{BufRead, Lines};
+use std::path::Path;
+use std::str::FromStr;
+
+use ahash::{HashSet, HashSetExt};
+use anyhow::Result;
+use bstr::BString;
+
+use super::open_reader;
+use crate::graph::{GraphSection, Header, InterGraphLink, ParseOptions, TSGraph, TsgError};
+
+/// Records of one graph section, kept as text until the section is complete
+struct Chunk {
+    text: String,
+    first_line: usize,
+}
+
+impl Chunk {
+    fn new(first_line: usize) -> Self {
+        Self {
+            text: String::new(),
+            first_line,
+        }
+    }
+
+    fn push_line(&mut self, line: &str) {
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+}
+
+/// Streaming reader that yields one [`GraphSection`] at a time
+///
+/// A section is emitted as soon as the next `G` record (or the end of input) is reached,
+/// so only a single section is held in memory. Records before the first `G` line form
+/// the default graph, which is emitted only if it has nodes.
+///
+/// Headers, comments before the first `G` record and `L` records are buffered separately and
+/// are available through [`GraphSectionReader::headers`], [`GraphSectionReader::comments`]
+/// and [`GraphSectionReader::links`]. Links cannot be
+/// checked against graphs that were already emitted, so they are not validated.
+///
+/// Only graph IDs are remembered across sections, to reject a `G` ID that was already used.
+/// In lenient mode the repeated section is skipped and reported as a diagnostic.
+pub struct GraphSectionReader<R> {
+    lines: Lines<R>,
+    line_number: usize,
+    chunk: Option<Chunk>,
+    seen_ids: HashSet<BString>,
+    options: ParseOptions,
+    headers: Vec<Header>,
+    comments: Vec<BString>,
+    links: Vec<InterGraphLink>,
+    diagnostics: Vec<TsgError>,
+}
+
+impl GraphSectionReader<Box<dyn BufRead>> {
+    /// Open a (possibly compressed) TSG file for streaming
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(open_reader(path)?))
+    }
+}
+
+impl<R: BufRead> GraphSectionReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParseOptions::strict())
+    }
+
+    /// Create a reader that parses each section with the given options
+    ///
+    /// In lenient mode malformed records are skipped and collected in [`GraphSectionReader::diagnostics`].
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        Self {
+            lines: reader.lines(),
+            line_number: 0,
+            chunk: Some(Chunk::new(1)),
+            seen_ids: HashSet::new(),
+            options,
+            headers: Vec::new(),
+            comments: Vec::new(),
+            links: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Headers read so far
+    pub fn headers(&self) -> &[Header] {
+        &self.headers
+    }
+
+    /// Comments before the first `G` record read so far
+    pub fn comments(&self) -> &[BString] {
+        &self.comments
+    }
+
+    /// Inter-graph links read so far; complete once the iterator is exhausted
+    pub fn links(&self) -> &[InterGraphLink] {
+        &self.links
+    }
+
+    /// Problems skipped so far in lenient mode
+    pub fn diagnostics(&self) -> &[TsgError] {
+        &self.diagnostics
+    }
+
+    fn parse_link(&mut self, line: &str) -> Result<(), TsgError> {
+        match InterGraphLink::from_str(line) {
+            Ok(link) => self.links.push(link),
+            Err(err) => {
+                let err = err.at_line(self.line_number);
+                if !self.options.is_lenient() {
+                    return Err(err);
+                }
+                self.diagnostics.push(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a completed chunk, returning its section if it contains one
+    fn parse_chunk(&mut self, chunk: Chunk) -> Result<Option<GraphSection>, TsgError> {
+        let (mut tsgraph, diagnostics) =
+            TSGraph::from_reader_at_line(chunk.text.as_bytes(), &self.options, chunk.first_line)?;
+        self.headers.append(&mut tsgraph.headers);
+        self.comments.append(&mut tsgraph.comments);
+
+        // A chunk holds either one G section or the records of the default graph
+        let Some(section) = tsgraph.graphs.into_values().next() else {
+            self.diagnostics.extend(diagnostics);
+            return Ok(None);
+        };
+        if !self.seen_ids.insert(section.id.clone()) {
+            let err =
+                TsgError::duplicate_id("G", "graph_id", &section.id).at_line(chunk.first_line);
+            if !self.options.is_lenient() {
+                return Err(err);
+            }
+            // Every record of the repeated section is skipped, as by the file parser
+            self.diagnostics.push(err);
+            for (offset, line) in chunk.text.lines().enumerate().skip(1) {
+                if let Some(record) = line.split_whitespace().next()
+                    && !record.starts_with('#')
+                    && record != "H"
+                {
+                    self.diagnostics
+                        .push(TsgError::outside_graph(record).at_line(chunk.first_line + offset));
+                }
+            }
+            return Ok(None);
+        }
+        self.diagnostics.extend(diagnostics);
+        Ok(Some(section))
+    }
+}
+
+impl<R: BufRead> Iterator for GraphSectionReader<R> {
+    type Item = Result<GraphSection, TsgError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(err)) => return Some(Err(err.into())),
+                None => {
+                    let chunk = self.chunk.take()?;
+                    return self.parse_chunk(chunk).transpose();
+                }
+            };
+            self.line_number += 1;
+
+            match line.split_whitespace().next() {
+                Some("G") => {
+                    let mut next_chunk = Chunk::new(self.line_number);
+                    next_chunk.push_line(&line);
+                    let Some(chunk) = self.chunk.replace(next_chunk) else {
+                        continue;
+                    };
+                    match self.parse_chunk(chunk) {
+                        Ok(Some(section)) => return Some(Ok(section)),
+                        Ok(None) => {}
+                        Err(err) => return Some(Err(err)),
+                    }
+                }
+                Some("L") => {
+                    if let Err(err) = self.parse_link(&line) {
+                        return Some(Err(err));
+                    }
+                    // Keep an empty line so line numbers of the section stay accurate
+                    if let Some(chunk) = self.chunk.as_mut() {
+                        chunk.push_line("");
+                    }
+                }
+                _ => {
+                    if let Some(chunk) = self.chunk.as_mut() {
+                        chunk.push_line(&line);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_section_reader() -> Result<()> {
+        let tsg = TSGraph::from_file("tests/data/test_mul.tsg")?;
+        let mut reader = GraphSectionReader::from_file("tests/data/test_mul.tsg")?;
+
+        let sections = reader.by_ref().collect::<Result<Vec<_>, _>>()?;
+        let ids: Vec<_> = sections.iter().map(|graph| graph.id.to_string()).collect();
+        assert_eq!(ids, vec!["gene_a", "gene_b"]);
+
+        for section in &sections {
+            let expected = &tsg.graphs[&section.id];
+            assert_eq!(section.nodes().len(), expected.nodes().len());
+            assert_eq!(section.edges().len(), expected.edges().len());
+            assert_eq!(section.groups().len(), expected.groups().len());
+        }
+
+        assert_eq!(reader.headers(), tsg.headers.as_slice());
+        assert_eq!(reader.comments(), tsg.comments.as_slice());
+        assert_eq!(reader.links().len(), 1);
+        assert!(reader.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_graph_section_reader_default_graph() -> Result<()> {
+        let reader = GraphSectionReader::from_file("tests/data/test.tsg")?;
+        let sections = reader.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].id, crate::graph::DEFAULT_GRAPH_ID);
+        Ok(())
+    }
+
+    #[test]
+    fn test_graph_section_reader_error_line() {
+        let content = "G\tg1\nN\tn1\tchr1:+:100-200\tr1:SO\nG\tg2\nN\tn2\tchr1:+:1-x\tr1:SO\n";
+        let mut reader = GraphSectionReader::new(content.as_bytes());
+
+        assert!(reader.next().unwrap().is_ok());
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.line(), Some(4));
+    }
+
+    #[test]
+    fn test_graph_section_reader_duplicate_graph() {
+        let content =
+            "G\tg1\nN\tn1\tchr1:+:100-200\tr1:SO\nG\tg2\nG\tg1\nN\tn2\tchr1:+:1-50\tr1:SO\n";
+        let sections = GraphSectionReader::new(content.as_bytes()).collect::<Vec<_>>();
+        let err = sections[2].as_ref().unwrap_err();
+        assert_eq!(err.line(), Some(4));
+        assert_eq!(err.record(), Some("G"));
+
+        let mut reader =
+            GraphSectionReader::with_options(content.as_bytes(), ParseOptions::lenient());
+        let ids = reader
+            .by_ref()
+            .map(|section| section.map(|graph| graph.id.to_string()))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(ids, vec!["g1", "g2"]);
+        let lines: Vec<_> = reader.diagnostics().iter().map(|err| err.line()).collect();
+        assert_eq!(lines, [Some(4), Some(5)]);
+    }
+
+    #[test]
+    fn test_graph_section_reader_rejected_graph() -> Result<()> {
+        let content = "G\tg1\nN\tn1\tchr1:+:100-200\tr1:SO\n\
+            G\tg2\tbad_attr\nN\tn2\tchr1:+:300-400\tr1:SO\n";
+        let mut reader =
+            GraphSectionReader::with_options(content.as_bytes(), ParseOptions::lenient());
+        let sections = reader.by_ref().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].nodes().len(), 1);
+        let lines: Vec<_> = reader.diagnostics().iter().map(|err| err.line()).collect();
+        assert_eq!(lines, [Some(3), Some(4)]);
+        Ok(())
+    }
+}
@@ -0,0 +1,676 @@
+use super::dot::PATH_PALETTE;
+use super::network::xml_escape;
+use crate::graph::{
+    FORWARD_COLOR, GraphSection, NodeData, OrderingPolicy, PlotOptions, REVERSE_COLOR, Strand,
+    scale_style,
+};
+use anyhow::{Result, bail};
+use bstr::BString;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+/// Space left of the tracks for their labels, and right of them, in pixels
+const MARGIN_LEFT: f64 = 160.0;
+const MARGIN_RIGHT: f64 = 40.0;
+/// Space between the coordinates of two references, in pixels
+const REFERENCE_GAP: f64 = 40.0;
+/// Heights of the title, the axis, the edge arcs, a row of nodes and a path track
+const TITLE_HEIGHT: f64 = 40.0;
+const AXIS_HEIGHT: f64 = 40.0;
+const ARC_HEIGHT: f64 = 90.0;
+const NODE_ROW_HEIGHT: f64 = 36.0;
+const PATH_TRACK_HEIGHT: f64 = 56.0;
+const EXON_HEIGHT: f64 = 14.0;
+/// Smallest distance between two axis ticks, in pixels
+const MIN_TICK_SPACING: f64 = 70.0;
+/// Range of arc stroke widths, in pixels
+const ARC_WIDTH_RANGE: (f64, f64) = (1.0, 4.0);
+/// Approximate width of a character of the 11px labels
+const CHAR_WIDTH: f64 = 6.5;
+
+/// Colour of an edge arc by structural variant type
+fn sv_color(sv_type: &[u8]) -> &'static str {
+    match sv_type.to_ascii_uppercase().as_slice() {
+        b"SPLICE" => "#525252",
+        b"DEL" => "#8c564b",
+        b"DUP" | b"TDUP" => "#2ca02c",
+        b"INV" => "#9467bd",
+        b"TRA" | b"BND" => "#ff7f0e",
+        b"INS" => "#17becf",
+        _ => "#7f7f7f",
+    }
+}
+
+fn strand_color(strand: Strand) -> &'static str {
+    match strand {
+        Strand::Forward => FORWARD_COLOR,
+        Strand::Reverse => REVERSE_COLOR,
+    }
+}
+
+/// Round step between axis ticks: 1, 2 or 5 times a power of ten
+fn nice_step(raw: f64) -> usize {
+    if raw <= 1.0 {
+        return 1;
+    }
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = match raw / magnitude {
+        n if n <= 1.0 => 1.0,
+        n if n <= 2.0 => 2.0,
+        n if n <= 5.0 => 5.0,
+        _ => 10.0,
+    };
+    (step * magnitude) as usize
+}
+
+/// Genomic position with thousands separators
+fn format_position(position: usize) -> String {
+    let digits = position.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(c);
+    }
+    formatted
+}
+
+/// Part of a reference drawn at a constant scale
+struct Segment {
+    start: usize,
+    end: usize,
+    /// Display position of `start`, in base pairs from the start of the reference
+    offset: f64,
+    /// Display base pairs per genomic base pair, below one in compressed gaps
+    factor: f64,
+}
+
+/// Coordinates of one reference on the x axis
+struct ReferenceScale<'a> {
+    name: &'a BString,
+    x: f64,
+    segments: Vec<Segment>,
+}
+
+impl ReferenceScale<'_> {
+    fn start(&self) -> usize {
+        self.segments.first().map_or(0, |segment| segment.start)
+    }
+
+    fn end(&self) -> usize {
+        self.segments.last().map_or(0, |segment| segment.end)
+    }
+
+    fn length(&self) -> f64 {
+        self.segments.last().map_or(0.0, |segment| {
+            segment.offset + (segment.end - segment.start) as f64 * segment.factor
+        })
+    }
+}
+
+/// Map from genomic coordinates to x, placing references side by side
+struct GenomeScale<'a> {
+    references: Vec<ReferenceScale<'a>>,
+    px_per_bp: f64,
+}
+
+impl<'a> GenomeScale<'a> {
+    /// Scale covering the exons of `nodes` in `width` pixels, shrinking gaps between
+    /// exons to at most `max_intron` base pairs
+    fn new(nodes: &[&'a NodeData], width: f64, max_intron: Option<usize>) -> Self {
+        let mut exons: BTreeMap<&BString, Vec<(usize, usize)>> = BTreeMap::new();
+        for node in nodes {
+            exons
+                .entry(&node.reference_id)
+                .or_default()
+                .extend(node.exons.exons.iter().map(|exon| (exon.start, exon.end)));
+        }
+
+        let mut references = Vec::new();
+        for (name, mut intervals) in exons {
+            intervals.sort_unstable();
+            let mut blocks: Vec<(usize, usize)> = Vec::new();
+            for (start, end) in intervals {
+                match blocks.last_mut() {
+                    Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                    _ => blocks.push((start, end)),
+                }
+            }
+
+            let mut segments = Vec::new();
+            let mut offset = 0.0;
+            let mut push = |start: usize, end: usize, factor: f64| {
+                segments.push(Segment {
+                    start,
+                    end,
+                    offset,
+                    factor,
+                });
+                offset += (end - start) as f64 * factor;
+            };
+            match max_intron {
+                None => push(blocks[0].0, blocks[blocks.len() - 1].1, 1.0),
+                Some(max_intron) => {
+                    for (i, &(start, end)) in blocks.iter().enumerate() {
+                        if i > 0 {
+                            let (gap_start, gap_end) = (blocks[i - 1].1, start);
+                            let gap = gap_end - gap_start;
+                            let factor = if gap > max_intron {
+                                max_intron as f64 / gap as f64
+                            } else {
+                                1.0
+                            };
+                            push(gap_start, gap_end, factor);
+                        }
+                        push(start, end, 1.0);
+                    }
+                }
+            }
+            references.push(ReferenceScale {
+                name,
+                x: 0.0,
+                segments,
+            });
+        }
+
+        let gaps = REFERENCE_GAP * references.len().saturating_sub(1) as f64;
+        let total: f64 = references.iter().map(ReferenceScale::length).sum();
+        let px_per_bp = (width - MARGIN_LEFT - MARGIN_RIGHT - gaps).max(1.0) / total.max(1.0);
+        let mut x = MARGIN_LEFT;
+        for reference in &mut references {
+            reference.x = x;
+            x += reference.length() * px_per_bp + REFERENCE_GAP;
+        }
+        Self {
+            references,
+            px_per_bp,
+        }
+    }
+
+    /// x of a genomic position, clamped to the plotted span of its reference
+    fn x(&self, reference: &BString, position: usize) -> f64 {
+        let Some(scale) = self.references.iter().find(|scale| scale.name == reference) else {
+            return MARGIN_LEFT;
+        };
+        let position = position.clamp(scale.start(), scale.end());
+        let segment = scale
+            .segments
+            .iter()
+            .find(|segment| position <= segment.end)
+            .unwrap();
+        scale.x
+            + (segment.offset + (position - segment.start) as f64 * segment.factor) * self.px_per_bp
+    }
+
+    /// Horizontal extent of the exons of a node
+    fn node_span(&self, node: &NodeData) -> (f64, f64) {
+        (
+            self.x(&node.reference_id, node.reference_start()),
+            self.x(&node.reference_id, node.reference_end()),
+        )
+    }
+}
+
+/// Write the exon boxes of a node joined by an intron line, with their top at `y`
+fn write_exons<W: Write>(
+    writer: &mut W,
+    scale: &GenomeScale,
+    node: &NodeData,
+    y: f64,
+    color: &str,
+) -> Result<()> {
+    let (start, end) = scale.node_span(node);
+    let middle = y + EXON_HEIGHT / 2.0;
+    writeln!(
+        writer,
+        r#"    <line x1="{start:.1}" y1="{middle:.1}" x2="{end:.1}" y2="{middle:.1}" stroke="{color}" stroke-width="1.5"/>"#
+    )?;
+    for exon in &node.exons.exons {
+        let x = scale.x(&node.reference_id, exon.start);
+        let width = (scale.x(&node.reference_id, exon.end) - x).max(1.0);
+        writeln!(
+            writer,
+            r#"    <rect x="{x:.1}" y="{y:.1}" width="{width:.1}" height="{EXON_HEIGHT}" rx="2" fill="{color}"/>"#
+        )?;
+    }
+    Ok(())
+}
+
+/// Write an arc from `x1` to `x2` rising `height` above `y`
+fn write_arc<W: Write>(
+    writer: &mut W,
+    (x1, x2): (f64, f64),
+    y: f64,
+    height: f64,
+    color: &str,
+    width: f64,
+    title: &str,
+) -> Result<()> {
+    let control_x = (x1 + x2) / 2.0;
+    let control_y = y - 2.0 * height;
+    writeln!(
+        writer,
+        r#"    <path d="M {x1:.1} {y:.1} Q {control_x:.1} {control_y:.1} {x2:.1} {y:.1}" fill="none" stroke="{color}" stroke-width="{width}" stroke-opacity="0.8"><title>{}</title></path>"#,
+        xml_escape(title.as_bytes())
+    )?;
+    Ok(())
+}
+
+/// Write the axis of each reference, with its name, ticks and marks at compressed gaps
+fn write_axis<W: Write>(writer: &mut W, scale: &GenomeScale, y: f64) -> Result<()> {
+    writeln!(
+        writer,
+        r##"  <g class="axis" stroke="#333333" font-size="10">"##
+    )?;
+    for reference in &scale.references {
+        let x_end = reference.x + reference.length() * scale.px_per_bp;
+        writeln!(
+            writer,
+            r#"    <line x1="{:.1}" y1="{y:.1}" x2="{x_end:.1}" y2="{y:.1}"/>"#,
+            reference.x
+        )?;
+        writeln!(
+            writer,
+            r#"    <text x="{:.1}" y="{:.1}" stroke="none" font-weight="bold" font-size="11">{}</text>"#,
+            reference.x,
+            y + 16.0,
+            xml_escape(reference.name)
+        )?;
+
+        let span = reference.end() - reference.start();
+        let ticks = ((x_end - reference.x) / MIN_TICK_SPACING).max(2.0);
+        let step = nice_step(span as f64 / ticks);
+        let mut last_x = f64::NEG_INFINITY;
+        let mut tick = reference.start().div_ceil(step) * step;
+        while tick <= reference.end() {
+            let x = scale.x(reference.name, tick);
+            if x - last_x >= MIN_TICK_SPACING {
+                writeln!(
+                    writer,
+                    r#"    <line x1="{x:.1}" y1="{y:.1}" x2="{x:.1}" y2="{:.1}"/>"#,
+                    y - 5.0
+                )?;
+                writeln!(
+                    writer,
+                    r#"    <text x="{x:.1}" y="{:.1}" stroke="none" text-anchor="middle">{}</text>"#,
+                    y - 8.0,
+                    format_position(tick)
+                )?;
+                last_x = x;
+            }
+            tick += step;
+        }
+
+        for segment in reference.segments.iter().filter(|s| s.factor < 1.0) {
+            let x = reference.x
+                + (segment.offset + (segment.end - segment.start) as f64 * segment.factor / 2.0)
+                    * scale.px_per_bp;
+            writeln!(
+                writer,
+                r#"    <path d="M {:.1} {:.1} L {:.1} {:.1} M {:.1} {:.1} L {:.1} {:.1}"/>"#,
+                x - 5.0,
+                y + 5.0,
+                x - 1.0,
+                y - 5.0,
+                x + 1.0,
+                y + 5.0,
+                x + 5.0,
+                y - 5.0
+            )?;
+        }
+    }
+    writeln!(writer, "  </g>")?;
+    Ok(())
+}
+
+/// Write the legend of node strands and edge types, right-aligned at `y`
+fn write_legend<W: Write>(
+    writer: &mut W,
+    width: f64,
+    y: f64,
+    sv_types: &BTreeSet<&BString>,
+) -> Result<()> {
+    let mut items: Vec<(String, &str, bool)> = vec![
+        ("+ strand".to_string(), FORWARD_COLOR, true),
+        ("- strand".to_string(), REVERSE_COLOR, true),
+    ];
+    items.extend(
+        sv_types
+            .iter()
+            .map(|sv_type| (sv_type.to_string(), sv_color(sv_type), false)),
+    );
+    let item_width = |label: &str| 22.0 + label.len() as f64 * CHAR_WIDTH + 12.0;
+    let mut x = width - MARGIN_RIGHT - items.iter().map(|(l, _, _)| item_width(l)).sum::<f64>();
+    writeln!(writer, r#"  <g class="legend" font-size="11">"#)?;
+    for (label, color, is_node) in &items {
+        if *is_node {
+            writeln!(
+                writer,
+                r#"    <rect x="{x:.1}" y="{:.1}" width="16" height="10" rx="2" fill="{color}"/>"#,
+                y - 9.0
+            )?;
+        } else {
+            writeln!(
+                writer,
+                r#"    <line x1="{x:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{color}" stroke-width="3"/>"#,
+                y - 4.0,
+                x + 16.0,
+                y - 4.0
+            )?;
+        }
+        writeln!(
+            writer,
+            r#"    <text x="{:.1}" y="{y:.1}">{}</text>"#,
+            x + 22.0,
+            xml_escape(label.as_bytes())
+        )?;
+        x += item_width(label);
+    }
+    writeln!(writer, "  </g>")?;
+    Ok(())
+}
+
+/// Assign nodes to rows so that neither their exons nor their labels overlap
+fn pack_rows(scale: &GenomeScale, nodes: &[&NodeData]) -> Vec<usize> {
+    let mut row_ends: Vec<f64> = Vec::new();
+    nodes
+        .iter()
+        .map(|node| {
+            let (start, end) = scale.node_span(node);
+            let half_label = node.id.len() as f64 * CHAR_WIDTH / 2.0;
+            let center = (start + end) / 2.0;
+            let (left, right) = (start.min(center - half_label), end.max(center + half_label));
+            let row = match row_ends.iter().position(|&row_end| row_end < left) {
+                Some(row) => row,
+                None => {
+                    row_ends.push(0.0);
+                    row_ends.len() - 1
+                }
+            };
+            row_ends[row] = right + 4.0;
+            row
+        })
+        .collect()
+}
+
+impl GraphSection {
+    /// Write the graph section as an SVG image, see [`GraphSection::to_svg_with_options`]
+    pub fn to_svg<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.to_svg_with_options(writer, &PlotOptions::default())
+    }
+
+    /// Write the graph section as a standalone SVG image
+    ///
+    /// Below a genome coordinate axis, edges are drawn as arcs coloured by structural
+    /// variant type and as wide as the reads shared by their ends, above the exons of
+    /// every node at true scale, coloured by strand. Each traversed path then gets a
+    /// sashimi-style track with its exons and arcs labelled with the reads supporting
+    /// each junction. With `options.max_intron`, the gaps between exons are shrunk.
+    /// Nodes without exons and the edges touching them are not drawn, and a graph without
+    /// any exons is an error.
+    pub fn to_svg_with_options<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &PlotOptions,
+    ) -> Result<()> {
+        let nodes: Vec<&NodeData> = self
+            .ordered_node_indices(OrderingPolicy::Sorted)
+            .into_iter()
+            .map(|idx| &self.inner_graph()[idx])
+            .filter(|node| !node.exons.is_empty())
+            .collect();
+        if nodes.is_empty() {
+            bail!("Graph {} has no exons to plot", self.id);
+        }
+        // Edges are drawn between exons, so edges with an endpoint without exons are left out
+        let has_exons = |idx: NodeIndex| !self.inner_graph()[idx].exons.is_empty();
+        let edges: Vec<EdgeIndex> = self
+            .ordered_edge_indices(OrderingPolicy::Sorted)
+            .into_iter()
+            .filter(|&idx| {
+                let (source, target) = self.inner_graph().edge_endpoints(idx).unwrap();
+                has_exons(source) && has_exons(target)
+            })
+            .collect();
+        let paths = self.traverse_with_ordering(OrderingPolicy::Sorted)?;
+
+        let width = options.width as f64;
+        let scale = GenomeScale::new(&nodes, width, options.max_intron);
+        let rows = pack_rows(&scale, &nodes);
+        let row_count = rows.iter().max().map_or(0, |row| row + 1);
+        let max_weight = edges
+            .iter()
+            .map(|&idx| self.edge_weight(idx))
+            .max()
+            .unwrap_or_default();
+
+        let axis_y = TITLE_HEIGHT + AXIS_HEIGHT / 2.0;
+        let arc_base = TITLE_HEIGHT + AXIS_HEIGHT + ARC_HEIGHT;
+        let node_top = arc_base + 6.0;
+        let path_top = node_top + row_count as f64 * NODE_ROW_HEIGHT + 10.0;
+        let height = path_top + paths.len() as f64 * PATH_TRACK_HEIGHT + 20.0;
+
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{height:.0}" viewBox="0 0 {} {height:.0}" font-family="Helvetica, Arial, sans-serif">"#,
+            options.width, options.width
+        )?;
+        writeln!(
+            writer,
+            r#"  <rect width="100%" height="100%" fill="white"/>"#
+        )?;
+        writeln!(
+            writer,
+            r#"  <text x="12" y="26" font-size="16" font-weight="bold">{}</text>"#,
+            xml_escape(&self.id)
+        )?;
+        let sv_types: BTreeSet<&BString> = edges
+            .iter()
+            .map(|&idx| &self.inner_graph()[idx].sv.sv_type)
+            .collect();
+        write_legend(writer, width, 26.0, &sv_types)?;
+        write_axis(writer, &scale, axis_y)?;
+
+        // Edge arcs, from the end of the source to the start of the target
+        writeln!(writer, r#"  <g class="edges">"#)?;
+        let mut drawn: Vec<(i64, i64)> = Vec::new();
+        for &idx in &edges {
+            let edge = &self.inner_graph()[idx];
+            let (source, target) = self.inner_graph().edge_endpoints(idx).unwrap();
+            let (source, target) = (&self.inner_graph()[source], &self.inner_graph()[target]);
+            let x1 = scale.x(&source.reference_id, source.reference_end());
+            let x2 = scale.x(&target.reference_id, target.reference_start());
+            // Edges between nodes at the same coordinates get flatter arcs, so they stay apart
+            let key = ((x1.min(x2) * 10.0) as i64, (x1.max(x2) * 10.0) as i64);
+            let repeats = drawn.iter().filter(|&&drawn| drawn == key).count();
+            drawn.push(key);
+            let arc_height = ((12.0 + (x2 - x1).abs() * 0.35).min(ARC_HEIGHT - 10.0)
+                - 14.0 * repeats as f64)
+                .max(8.0);
+            let weight = self.edge_weight(idx);
+            write_arc(
+                writer,
+                (x1, x2),
+                arc_base,
+                arc_height,
+                sv_color(&edge.sv.sv_type),
+                scale_style(weight, max_weight, ARC_WIDTH_RANGE),
+                &format!("{} {}: {} reads", edge.id, edge.sv.sv_type, weight),
+            )?;
+        }
+        writeln!(writer, "  </g>")?;
+
+        writeln!(
+            writer,
+            r##"  <text x="{:.1}" y="{:.1}" font-size="11" text-anchor="end" fill="#555555">nodes</text>"##,
+            MARGIN_LEFT - 10.0,
+            node_top + 24.0
+        )?;
+        for (node, row) in nodes.iter().zip(&rows) {
+            let y = node_top + *row as f64 * NODE_ROW_HEIGHT;
+            let (start, end) = scale.node_span(node);
+            writeln!(
+                writer,
+                r#"  <g class="node"><title>{} {}:{}-{} ({}), {} reads</title>"#,
+                xml_escape(&node.id),
+                xml_escape(&node.reference_id),
+                node.reference_start(),
+                node.reference_end(),
+                node.strand,
+                node.reads.len()
+            )?;
+            writeln!(
+                writer,
+                r#"    <text x="{:.1}" y="{:.1}" font-size="11" text-anchor="middle">{}</text>"#,
+                (start + end) / 2.0,
+                y + 10.0,
+                xml_escape(&node.id)
+            )?;
+            write_exons(writer, &scale, node, y + 14.0, strand_color(node.strand))?;
+            writeln!(writer, "  </g>")?;
+        }
+
+        // Sashimi-style path tracks, with the reads of each junction over its arc
+        for (i, path) in paths.iter().enumerate() {
+            let color = PATH_PALETTE[i % PATH_PALETTE.len()];
+            let y = path_top + i as f64 * PATH_TRACK_HEIGHT;
+            let exon_y = y + PATH_TRACK_HEIGHT - EXON_HEIGHT - 8.0;
+            writeln!(
+                writer,
+                r#"  <g class="path"><title>{}</title>"#,
+                xml_escape(&path.id()?)
+            )?;
+            writeln!(
+                writer,
+                r##"    <line x1="12" y1="{y:.1}" x2="{:.1}" y2="{y:.1}" stroke="#e5e5e5"/>"##,
+                width - MARGIN_RIGHT
+            )?;
+            writeln!(
+                writer,
+                r#"    <text x="{:.1}" y="{:.1}" font-size="11" text-anchor="end">{}</text>"#,
+                MARGIN_LEFT - 10.0,
+                exon_y + EXON_HEIGHT - 2.0,
+                xml_escape(&path.id()?)
+            )?;
+            let path_nodes: Vec<&NodeData> = path
+                .nodes
+                .iter()
+                .map(|&idx| &self.inner_graph()[idx])
+                .collect();
+            for node in &path_nodes {
+                if !node.exons.is_empty() {
+                    write_exons(writer, &scale, node, exon_y, color)?;
+                }
+            }
+            for (pair, &edge_idx) in path_nodes.windows(2).zip(&path.edges) {
+                if pair[0].exons.is_empty() || pair[1].exons.is_empty() {
+                    continue;
+                }
+                let x1 = scale.x(&pair[0].reference_id, pair[0].reference_end());
+                let x2 = scale.x(&pair[1].reference_id, pair[1].reference_start());
+                let arc_height = (8.0 + (x2 - x1).abs() * 0.2).min(PATH_TRACK_HEIGHT - 30.0);
+                let weight = self.edge_weight(edge_idx);
+                write_arc(
+                    writer,
+                    (x1, x2),
+                    exon_y,
+                    arc_height,
+                    color,
+                    scale_style(weight, max_weight, ARC_WIDTH_RANGE),
+                    &format!("{}: {} reads", self.inner_graph()[edge_idx].id, weight),
+                )?;
+                writeln!(
+                    writer,
+                    r#"    <text x="{:.1}" y="{:.1}" font-size="10" text-anchor="middle">{}</text>"#,
+                    (x1 + x2) / 2.0,
+                    exon_y - arc_height - 3.0,
+                    weight
+                )?;
+            }
+            writeln!(writer, "  </g>")?;
+        }
+
+        writeln!(writer, "</svg>")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::TSGraph;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_nice_step() {
+        assert_eq!(nice_step(0.4), 1);
+        assert_eq!(nice_step(7.0), 10);
+        assert_eq!(nice_step(130.0), 200);
+        assert_eq!(nice_step(4500.0), 5000);
+        assert_eq!(format_position(1234567), "1,234,567");
+        assert_eq!(format_position(500), "500");
+    }
+
+    #[test]
+    fn test_genome_scale() -> Result<()> {
+        let tsg_graph = TSGraph::from_file("tests/data/test.tsg")?;
+        let graph = tsg_graph.default_graph().unwrap();
+        let nodes = graph.nodes();
+        let chr1 = BString::from("chr1");
+
+        let scale = GenomeScale::new(&nodes, 1200.0, None);
+        assert_eq!(scale.x(&chr1, 1000), MARGIN_LEFT);
+        assert_eq!(scale.x(&chr1, 2700), 1200.0 - MARGIN_RIGHT);
+        let intron = scale.x(&chr1, 1500) - scale.x(&chr1, 1200);
+
+        let compressed = GenomeScale::new(&nodes, 1200.0, Some(50));
+        assert_eq!(compressed.x(&chr1, 2700), 1200.0 - MARGIN_RIGHT);
+        let compressed_intron = compressed.x(&chr1, 1500) - compressed.x(&chr1, 1200);
+        let exon = compressed.x(&chr1, 1200) - compressed.x(&chr1, 1000);
+        assert!(compressed_intron < intron);
+        assert!(
+            (exon / compressed_intron - 4.0).abs() < 1e-9,
+            "exons keep their scale"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_svg() -> Result<()> {
+        let tsg_graph = TSGraph::from_file("tests/data/test.tsg")?;
+        let graph = tsg_graph.default_graph().unwrap();
+        let mut buf = Vec::new();
+        graph.to_svg_with_options(&mut buf, &PlotOptions::builder().max_intron(100).build())?;
+        let svg = String::from_utf8(buf)?;
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"1200\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<g class=\"node\">").count(), 5);
+        assert_eq!(svg.matches("<g class=\"path\">").count(), 2);
+        assert!(svg.contains("<title>e1 INV: 2 reads</title>"));
+        assert!(svg.contains("stroke=\"#9467bd\""), "INV arcs");
+        assert!(svg.contains(">TDUP</text>"), "legend");
+        assert!(svg.contains(">1,200</text>"), "axis ticks");
+
+        let empty = GraphSection::new("empty".into());
+        assert!(empty.to_svg(&mut Vec::new()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_svg_skips_nodes_without_exons() -> Result<()> {
+        let tsg_graph = TSGraph::from_str(
+            "N\ta\tchr1:+:100-200\tr1:SO\n\
+             N\tb\t:+:\tr1:SI\n\
+             E\te1\ta\tb\tchr1,chr1,200,300,splice\n",
+        )?;
+        let mut buf = Vec::new();
+        tsg_graph.default_graph().unwrap().to_svg(&mut buf)?;
+        let svg = String::from_utf8(buf)?;
+        assert_eq!(svg.matches("<g class=\"node\">").count(), 1);
+        assert!(!svg.contains("<title>e1"));
+        Ok(())
+    }
+}
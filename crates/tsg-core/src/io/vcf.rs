@@ -1,71 +1,848 @@
-use crate::graph::TSGraph;
-use anyhow::Result;
-use std::io::Write;
+use super::gtf::percent_decode;
+use crate::graph::{
+    Attribute, DEFAULT_GRAPH_ID, EdgeData, Exons, GraphSection, Interval, NodeData, OrderingPolicy,
+    StructuralVariant, TSGraph, VcfRecord, WriteOptions,
+};
+use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
+use anyhow::{Context, Result, anyhow, bail};
+use bstr::{BString, ByteSlice};
+use noodles::fasta;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, Write};
+use std::path::Path;
+use tracing::{debug, warn};
 
+/// Meta-information lines of the keys the writer adds to INFO
+static VCF_SV_INFO: &[(&str, &str)] = &[
+    (
+        "SVTYPE",
+        "##INFO=<ID=SVTYPE,Number=1,Type=String,Description=\"Type of structural variant\">",
+    ),
+    (
+        "END",
+        "##INFO=<ID=END,Number=1,Type=Integer,Description=\"End position of the structural variant\">",
+    ),
+    (
+        "MATEID",
+        "##INFO=<ID=MATEID,Number=.,Type=String,Description=\"ID of the mate breakend\">",
+    ),
+    (
+        "EVENT",
+        "##INFO=<ID=EVENT,Number=1,Type=String,Description=\"ID of the edge the breakend belongs to\">",
+    ),
+    (
+        "EVENTTYPE",
+        "##INFO=<ID=EVENTTYPE,Number=1,Type=String,Description=\"Type of the edge the breakend belongs to\">",
+    ),
+];
+
+/// Description of an `##ALT` line for a symbolic allele
+fn alt_description(sv_type: &str) -> String {
+    match sv_type.to_ascii_uppercase().as_str() {
+        "DEL" => "Deletion".to_string(),
+        "INS" => "Insertion".to_string(),
+        "DUP" => "Duplication".to_string(),
+        "TDUP" | "DUP:TANDEM" => "Tandem duplication".to_string(),
+        "IDUP" => "Inverted duplication".to_string(),
+        "CNV" => "Copy number variable region".to_string(),
+        "SPLICE" => "Splice junction".to_string(),
+        _ => format!("Structural variant of type {}", sv_type),
+    }
+}
+
+/// VCF type of an attribute, where string attributes without a value are flags
+fn info_type(attr: &Attribute) -> &'static str {
+    match attr.attribute_type {
+        'i' => "Integer",
+        'f' => "Float",
+        _ if attr.value.is_empty() => "Flag",
+        _ => "String",
+    }
+}
+
+/// Meta-information and column header lines written before the SV records of older releases
 static VCF_HEADER: &[&str] = &[
     "##fileformat=VCFv4.3",
     "##source=tsg",
-    "##INFO=<ID=CANONICAL,Number=0,Type=Flag,Description=\"Canonical splice site\">",
-    "##INFO=<ID=NONCANONICAL,Number=0,Type=Flag,Description=\"Noncanonical splice site\">",
-    "##INFO=<ID=BOUNDARY,Number=1,Type=String,Description=\"The coding exon boundary type of event, BOTH, LEFT, RIGHT, NEITHER.\">",
-    "##INFO=<ID=DP1,Number=1,Type=Integer,Description=\"Total read depth at the breakpoint1\">",
-    "##INFO=<ID=DP2,Number=1,Type=Integer,Description=\"Total read depth at the breakpoint2\">",
-    "##INFO=<ID=SR,Number=1,Type=Integer,Description=\"The number of support reads for the breakpoints\">",
-    "##INFO=<ID=OSR,Number=1,Type=Integer,Description=\"The number of support reads for the breakpoints before rescuer\">",
-    "##INFO=<ID=PSI,Number=1,Type=Float,Description=\"Estimated Percent splice-in in the range (0,1], representing the percentage of NLS transcripts\">",
-    "##INFO=<ID=SVMETHOD,Number=1,Type=String,Description=\"Type of approach used to detect SV\">",
-    "##INFO=<ID=SVTYPE,Number=1,Type=String,Description=\"The type of event, DEL, TDUP, IDUP, INV, TRA.\">",
-    "##INFO=<ID=SVLEN,Number=1,Type=Integer,Description=\"Difference in length between REF and ALT alleles\">",
-    "##INFO=<ID=CHR2,Number=1,Type=String,Description=\"Chromosome for END coordinate in case of a translocation\">",
-    "##INFO=<ID=SVEND,Number=1,Type=Integer,Description=\"2nd position of the structural variant\">",
-    "##INFO=<ID=END,Number=1,Type=Integer,Description=\"A placeholder for END coordinate in case of a translocation\">",
-    "##INFO=<ID=STRAND1,Number=1,Type=String,Description=\"Strand for breakpoint1\">",
-    "##INFO=<ID=STRAND2,Number=1,Type=String,Description=\"Strand for breakpoint2\">",
-    "##INFO=<ID=MODE1,Number=1,Type=String,Description=\"Mode for softclipped reads at breakpoint1\">",
-    "##INFO=<ID=MODE2,Number=1,Type=String,Description=\"Mode for softclipped reads at breakpoint2\">",
-    "##INFO=<ID=GENE1,Number=1,Type=String,Description=\"Overlapped coding gene for breakpoint1\">",
-    "##INFO=<ID=GENE2,Number=1,Type=String,Description=\"Overlapped coding gene for breakpoint2\">",
-    "##INFO=<ID=MEGAEXON1,Number=.,Type=String,Description=\"ID for source mega exon\">",
-    "##INFO=<ID=MEGAEXON2,Number=.,Type=String,Description=\"ID for target mega exon\">",
-    "##INFO=<ID=HOMSEQ,Number=1,Type=String,Description=\"MicroHomology sequence\">",
-    "##INFO=<ID=INSSEQ,Number=1,Type=String,Description=\"MicroInsertion sequence\">",
-    "##INFO=<ID=TRANSCRIPT_ID,Number=.,Type=String,Description=\"Transcript ID\">",
-    "##INFO=<ID=GENE_ID,Number=1,Type=String,Description=\"Gene ID\">",
-    "##INFO=<ID=SR_ID,Number=.,Type=String,Description=\"Support read ID\">",
-    "##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">",
-    "##ALT=<ID=DEL,Description=\"Deletion\">",
-    "##ALT=<ID=TDUP,Description=\"Tandem duplication\">",
-    "##ALT=<ID=IDUP,Description=\"Inverted duplication\">",
-    "##ALT=<ID=INV,Description=\"Inversion\">",
-    "##ALT=<ID=TRA,Description=\"Translocation\">",
-    "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT",
+    "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO",
 ];
 
+/// Write all graphs as VCF 4.3 with `N` as reference bases, see [`to_vcf_with_reference`]
 pub fn to_vcf<W: Write>(tsg_graph: &TSGraph, writer: &mut W) -> Result<()> {
-    let paths = tsg_graph.traverse_all_graphs()?;
+    let graphs = tsg_graph.ordered_graphs(OrderingPolicy::Sorted);
+    write_vcf(&graphs, writer, None)
+}
+
+/// Write all graphs as VCF 4.3, see [`to_vcf`]
+#[deprecated(note = "VCF records are always sorted by position; use `to_vcf`")]
+pub fn to_vcf_with_options<W: Write>(
+    tsg_graph: &TSGraph,
+    writer: &mut W,
+    _options: &WriteOptions,
+) -> Result<()> {
+    to_vcf(tsg_graph, writer)
+}
 
-    let header = VCF_HEADER;
-    for line in header {
+/// Write the VCF file format, source and column header lines
+#[deprecated(
+    note = "the header depends on the contigs and INFO keys of the records; use `VcfCollector`"
+)]
+pub fn write_vcf_header<W: Write>(writer: &mut W) -> Result<()> {
+    for line in VCF_HEADER {
         writeln!(writer, "{}", line)?;
     }
+    Ok(())
+}
+
+/// Write the edges of all graphs as VCF 4.3, with reference bases from an indexed FASTA file
+///
+/// Every edge becomes one record, or a pair of breakend records for translocations and
+/// inversions (see [`EdgeData::to_vcf_records`]), sorted by contig and position. Edge IDs are
+/// prefixed with their graph ID when there are several graphs. The header declares the
+/// contigs in the order and with the lengths of the FASTA index, followed by any other
+/// reference of the graphs, and an `##INFO` line for every attribute tag in use, typed after
+/// its attribute type.
+pub fn to_vcf_with_reference<W: Write, P: AsRef<Path>>(
+    tsg_graph: &TSGraph,
+    writer: &mut W,
+    reference: P,
+) -> Result<()> {
+    let graphs = tsg_graph.ordered_graphs(OrderingPolicy::Sorted);
+    write_vcf(&graphs, writer, Some(reference.as_ref()))
+}
 
-    for path in paths {
-        let seq = path.to_vcf()?;
-        writeln!(writer, "{}", seq)?;
+/// Write the edges of a single graph section as VCF 4.3, see [`to_vcf_with_reference`]
+pub fn graph_to_vcf<W: Write>(graph: &GraphSection, writer: &mut W) -> Result<()> {
+    write_vcf(&[graph], writer, None)
+}
+
+/// Write the edges of a single graph section as VCF 4.3, see [`graph_to_vcf`]
+#[deprecated(note = "VCF records are always sorted by position; use `graph_to_vcf`")]
+pub fn graph_to_vcf_with_options<W: Write>(
+    graph: &GraphSection,
+    writer: &mut W,
+    _options: &WriteOptions,
+) -> Result<()> {
+    graph_to_vcf(graph, writer)
+}
+
+fn write_vcf<W: Write>(
+    graphs: &[&GraphSection],
+    writer: &mut W,
+    reference: Option<&Path>,
+) -> Result<()> {
+    let mut collector = VcfCollector::new();
+    for graph in graphs {
+        collector.add_graph(graph);
+    }
+    collector.write(writer, reference)
+}
+
+/// VCF records of graph sections added one at a time, written as VCF 4.3 at the end
+///
+/// Only the records and what the header needs are kept, so the graph sections of a large
+/// file can be streamed, for instance from a
+/// [`GraphSectionReader`](crate::io::GraphSectionReader). The output is that of
+/// [`to_vcf_with_reference`].
+#[derive(Debug, Default)]
+pub struct VcfCollector {
+    /// Records with the ID of their graph section
+    records: Vec<(BString, VcfRecord)>,
+    references: BTreeSet<BString>,
+    /// VCF type of every edge attribute tag
+    info_types: BTreeMap<BString, &'static str>,
+    graph_count: usize,
+}
+
+impl VcfCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the edges and node references of a graph section
+    pub fn add_graph(&mut self, graph: &GraphSection) {
+        self.graph_count += 1;
+        for node in graph.nodes() {
+            self.references.insert(node.reference_id.clone());
+        }
+        for edge in graph.edges() {
+            for attr in edge.attributes.values() {
+                let kind = info_type(attr);
+                self.info_types
+                    .entry(attr.tag.clone())
+                    .and_modify(|existing| {
+                        if *existing != kind {
+                            *existing = "String";
+                        }
+                    })
+                    .or_insert(kind);
+            }
+            self.records.extend(
+                edge.to_vcf_records()
+                    .into_iter()
+                    .map(|record| (graph.id.clone(), record)),
+            );
+        }
+    }
+
+    /// Write the header and the records sorted by contig and position
+    ///
+    /// Reference bases are read from the indexed FASTA file `reference` when given.
+    pub fn write<W: Write>(self, writer: &mut W, reference: Option<&Path>) -> Result<()> {
+        // edge IDs are only unique within their graph
+        let prefix = self.graph_count > 1;
+        let records: Vec<VcfRecord> = self
+            .records
+            .into_iter()
+            .map(|(graph_id, mut record)| {
+                if prefix {
+                    record.id = format!("{}.{}", graph_id, record.id).into();
+                    for (key, value) in record.info.iter_mut() {
+                        if key == "MATEID" || key == "EVENT" {
+                            *value = format!("{}.{}", graph_id, value).into();
+                        }
+                    }
+                }
+                record
+            })
+            .collect();
+        write_records(
+            records,
+            self.references,
+            &self.info_types,
+            writer,
+            reference,
+        )
+    }
+}
+
+fn write_records<W: Write>(
+    mut records: Vec<VcfRecord>,
+    mut names: BTreeSet<BString>,
+    types: &BTreeMap<BString, &'static str>,
+    writer: &mut W,
+    reference: Option<&Path>,
+) -> Result<()> {
+    let mut fasta = match reference {
+        Some(path) => Some(
+            fasta::io::indexed_reader::Builder::default()
+                .build_from_path(path)
+                .with_context(|| format!("Failed to open indexed FASTA {}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    // contigs of the FASTA index come first, in its order
+    let mut contigs: Vec<(BString, Option<u64>)> = match &fasta {
+        Some(reader) => reader
+            .index()
+            .as_ref()
+            .iter()
+            .map(|record| (BString::from(record.name()), Some(record.length())))
+            .collect(),
+        None => Vec::new(),
+    };
+    names.extend(records.iter().map(|record| record.chrom.clone()));
+    for name in names {
+        if !contigs.iter().any(|(contig, _)| *contig == name) {
+            contigs.push((name, None));
+        }
+    }
+    let rank: HashMap<&BString, usize> = contigs
+        .iter()
+        .enumerate()
+        .map(|(idx, (name, _))| (name, idx))
+        .collect();
+    records.sort_by(|a, b| (rank[&a.chrom], a.pos, &a.id).cmp(&(rank[&b.chrom], b.pos, &b.id)));
+
+    if let Some(reader) = fasta.as_mut() {
+        for record in records.iter_mut() {
+            let region = format!("{}:{}-{}", record.chrom, record.pos, record.pos)
+                .parse()
+                .with_context(|| format!("Invalid position of record {}", record.id))?;
+            let base = reader
+                .query(&region)
+                .with_context(|| format!("Failed to fetch the reference base of {}", record.id))?
+                .sequence()
+                .as_ref()
+                .first()
+                .copied()
+                .unwrap_or(b'N');
+            record.set_reference_base(base);
+        }
+    }
+
+    writeln!(writer, "##fileformat=VCFv4.3")?;
+    writeln!(writer, "##source=tsg")?;
+    if let Some(path) = reference {
+        writeln!(writer, "##reference=file://{}", path.display())?;
+    }
+    for (name, length) in &contigs {
+        match length {
+            Some(length) => writeln!(writer, "##contig=<ID={},length={}>", name, length)?,
+            None => writeln!(writer, "##contig=<ID={}>", name)?,
+        }
+    }
+
+    // INFO keys in use, typed after their attributes
+    let mut info_keys: BTreeSet<&BString> = BTreeSet::new();
+    for record in &records {
+        info_keys.extend(record.info.iter().map(|(key, _)| key));
+    }
+    for (key, line) in VCF_SV_INFO {
+        if info_keys.contains(&BString::from(*key)) {
+            writeln!(writer, "{}", line)?;
+        }
+    }
+    for (key, kind) in types {
+        if info_keys.contains(key) && !VCF_SV_INFO.iter().any(|(sv_key, _)| key == sv_key) {
+            let number = if *kind == "Flag" { 0 } else { 1 };
+            writeln!(
+                writer,
+                "##INFO=<ID={},Number={},Type={},Description=\"Edge attribute {}\">",
+                key, number, kind, key
+            )?;
+        }
+    }
+
+    let symbolic: BTreeSet<&[u8]> = records
+        .iter()
+        .filter_map(|record| record.alt.strip_prefix(b"<")?.strip_suffix(b">"))
+        .collect();
+    for sv_type in symbolic {
+        let sv_type = sv_type.to_str_lossy();
+        writeln!(
+            writer,
+            "##ALT=<ID={},Description=\"{}\">",
+            sv_type,
+            alt_description(&sv_type)
+        )?;
+    }
+
+    writeln!(writer, "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO")?;
+    for record in &records {
+        writeln!(writer, "{}", record)?;
     }
     Ok(())
 }
 
+/// INFO keys that describe the structural variant itself rather than an attribute
+const VCF_SV_KEYS: &[&str] = &[
+    "SVTYPE",
+    "END",
+    "SVEND",
+    "CHR2",
+    "MATEID",
+    "EVENT",
+    "EVENTTYPE",
+];
+
+/// Attribute type of an INFO value: from its `##INFO` declaration, otherwise from its content
+fn info_attribute(key: &str, value: Option<&str>, types: &HashMap<String, String>) -> Attribute {
+    let Some(value) = value else {
+        // Flags have no value and are kept as empty strings
+        return Attribute::builder().tag(key).value("").build();
+    };
+    let attribute_type = match types.get(key).map(String::as_str) {
+        Some("Integer") if value.parse::<i64>().is_ok() => 'i',
+        Some("Float") if value.parse::<f64>().is_ok() => 'f',
+        Some(_) => 'Z',
+        None if value.parse::<i64>().is_ok() => 'i',
+        None if value.parse::<f64>().is_ok() => 'f',
+        None => 'Z',
+    };
+    Attribute::builder()
+        .tag(key)
+        .attribute_type(attribute_type)
+        .value(value)
+        .build()
+}
+
+/// Mate position of a breakend ALT such as `N[chr2:3000[` or `]chr2:3000]N`
+fn parse_breakend(alt: &str) -> Option<(&str, usize)> {
+    let start = alt.find(['[', ']'])?;
+    let bracket = alt[start..].chars().next()?;
+    let mate = &alt[start + 1..];
+    let mate = &mate[..mate.find(bracket)?];
+    let (chrom, pos) = mate.rsplit_once(':')?;
+    Some((chrom, pos.parse().ok()?))
+}
+
+/// Read the structural variants of a VCF stream as edges
+///
+/// Symbolic alleles (`<DEL>`, `<INV>`, `<DUP:TANDEM>`, ...) span from `POS` to `SVEND` or
+/// `END` on `CHR2` or the same chromosome; breakends (`BND`) join `POS` to their mate, and
+/// only the first record of a `MATEID` pair is kept, named after its `EVENT` when set. The
+/// SV type comes from `SVTYPE` or the allele, or for breakends from `EVENTTYPE` when set. Other INFO fields become attributes typed
+/// from their `##INFO` declaration, and `QUAL` and `FILTER` are kept when set. Records with
+/// sequence alleles and no `SVTYPE` are not structural variants and are skipped. Edges are
+/// named after the record ID, or `sv<n>` when it is missing.
+pub fn read_vcf_edges<R: BufRead>(reader: R) -> Result<Vec<EdgeData>> {
+    let mut types: HashMap<String, String> = HashMap::new();
+    let mut edges = Vec::new();
+    let mut seen_ids: HashSet<String> = HashSet::new();
+
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if let Some(info) = line.strip_prefix("##INFO=<") {
+            let fields: HashMap<&str, &str> = info
+                .trim_end_matches('>')
+                .split(',')
+                .filter_map(|field| field.split_once('='))
+                .collect();
+            if let (Some(id), Some(kind)) = (fields.get("ID"), fields.get("Type")) {
+                types.insert(id.to_string(), kind.to_string());
+            }
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let edge = parse_vcf_record(&line, &types, &seen_ids, edges.len())
+            .with_context(|| format!("Invalid VCF record at line {}", idx + 1))?;
+        if let Some(edge) = edge {
+            if let Some(id) = line.split('\t').nth(2) {
+                seen_ids.insert(id.to_string());
+            }
+            seen_ids.insert(edge.id.to_string());
+            edges.push(edge);
+        }
+    }
+    Ok(edges)
+}
+
+fn parse_vcf_record(
+    line: &str,
+    types: &HashMap<String, String>,
+    seen_ids: &HashSet<String>,
+    count: usize,
+) -> Result<Option<EdgeData>> {
+    // CHROM  POS  ID  REF  ALT  QUAL  FILTER  INFO  [FORMAT ...]
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() < 8 {
+        bail!("expected at least 8 fields, found {}", fields.len());
+    }
+    let chrom = fields[0];
+    let pos: usize = fields[1].parse().context("invalid POS")?;
+    let alt = fields[4].split(',').next().unwrap_or_default();
+
+    let info: Vec<(&str, Option<&str>)> = fields[7]
+        .split(';')
+        .filter(|item| !item.is_empty() && *item != ".")
+        .map(|item| match item.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (item, None),
+        })
+        .collect();
+    let lookup = |key: &str| {
+        info.iter()
+            .find(|(k, _)| *k == key)
+            .and_then(|(_, value)| *value)
+    };
+
+    if lookup("MATEID").is_some_and(|mates| mates.split(',').any(|mate| seen_ids.contains(mate))) {
+        debug!("Skipping the mate of an imported breakend: {}", fields[2]);
+        return Ok(None);
+    }
+
+    let (sv_type, reference_name2, breakpoint2) =
+        if let Some((mate_chrom, mate_pos)) = parse_breakend(alt) {
+            let sv_type = lookup("EVENTTYPE").or_else(|| lookup("SVTYPE"));
+            (sv_type.unwrap_or("BND"), mate_chrom, mate_pos)
+        } else {
+            let symbolic = alt.strip_prefix('<').and_then(|alt| alt.strip_suffix('>'));
+            let Some(sv_type) = lookup("SVTYPE").or(symbolic) else {
+                debug!("Skipping record without structural variant: {}", fields[2]);
+                return Ok(None);
+            };
+            let end = match lookup("SVEND").or_else(|| lookup("END")) {
+                Some(end) => end.parse().context("invalid END")?,
+                None => {
+                    let length: i64 = lookup("SVLEN")
+                        .unwrap_or("0")
+                        .parse()
+                        .context("invalid SVLEN")?;
+                    pos + length.unsigned_abs() as usize
+                }
+            };
+            (sv_type, lookup("CHR2").unwrap_or(chrom), end)
+        };
+
+    let mut attributes: HashMap<BString, Attribute> = info
+        .iter()
+        .filter(|(key, _)| !VCF_SV_KEYS.contains(key))
+        .map(|&(key, value)| {
+            let value = value.map(percent_decode);
+            (key.into(), info_attribute(key, value.as_deref(), types))
+        })
+        .collect();
+    if fields[5] != "." {
+        let attr = info_attribute("QUAL", Some(fields[5]), types);
+        attributes.insert("QUAL".into(), attr);
+    }
+    if fields[6] != "." {
+        let attr = Attribute::builder().tag("FILTER").value(fields[6]).build();
+        attributes.insert("FILTER".into(), attr);
+    }
+
+    // breakend pairs written from an edge name it in EVENT
+    let event = lookup("EVENT").filter(|_| parse_breakend(alt).is_some());
+    let id = match (event, fields[2]) {
+        (Some(event), _) => event.to_string(),
+        (None, ".") => format!("sv{}", count + 1),
+        (None, id) => id.to_string(),
+    };
+    Ok(Some(EdgeData {
+        id: id.into(),
+        sv: StructuralVariant {
+            reference_name1: chrom.into(),
+            reference_name2: reference_name2.into(),
+            breakpoint1: pos,
+            breakpoint2,
+            sv_type: sv_type.into(),
+        },
+        attributes,
+    }))
+}
+
+/// Nodes of a graph section by exon boundary, for matching breakpoints
+#[derive(Default)]
+struct BoundaryIndex {
+    ends: HashMap<(BString, usize), NodeIndex>,
+    starts: HashMap<(BString, usize), NodeIndex>,
+}
+
+impl BoundaryIndex {
+    fn new(graph: &GraphSection) -> Self {
+        let mut index = Self {
+            ends: HashMap::new(),
+            starts: HashMap::new(),
+        };
+        for node_idx in graph.ordered_node_indices(OrderingPolicy::Sorted) {
+            let node = graph.node_by_idx(node_idx).unwrap();
+            for exon in &node.exons.exons {
+                let reference = node.reference_id.clone();
+                index
+                    .starts
+                    .entry((reference.clone(), exon.start))
+                    .or_insert(node_idx);
+                index.ends.entry((reference, exon.end)).or_insert(node_idx);
+            }
+        }
+        index
+    }
+
+    /// The node ending at a breakpoint, or else starting at it
+    fn source(&self, reference: &BString, position: usize) -> Option<NodeIndex> {
+        let key = (reference.clone(), position);
+        self.ends
+            .get(&key)
+            .or_else(|| self.starts.get(&key))
+            .copied()
+    }
+
+    /// The node starting at a breakpoint, or else ending at it
+    fn target(&self, reference: &BString, position: usize) -> Option<NodeIndex> {
+        let key = (reference.clone(), position);
+        self.starts
+            .get(&key)
+            .or_else(|| self.ends.get(&key))
+            .copied()
+    }
+}
+
+impl GraphSection {
+    /// Add a structural variant edge between the nodes at its breakpoints
+    ///
+    /// The source is a node with an exon ending (or else starting) at `breakpoint1` and the
+    /// target a node with an exon starting (or else ending) at `breakpoint2`. Breakpoints
+    /// without such a node get a placeholder node `<chrom>_<position>` spanning that single
+    /// position. An edge whose ID is already taken gets a numeric suffix.
+    pub fn add_sv_edge(&mut self, edge: EdgeData) -> Result<EdgeIndex> {
+        let index = BoundaryIndex::new(self);
+        self.add_sv_edge_with_index(&index, edge)
+    }
+
+    fn add_sv_edge_with_index(
+        &mut self,
+        index: &BoundaryIndex,
+        mut edge: EdgeData,
+    ) -> Result<EdgeIndex> {
+        let sv = edge.sv.clone();
+        let source = match index.source(&sv.reference_name1, sv.breakpoint1) {
+            Some(idx) => self.node_by_idx(idx).unwrap().id.clone(),
+            None => self.add_breakpoint_node(&sv.reference_name1, sv.breakpoint1)?,
+        };
+        let target = match index.target(&sv.reference_name2, sv.breakpoint2) {
+            Some(idx) => self.node_by_idx(idx).unwrap().id.clone(),
+            None => self.add_breakpoint_node(&sv.reference_name2, sv.breakpoint2)?,
+        };
+
+        if self.edge_indices().contains_key(&edge.id) {
+            let mut suffix = 1;
+            while self
+                .edge_indices()
+                .contains_key(format!("{}_{}", edge.id, suffix).as_bytes())
+            {
+                suffix += 1;
+            }
+            let id: BString = format!("{}_{}", edge.id, suffix).into();
+            warn!("Edge {} already exists, importing it as {}", edge.id, id);
+            edge.id = id;
+        }
+        self.add_edge(source.as_bstr(), target.as_bstr(), edge)
+    }
+
+    /// Placeholder node for a breakpoint that matches no exon boundary
+    fn add_breakpoint_node(&mut self, reference: &BString, position: usize) -> Result<BString> {
+        let id: BString = format!("{}_{}", reference, position).into();
+        if !self.node_indices().contains_key(&id) {
+            self.add_node(NodeData {
+                id: id.clone(),
+                reference_id: reference.clone(),
+                exons: Exons {
+                    exons: vec![Interval {
+                        start: position,
+                        end: position,
+                    }],
+                },
+                ..Default::default()
+            })?;
+        }
+        Ok(id)
+    }
+}
+
+impl TSGraph {
+    /// Merge the structural variants of a VCF stream into the graph, see [`read_vcf_edges`]
+    ///
+    /// Each edge goes to the first graph, in ID order, with a node at its first breakpoint,
+    /// otherwise at its second one, and otherwise to `graph_id` (the default graph if not
+    /// given), which is created when missing. Returns the number of imported edges.
+    pub fn import_vcf<R: BufRead>(&mut self, reader: R, graph_id: Option<&str>) -> Result<usize> {
+        let edges = read_vcf_edges(reader)?;
+        let fallback: BString = graph_id.unwrap_or(DEFAULT_GRAPH_ID).into();
+
+        let mut graph_ids: Vec<BString> = self.graphs.keys().cloned().collect();
+        graph_ids.sort();
+        let indices: Vec<(BString, BoundaryIndex)> = graph_ids
+            .into_iter()
+            .map(|id| {
+                let index = BoundaryIndex::new(&self.graphs[&id]);
+                (id, index)
+            })
+            .collect();
+
+        let count = edges.len();
+        for edge in edges {
+            let sv = &edge.sv;
+            let graph_id = indices
+                .iter()
+                .find(|(_, index)| index.source(&sv.reference_name1, sv.breakpoint1).is_some())
+                .or_else(|| {
+                    indices.iter().find(|(_, index)| {
+                        index.target(&sv.reference_name2, sv.breakpoint2).is_some()
+                    })
+                })
+                .map_or_else(|| fallback.clone(), |(id, _)| id.clone());
+
+            if !self.graphs.contains_key(&graph_id) {
+                self.add_graph(GraphSection::new(graph_id.clone()));
+            }
+            let empty = BoundaryIndex::default();
+            let index = indices
+                .iter()
+                .find(|(id, _)| *id == graph_id)
+                .map_or(&empty, |(_, index)| index);
+            let graph = self
+                .graphs
+                .get_mut(&graph_id)
+                .ok_or_else(|| anyhow!("Graph {} not found", graph_id))?;
+            graph.add_sv_edge_with_index(index, edge)?;
+        }
+        Ok(count)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::io::GraphSectionReader;
 
     #[test]
     fn test_to_vcf() {
         let tsg_graph = TSGraph::from_file("tests/data/test.tsg").unwrap();
-        let output = "tests/data/test.vcf";
+        let output = std::env::temp_dir().join("tsg_vcf_test.vcf");
         let file = std::fs::File::create(output).unwrap();
         let mut writer = std::io::BufWriter::new(file);
         to_vcf(&tsg_graph, &mut writer).unwrap();
     }
+
+    #[test]
+    fn test_vcf_collector_streams_graphs() -> Result<()> {
+        let mut collector = VcfCollector::new();
+        for graph in GraphSectionReader::from_file("tests/data/test_mul.tsg")? {
+            collector.add_graph(&graph?);
+        }
+        let mut streamed = Vec::new();
+        collector.write(&mut streamed, None)?;
+
+        let mut buf = Vec::new();
+        to_vcf(&TSGraph::from_file("tests/data/test_mul.tsg")?, &mut buf)?;
+        assert_eq!(String::from_utf8(streamed)?, String::from_utf8(buf)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_vcf_with_reference() -> Result<()> {
+        let mut tsg_graph = TSGraph::from_file("tests/data/test.tsg")?;
+        let reader = std::io::BufReader::new(std::fs::File::open("tests/data/sv.vcf")?);
+        tsg_graph.import_vcf(reader, None)?;
+
+        let mut buf = Vec::new();
+        to_vcf_with_reference(&tsg_graph, &mut buf, "tests/data/sv.fa")?;
+        let vcf = String::from_utf8(buf)?;
+        let lines: Vec<&str> = vcf.lines().collect();
+        assert_eq!(lines[0], "##fileformat=VCFv4.3");
+        assert!(lines.contains(&"##contig=<ID=chr1,length=3000>"));
+        assert!(lines.contains(&"##contig=<ID=chr2,length=5100>"));
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("##INFO=<ID=SR,Number=1,Type=Integer"))
+        );
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("##INFO=<ID=PSI,Number=1,Type=Float"))
+        );
+        assert!(lines.iter().any(|line| line.starts_with("##ALT=<ID=TDUP,")));
+        assert!(!lines.iter().any(|line| line.starts_with("##ALT=<ID=INV,")));
+
+        let records: Vec<Vec<&str>> = lines
+            .iter()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.split('\t').collect())
+            .collect();
+        assert!(records.iter().all(|fields| fields.len() == 8));
+        assert!(records.iter().all(|fields| !fields[7].ends_with(';')));
+        let positions: Vec<(&str, usize)> = records
+            .iter()
+            .map(|fields| (fields[0], fields[1].parse().unwrap()))
+            .collect();
+        assert!(positions.is_sorted());
+
+        // flags are declared and written without a value, and read back as flags
+        assert!(lines.contains(
+            &"##INFO=<ID=IMPRECISE,Number=0,Type=Flag,Description=\"Edge attribute IMPRECISE\">"
+        ));
+        assert!(
+            records
+                .iter()
+                .any(|fields| fields[7].split(';').any(|item| item == "IMPRECISE"))
+        );
+        let edges = read_vcf_edges(vcf.as_bytes())?;
+        assert!(edges.iter().any(|edge| {
+            edge.attributes
+                .get(&BString::from("IMPRECISE"))
+                .is_some_and(|attr| attr.to_string() == "IMPRECISE:Z:")
+        }));
+
+        // reference bases come from the FASTA and are repeated in breakend alleles
+        let mut fasta =
+            fasta::io::indexed_reader::Builder::default().build_from_path("tests/data/sv.fa")?;
+        let bnd = records.iter().find(|fields| fields[2] == "bnd1_1").unwrap();
+        let base = fasta.query(&"chr1:2200-2200".parse()?)?.sequence().as_ref()[0] as char;
+        assert_eq!(bnd[3], base.to_string());
+        assert_eq!(bnd[4], format!("{}[chr2:5000[", base));
+        assert_eq!(bnd[7], "SVTYPE=BND;MATEID=bnd1_2;EVENT=bnd1;PSI=0.5");
+        let del = records.iter().find(|fields| fields[2] == "del1").unwrap();
+        assert_eq!(del[6], "PASS");
+        assert_eq!(del[7], "SVTYPE=DEL;END=2000;SR=5");
+        assert_eq!(
+            records
+                .iter()
+                .filter(|fields| fields[7].contains("EVENT=e1"))
+                .count(),
+            2,
+            "inversions are written as breakend pairs"
+        );
+
+        // the records read back as the same edges
+        let edges = read_vcf_edges(vcf.as_bytes())?;
+        assert_eq!(edges.len(), 7);
+        let bnd = edges.iter().find(|edge| edge.id == "bnd1").unwrap();
+        assert_eq!(bnd.sv.to_string(), "chr1,chr2,2200,5000,BND");
+        let del = edges.iter().find(|edge| edge.id == "del1").unwrap();
+        assert_eq!(del.sv.to_string(), "chr1,chr1,1700,2000,DEL");
+        let inv = edges.iter().find(|edge| edge.id == "e1").unwrap();
+        assert_eq!(
+            inv.sv.to_string(),
+            tsg_graph
+                .default_graph()
+                .unwrap()
+                .edge_by_id("e1")
+                .unwrap()
+                .sv
+                .to_string()
+        );
+        assert_eq!(inv.sv.sv_type, "INV");
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_vcf_edges() -> Result<()> {
+        let reader = std::io::BufReader::new(std::fs::File::open("tests/data/sv.vcf")?);
+        let edges = read_vcf_edges(reader)?;
+        assert_eq!(edges.len(), 3, "SNVs and breakend mates are skipped");
+
+        let del = &edges[0];
+        assert_eq!(del.sv.to_string(), "chr1,chr1,1700,2000,DEL");
+        assert_eq!(del.attributes[&BString::from("SR")].to_string(), "SR:i:5");
+        assert_eq!(del.attributes[&BString::from("FILTER")].value, "PASS");
+
+        let bnd = &edges[1];
+        assert_eq!(bnd.sv.to_string(), "chr1,chr2,2200,5000,BND");
+        assert_eq!(bnd.attributes[&BString::from("PSI")].attribute_type, 'f');
+        assert!(!bnd.attributes.contains_key(&BString::from("MATEID")));
+
+        let inv = &edges[2];
+        assert_eq!(inv.id, "sv3");
+        assert_eq!(inv.sv.to_string(), "chr1,chr1,2700,2500,INV");
+        assert_eq!(
+            inv.attributes[&BString::from("IMPRECISE")].to_string(),
+            "IMPRECISE:Z:"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_vcf() -> Result<()> {
+        let mut tsg_graph = TSGraph::from_file("tests/data/test.tsg")?;
+        let reader = std::io::BufReader::new(std::fs::File::open("tests/data/sv.vcf")?);
+        assert_eq!(tsg_graph.import_vcf(reader, None)?, 3);
+
+        let graph = tsg_graph.default_graph().unwrap();
+        assert_eq!(graph.edge_indices().len(), 7);
+        let endpoints = |id: &str| {
+            let idx = graph.edge_indices()[&BString::from(id)];
+            let (source, target) = graph.inner_graph().edge_endpoints(idx).unwrap();
+            (
+                graph.find_node_id_by_idx(source).unwrap().to_string(),
+                graph.find_node_id_by_idx(target).unwrap().to_string(),
+            )
+        };
+        assert_eq!(endpoints("del1"), ("n1".into(), "n2".into()));
+        assert_eq!(endpoints("bnd1"), ("n2".into(), "chr2_5000".into()));
+        assert_eq!(endpoints("sv3"), ("n3".into(), "n3".into()));
+
+        // placeholder nodes survive a round trip through TSG text
+        let mut buf = Vec::new();
+        tsg_graph.to_writer(&mut buf)?;
+        let reparsed = TSGraph::from_reader(buf.as_slice())?;
+        assert!(reparsed.node(DEFAULT_GRAPH_ID, "chr2_5000").is_some());
+        Ok(())
+    }
 }